@@ -0,0 +1,237 @@
+// src/ai_service_proxy/ab.rs
+//! A/B testing between two checkpointed logistic regression versions.
+//! While a test runs, each risk request is routed to one arm by a fixed
+//! traffic split; the arm's decision is remembered per feature vector so
+//! outcomes later submitted by the pool can be credited to the right arm.
+//! A running test, arms and remembered decisions included, survives upgrades.
+
+use crate::types::{AbArmStats, AbTestConfig, AbTestStats};
+use crate::{calibration, learning, LogisticRegressionBrain, FEATURE_COUNT, MODELS};
+use candid::CandidType;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Basis points in 100%
+const BPS: u64 = 10_000;
+/// Scored requests remembered for outcome attribution
+const MAX_TRACKED_DECISIONS: usize = 50_000;
+
+type FeatureKey = [u64; FEATURE_COUNT];
+
+struct Arm {
+    brain: LogisticRegressionBrain,
+    stats: AbArmStats,
+}
+
+struct AbTest {
+    config: AbTestConfig,
+    started_at: u64,
+    arms: [Arm; 2],
+    counter: u64,
+    /// Feature vector -> (arm index, predicted high risk)
+    decisions: HashMap<FeatureKey, (usize, bool)>,
+    decision_order: VecDeque<FeatureKey>,
+}
+
+static AB_TEST: Lazy<Mutex<Option<AbTest>>> = Lazy::new(|| Mutex::new(None));
+
+fn key(x: &[f64; FEATURE_COUNT]) -> FeatureKey {
+    let mut k = [0u64; FEATURE_COUNT];
+    for (slot, v) in k.iter_mut().zip(x.iter()) {
+        *slot = v.to_bits();
+    }
+    k
+}
+
+fn arm(version: u64) -> Result<Arm, String> {
+    let brain = learning::brain_for_version(version)
+        .ok_or(format!("no checkpoint for version {}", version))?;
+    Ok(Arm { brain, stats: AbArmStats { version, ..Default::default() } })
+}
+
+pub fn start(config: AbTestConfig) -> Result<(), String> {
+    if config.candidate_share_bps as u64 > BPS {
+        return Err("candidate_share_bps must be at most 10000".to_string());
+    }
+    if config.control_version == config.candidate_version {
+        return Err("control and candidate versions must differ".to_string());
+    }
+    let arms = [arm(config.control_version)?, arm(config.candidate_version)?];
+    *AB_TEST.lock().unwrap() = Some(AbTest {
+        config,
+        started_at: ic_cdk::api::time(),
+        arms,
+        counter: 0,
+        decisions: HashMap::new(),
+        decision_order: VecDeque::new(),
+    });
+    Ok(())
+}
+
+/// End the test; returns its final stats
+pub fn stop() -> Option<AbTestStats> {
+    let stats = stats();
+    *AB_TEST.lock().unwrap() = None;
+    stats
+}
+
+/// Route a request to an arm: (version, brain, calibrated probability)
+pub fn score(x: &[f64; FEATURE_COUNT]) -> Option<(u64, LogisticRegressionBrain, f64)> {
+    let mut guard = AB_TEST.lock().unwrap();
+    let test = guard.as_mut()?;
+
+    // Multiplying by a prime spreads consecutive requests across the split
+    let slot = test.counter.wrapping_mul(7_919) % BPS;
+    test.counter += 1;
+    let idx = if slot < test.config.candidate_share_bps as u64 { 1 } else { 0 };
+
+    let prob = calibration::apply(
+        &MODELS.lock().unwrap().calibration,
+        test.arms[idx].brain.predict_proba(x),
+    );
+    let high_risk = prob >= crate::HIGH_RISK_THRESHOLD;
+
+    let arm = &mut test.arms[idx];
+    arm.stats.requests += 1;
+    arm.stats.probability_sum += prob;
+    if high_risk {
+        arm.stats.high_risk += 1;
+    }
+
+    let k = key(x);
+    if test.decisions.insert(k, (idx, high_risk)).is_none() {
+        test.decision_order.push_back(k);
+        if test.decision_order.len() > MAX_TRACKED_DECISIONS {
+            if let Some(old) = test.decision_order.pop_front() {
+                test.decisions.remove(&old);
+            }
+        }
+    }
+    Some((arm.stats.version, arm.brain.clone(), prob))
+}
+
+/// Credit a labeled outcome to the arm that scored these features
+pub fn record_outcome(x: &[f64; FEATURE_COUNT], defaulted: bool) {
+    let mut guard = AB_TEST.lock().unwrap();
+    let test = match guard.as_mut() {
+        Some(t) => t,
+        None => return,
+    };
+    let (idx, high_risk) = match test.decisions.get(&key(x)) {
+        Some(d) => *d,
+        None => return,
+    };
+    let stats = &mut test.arms[idx].stats;
+    stats.outcomes += 1;
+    if defaulted {
+        stats.defaults += 1;
+    }
+    if high_risk == defaulted {
+        stats.correct += 1;
+    }
+}
+
+pub fn stats() -> Option<AbTestStats> {
+    let guard = AB_TEST.lock().unwrap();
+    let test = guard.as_ref()?;
+    Some(AbTestStats {
+        config: test.config.clone(),
+        started_at: test.started_at,
+        arms: test.arms.iter().map(|a| a.stats.clone()).collect(),
+    })
+}
+
+#[derive(CandidType, Serialize, Deserialize)]
+struct ArmSnapshot {
+    means: Vec<f64>,
+    stds: Vec<f64>,
+    weights: Vec<f64>,
+    intercept: f64,
+    stats: AbArmStats,
+}
+
+/// Running test carried across upgrades
+#[derive(CandidType, Serialize, Deserialize)]
+pub struct Snapshot {
+    config: AbTestConfig,
+    started_at: u64,
+    arms: Vec<ArmSnapshot>,
+    counter: u64,
+    /// (feature bits, arm index, predicted high risk), oldest first
+    decisions: Vec<(Vec<u64>, u8, bool)>,
+}
+
+impl Snapshot {
+    /// Drop the remembered decisions to fit the snapshot in stable memory;
+    /// outcomes for requests scored before the upgrade are then not credited
+    pub fn forget_decisions(&mut self) {
+        self.decisions.clear();
+    }
+}
+
+pub fn snapshot() -> Option<Snapshot> {
+    let guard = AB_TEST.lock().unwrap();
+    let test = guard.as_ref()?;
+    Some(Snapshot {
+        config: test.config.clone(),
+        started_at: test.started_at,
+        arms: test
+            .arms
+            .iter()
+            .map(|a| ArmSnapshot {
+                means: a.brain.means.to_vec(),
+                stds: a.brain.stds.to_vec(),
+                weights: a.brain.weights.to_vec(),
+                intercept: a.brain.intercept,
+                stats: a.stats.clone(),
+            })
+            .collect(),
+        counter: test.counter,
+        decisions: test
+            .decision_order
+            .iter()
+            .filter_map(|k| {
+                let (idx, high_risk) = test.decisions.get(k)?;
+                Some((k.to_vec(), *idx as u8, *high_risk))
+            })
+            .collect(),
+    })
+}
+
+pub fn restore(snapshot: Snapshot) {
+    let arms: Option<Vec<Arm>> = snapshot
+        .arms
+        .into_iter()
+        .map(|a| {
+            let brain = LogisticRegressionBrain {
+                means: a.means.try_into().ok()?,
+                stds: a.stds.try_into().ok()?,
+                weights: a.weights.try_into().ok()?,
+                intercept: a.intercept,
+            };
+            Some(Arm { brain, stats: a.stats })
+        })
+        .collect();
+    let Some(Ok(arms)) = arms.map(<[Arm; 2]>::try_from) else {
+        ic_cdk::println!("A/B test not restored: unreadable arms");
+        return;
+    };
+    let mut decisions = HashMap::new();
+    let mut decision_order = VecDeque::new();
+    for (k, idx, high_risk) in snapshot.decisions {
+        let Ok(k) = FeatureKey::try_from(k) else { continue };
+        if idx < 2 && decisions.insert(k, (idx as usize, high_risk)).is_none() {
+            decision_order.push_back(k);
+        }
+    }
+    *AB_TEST.lock().unwrap() = Some(AbTest {
+        config: snapshot.config,
+        started_at: snapshot.started_at,
+        arms,
+        counter: snapshot.counter,
+        decisions,
+        decision_order,
+    });
+}
@@ -0,0 +1,143 @@
+// src/ai_service_proxy/advice.rs
+//! Advice templates keyed by risk tier and language. `{prob}` is replaced
+//! with the probability and `{driver}` with the top risk driver (or the
+//! language's phrase for the position as a whole). Controllers can override
+//! any template or add languages; unknown languages fall back to English.
+
+use crate::types::RiskTier;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+pub const DEFAULT_LANG: &str = "en";
+
+struct BuiltIn {
+    lang: &'static str,
+    /// Low, Moderate, High, Critical
+    templates: [&'static str; 4],
+    /// Stands in for `{driver}` when no feature stands out
+    no_driver: &'static str,
+}
+
+const BUILT_IN: [BuiltIn; 4] = [
+    BuiltIn {
+        lang: "en",
+        templates: [
+            "Safe to borrow",
+            "Safe to borrow (prob {prob}); keep an eye on {driver}",
+            "High risk (prob {prob}), mainly driven by {driver}; consider increasing collateral",
+            "Very high risk (prob {prob}), mainly driven by {driver}; add collateral or repay before borrowing more",
+        ],
+        no_driver: "your overall position",
+    },
+    BuiltIn {
+        lang: "es",
+        templates: [
+            "Puede pedir prestado con seguridad",
+            "Puede pedir prestado (prob {prob}); vigile {driver}",
+            "Riesgo alto (prob {prob}), debido sobre todo a {driver}; considere aumentar la garantía",
+            "Riesgo muy alto (prob {prob}), debido sobre todo a {driver}; añada garantía o pague antes de pedir más",
+        ],
+        no_driver: "su posición general",
+    },
+    BuiltIn {
+        lang: "fr",
+        templates: [
+            "Emprunt sans risque",
+            "Emprunt possible (prob {prob}) ; surveillez {driver}",
+            "Risque élevé (prob {prob}), principalement dû à {driver} ; envisagez d'augmenter la garantie",
+            "Risque très élevé (prob {prob}), principalement dû à {driver} ; ajoutez une garantie ou remboursez avant d'emprunter davantage",
+        ],
+        no_driver: "votre position globale",
+    },
+    BuiltIn {
+        lang: "de",
+        templates: [
+            "Kreditaufnahme unbedenklich",
+            "Kreditaufnahme möglich (Wahrsch. {prob}); behalten Sie {driver} im Blick",
+            "Hohes Risiko (Wahrsch. {prob}), vor allem durch {driver}; erwägen Sie mehr Sicherheiten",
+            "Sehr hohes Risiko (Wahrsch. {prob}), vor allem durch {driver}; hinterlegen Sie Sicherheiten oder tilgen Sie, bevor Sie mehr leihen",
+        ],
+        no_driver: "Ihre Gesamtposition",
+    },
+];
+
+/// Controller overrides: (lang, tier) -> template
+static OVERRIDES: Lazy<Mutex<HashMap<(String, RiskTier), String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Probability bands; Low and Moderate are approved, High and Critical are not
+pub fn tier(prob: f64) -> RiskTier {
+    if prob < 0.25 {
+        RiskTier::Low
+    } else if prob < crate::HIGH_RISK_THRESHOLD {
+        RiskTier::Moderate
+    } else if prob < 0.75 {
+        RiskTier::High
+    } else {
+        RiskTier::Critical
+    }
+}
+
+fn tier_index(tier: RiskTier) -> usize {
+    match tier {
+        RiskTier::Low => 0,
+        RiskTier::Moderate => 1,
+        RiskTier::High => 2,
+        RiskTier::Critical => 3,
+    }
+}
+
+/// Normalize a requested language ("pt-BR" -> "pt"), defaulting to English
+pub fn normalize_lang(lang: Option<&str>) -> String {
+    lang.and_then(|l| l.split(['-', '_']).next())
+        .map(|l| l.trim().to_ascii_lowercase())
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| DEFAULT_LANG.to_string())
+}
+
+pub fn render(tier: RiskTier, lang: &str, prob: f64, driver: Option<&str>) -> String {
+    let built_in = BUILT_IN.iter().find(|b| b.lang == lang);
+    let override_template = OVERRIDES.lock().unwrap().get(&(lang.to_string(), tier)).cloned();
+    let (template, no_driver) = match (override_template, built_in) {
+        (Some(t), b) => (t, b.unwrap_or(&BUILT_IN[0]).no_driver),
+        (None, Some(b)) => (b.templates[tier_index(tier)].to_string(), b.no_driver),
+        (None, None) => (BUILT_IN[0].templates[tier_index(tier)].to_string(), BUILT_IN[0].no_driver),
+    };
+    template
+        .replace("{prob}", &format!("{:.2}", prob))
+        .replace("{driver}", driver.unwrap_or(no_driver))
+}
+
+pub fn set_template(lang: &str, tier: RiskTier, template: String) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("template must not be empty".to_string());
+    }
+    OVERRIDES.lock().unwrap().insert((normalize_lang(Some(lang)), tier), template);
+    Ok(())
+}
+
+/// Languages with built-in or overridden templates
+pub fn languages() -> Vec<String> {
+    let mut langs: Vec<String> = BUILT_IN.iter().map(|b| b.lang.to_string()).collect();
+    for (lang, _) in OVERRIDES.lock().unwrap().keys() {
+        if !langs.contains(lang) {
+            langs.push(lang.clone());
+        }
+    }
+    langs
+}
+
+pub fn snapshot() -> Vec<(String, RiskTier, String)> {
+    OVERRIDES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|((lang, tier), t)| (lang.clone(), *tier, t.clone()))
+        .collect()
+}
+
+pub fn restore(overrides: Vec<(String, RiskTier, String)>) {
+    *OVERRIDES.lock().unwrap() =
+        overrides.into_iter().map(|(lang, tier, t)| ((lang, tier), t)).collect();
+}
@@ -0,0 +1,59 @@
+// src/ai_service_proxy/backtest.rs
+//! Offline evaluation of a checkpointed model against history: labeled rows
+//! from the outcome dataset give a confusion matrix, and requests from the
+//! scoring log show how many live decisions the candidate would have changed.
+
+use crate::types::BacktestReport;
+use crate::{calibration, dataset, learning, HIGH_RISK_THRESHOLD, MODELS, SCORING_LOG};
+
+fn ratio(num: u64, den: u64) -> f64 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f64 / den as f64
+    }
+}
+
+pub fn run(model_version: u64, from_ts: u64, to_ts: u64) -> Result<BacktestReport, String> {
+    if from_ts > to_ts {
+        return Err("from_ts must not be after to_ts".to_string());
+    }
+    let brain = learning::brain_for_version(model_version)
+        .ok_or(format!("no checkpoint for version {}", model_version))?;
+    let cal = MODELS.lock().unwrap().calibration.clone();
+    let in_range = |ts: u64| ts >= from_ts && ts <= to_ts;
+
+    let mut report = BacktestReport { model_version, ..Default::default() };
+
+    dataset::for_each(|record| {
+        if !in_range(record.timestamp) {
+            return;
+        }
+        let mut x = [0.0; crate::FEATURE_COUNT];
+        x.copy_from_slice(&record.features);
+        let high_risk = calibration::apply(&cal, brain.predict_proba(&x)) >= HIGH_RISK_THRESHOLD;
+        match (high_risk, record.defaulted) {
+            (true, true) => report.true_positives += 1,
+            (true, false) => report.false_positives += 1,
+            (false, false) => report.true_negatives += 1,
+            (false, true) => report.false_negatives += 1,
+        }
+        report.labeled_samples += 1;
+    });
+
+    for entry in SCORING_LOG.lock().unwrap().entries.iter().filter(|e| in_range(e.timestamp)) {
+        let mut x = [0.0; crate::FEATURE_COUNT];
+        x.copy_from_slice(&entry.features);
+        let high_risk = calibration::apply(&cal, brain.predict_proba(&x)) >= HIGH_RISK_THRESHOLD;
+        report.replayed_decisions += 1;
+        if high_risk != (entry.risk_score == 1) {
+            report.changed_decisions += 1;
+        }
+    }
+
+    let correct = report.true_positives + report.true_negatives;
+    report.accuracy = ratio(correct, report.labeled_samples);
+    report.precision = ratio(report.true_positives, report.true_positives + report.false_positives);
+    report.recall = ratio(report.true_positives, report.true_positives + report.false_negatives);
+    Ok(report)
+}
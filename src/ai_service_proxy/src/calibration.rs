@@ -0,0 +1,65 @@
+// src/ai_service_proxy/calibration.rs
+//! Post-hoc probability calibration applied to whichever model is active, so
+//! the probabilities behind advice and LTV decisions match observed default
+//! rates rather than the raw model output.
+
+use crate::types::Calibration;
+
+/// Raw probabilities are clamped away from 0 and 1 before taking the logit
+const LOGIT_EPS: f64 = 1e-12;
+
+pub fn validate(cal: &Calibration) -> Result<(), String> {
+    match cal {
+        Calibration::None => Ok(()),
+        Calibration::Platt { a, b } => {
+            if a.is_finite() && b.is_finite() {
+                Ok(())
+            } else {
+                Err("Platt parameters must be finite".to_string())
+            }
+        }
+        Calibration::Isotonic { thresholds, values } => {
+            if thresholds.len() < 2 || thresholds.len() != values.len() {
+                return Err("isotonic calibration needs at least 2 matching thresholds and values".to_string());
+            }
+            let in_unit = |v: &f64| (0.0..=1.0).contains(v);
+            if !thresholds.iter().all(in_unit) || !values.iter().all(in_unit) {
+                return Err("isotonic thresholds and values must lie in [0, 1]".to_string());
+            }
+            if thresholds.windows(2).any(|w| w[0] >= w[1]) {
+                return Err("isotonic thresholds must be strictly increasing".to_string());
+            }
+            if values.windows(2).any(|w| w[0] > w[1]) {
+                return Err("isotonic values must be non-decreasing".to_string());
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Map a raw model probability through the calibration
+pub fn apply(cal: &Calibration, raw: f64) -> f64 {
+    match cal {
+        Calibration::None => raw,
+        // sklearn convention: p = 1 / (1 + exp(a * f + b)) on the raw logit f
+        Calibration::Platt { a, b } => {
+            let p = raw.clamp(LOGIT_EPS, 1.0 - LOGIT_EPS);
+            let logit = (p / (1.0 - p)).ln();
+            1.0 / (1.0 + (a * logit + b).exp())
+        }
+        // Linear interpolation between bins, clamped at both ends
+        Calibration::Isotonic { thresholds, values } => {
+            let last = thresholds.len() - 1;
+            if raw <= thresholds[0] {
+                return values[0];
+            }
+            if raw >= thresholds[last] {
+                return values[last];
+            }
+            let i = thresholds.partition_point(|t| *t <= raw);
+            let (x0, x1) = (thresholds[i - 1], thresholds[i]);
+            let (y0, y1) = (values[i - 1], values[i]);
+            y0 + (y1 - y0) * (raw - x0) / (x1 - x0)
+        }
+    }
+}
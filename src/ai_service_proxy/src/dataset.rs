@@ -0,0 +1,87 @@
+// src/ai_service_proxy/dataset.rs
+//! Labeled loan outcomes stored as fixed-size rows in the OUTCOMES region of
+//! stable memory, for export to the off-chain training pipeline.
+//!
+//! Row layout (56 bytes, little endian): 5 x f64 features, u64 timestamp,
+//! u8 label (1 = defaulted), 7 bytes padding.
+
+use crate::memory::{self, OUTCOMES_START};
+use crate::types::{OutcomeChunk, OutcomeRecord};
+use crate::FEATURE_COUNT;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+const RECORD_SIZE: u64 = 56;
+/// Largest number of rows returned by one export call
+pub const MAX_EXPORT_CHUNK: u64 = 1_000;
+
+/// Number of rows written so far
+pub static OUTCOME_COUNT: Lazy<Mutex<u64>> = Lazy::new(|| Mutex::new(0));
+
+fn encode(features: &[f64; FEATURE_COUNT], defaulted: bool, timestamp: u64) -> [u8; RECORD_SIZE as usize] {
+    let mut row = [0u8; RECORD_SIZE as usize];
+    for (i, f) in features.iter().enumerate() {
+        row[i * 8..i * 8 + 8].copy_from_slice(&f.to_le_bytes());
+    }
+    row[40..48].copy_from_slice(&timestamp.to_le_bytes());
+    row[48] = defaulted as u8;
+    row
+}
+
+fn decode(row: &[u8]) -> OutcomeRecord {
+    let word = |i: usize| -> [u8; 8] { row[i * 8..i * 8 + 8].try_into().unwrap() };
+    OutcomeRecord {
+        features: (0..FEATURE_COUNT).map(|i| f64::from_le_bytes(word(i))).collect(),
+        timestamp: u64::from_le_bytes(word(5)),
+        defaulted: row[48] == 1,
+    }
+}
+
+/// Append one labeled outcome; returns its row index
+pub fn append(features: &[f64; FEATURE_COUNT], defaulted: bool, timestamp: u64) -> Result<u64, String> {
+    let mut count = OUTCOME_COUNT.lock().unwrap();
+    let index = *count;
+    memory::write(OUTCOMES_START + index * RECORD_SIZE, &encode(features, defaulted, timestamp))?;
+    *count += 1;
+    Ok(index)
+}
+
+/// Read up to `limit` rows starting at row `start`
+pub fn export(start: u64, limit: u64) -> OutcomeChunk {
+    let total = *OUTCOME_COUNT.lock().unwrap();
+    let start = start.min(total);
+    let end = (start + limit.min(MAX_EXPORT_CHUNK)).min(total);
+    let bytes = memory::read(OUTCOMES_START + start * RECORD_SIZE, (end - start) * RECORD_SIZE);
+    OutcomeChunk {
+        records: bytes.chunks_exact(RECORD_SIZE as usize).map(decode).collect(),
+        next_index: if end < total { Some(end) } else { None },
+        total,
+    }
+}
+
+/// Visit every stored row in order, reading stable memory a chunk at a time
+pub fn for_each(mut f: impl FnMut(&OutcomeRecord)) {
+    let mut next = Some(0);
+    while let Some(start) = next {
+        let chunk = export(start, MAX_EXPORT_CHUNK);
+        chunk.records.iter().for_each(&mut f);
+        next = chunk.next_index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_round_trip() {
+        let features = [0.25, -1.5, 3e9, 0.0, f64::MAX];
+        let row = encode(&features, true, 1_700_000_000_000_000_000);
+        assert_eq!(row[49..], [0u8; 7]);
+        let record = decode(&row);
+        assert_eq!(record.features, features.to_vec());
+        assert_eq!(record.timestamp, 1_700_000_000_000_000_000);
+        assert!(record.defaulted);
+        assert!(!decode(&encode(&features, false, 0)).defaulted);
+    }
+}
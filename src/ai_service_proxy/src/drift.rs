@@ -0,0 +1,171 @@
+// src/ai_service_proxy/drift.rs
+//! Feature drift detection: exponentially weighted means and variances of
+//! live features are compared with the training-time `means`/`stds` of the
+//! brain. A feature drifts when its mean moves too many training standard
+//! deviations or its spread changes by too large a factor.
+
+use crate::types::{DriftAlert, DriftReport, FeatureDrift, ScalingStats};
+use crate::{FEATURE_COUNT, FEATURE_NAMES, MODELS};
+use candid::CandidType;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Smoothing factor, roughly a 1000-request window
+const EWMA_ALPHA: f64 = 2.0 / 1001.0;
+/// Requests observed before drift is reported
+const MIN_SAMPLES: u64 = 100;
+/// Mean shift, in training standard deviations, that counts as drift
+const MEAN_SHIFT_THRESHOLD: f64 = 0.5;
+/// Live/training std ratio outside [1/x, x] counts as drift
+const STD_RATIO_THRESHOLD: f64 = 2.0;
+/// Alerts retained for the alert query
+const MAX_ALERTS: usize = 500;
+
+#[derive(Default)]
+struct DriftState {
+    samples: u64,
+    mean: [f64; FEATURE_COUNT],
+    var: [f64; FEATURE_COUNT],
+    drifting: [bool; FEATURE_COUNT],
+    alerts: VecDeque<DriftAlert>,
+}
+
+static DRIFT: Lazy<Mutex<DriftState>> = Lazy::new(|| Mutex::new(DriftState::default()));
+
+/// Compare live stats with the training stats, one entry per feature
+fn compare(state: &DriftState) -> Vec<FeatureDrift> {
+    let models = MODELS.lock().unwrap();
+    (0..FEATURE_COUNT)
+        .map(|i| {
+            let training_mean = models.brain.means[i];
+            let training_std = models.brain.stds[i];
+            let live_std = state.var[i].sqrt();
+            let mean_shift = (state.mean[i] - training_mean).abs() / training_std;
+            let std_ratio = live_std / training_std;
+            let drifting = state.samples >= MIN_SAMPLES
+                && (mean_shift > MEAN_SHIFT_THRESHOLD
+                    || !(1.0 / STD_RATIO_THRESHOLD..=STD_RATIO_THRESHOLD).contains(&std_ratio));
+            FeatureDrift {
+                feature: FEATURE_NAMES[i].to_string(),
+                training_mean,
+                training_std,
+                live_mean: state.mean[i],
+                live_std,
+                mean_shift,
+                std_ratio,
+                drifting,
+            }
+        })
+        .collect()
+}
+
+/// Fold one scored request into the rolling stats, alerting on new drift
+pub fn observe(x: &[f64; FEATURE_COUNT]) {
+    let mut state = DRIFT.lock().unwrap();
+    if state.samples == 0 {
+        state.mean = *x;
+    } else {
+        for (i, xi) in x.iter().enumerate() {
+            let diff = xi - state.mean[i];
+            let incr = EWMA_ALPHA * diff;
+            state.mean[i] += incr;
+            state.var[i] = (1.0 - EWMA_ALPHA) * (state.var[i] + diff * incr);
+        }
+    }
+    state.samples += 1;
+    if state.samples < MIN_SAMPLES {
+        return;
+    }
+
+    let now = ic_cdk::api::time();
+    for (i, drift) in compare(&state).into_iter().enumerate() {
+        // Alert only when a feature starts drifting, not on every request
+        if drift.drifting && !state.drifting[i] {
+            ic_cdk::println!(
+                "Feature drift: {} mean shift {:.2} std ratio {:.2}",
+                drift.feature, drift.mean_shift, drift.std_ratio
+            );
+            if state.alerts.len() >= MAX_ALERTS {
+                state.alerts.pop_front();
+            }
+            state.alerts.push_back(DriftAlert {
+                timestamp: now,
+                feature: drift.feature,
+                mean_shift: drift.mean_shift,
+                std_ratio: drift.std_ratio,
+            });
+        }
+        state.drifting[i] = drift.drifting;
+    }
+}
+
+pub fn report() -> DriftReport {
+    let state = DRIFT.lock().unwrap();
+    let features = compare(&state);
+    DriftReport {
+        samples: state.samples,
+        drifting: features.iter().any(|f| f.drifting),
+        features,
+    }
+}
+
+pub fn alerts() -> Vec<DriftAlert> {
+    DRIFT.lock().unwrap().alerts.iter().cloned().collect()
+}
+
+/// Live means and standard deviations, once enough traffic has been seen
+pub fn live_scaling() -> Result<ScalingStats, String> {
+    let state = DRIFT.lock().unwrap();
+    if state.samples < MIN_SAMPLES {
+        return Err(format!("only {} of {} samples observed", state.samples, MIN_SAMPLES));
+    }
+    Ok(ScalingStats {
+        means: state.mean.to_vec(),
+        stds: state.var.iter().map(|v| v.sqrt()).collect(),
+    })
+}
+
+/// Start the rolling stats over, e.g. after retraining on recent traffic
+pub fn reset() {
+    *DRIFT.lock().unwrap() = DriftState::default();
+}
+
+/// Rolling statistics carried across upgrades
+#[derive(CandidType, Serialize, Deserialize)]
+pub struct Snapshot {
+    samples: u64,
+    mean: Vec<f64>,
+    var: Vec<f64>,
+    drifting: Vec<bool>,
+    alerts: Vec<DriftAlert>,
+}
+
+pub fn snapshot() -> Snapshot {
+    let state = DRIFT.lock().unwrap();
+    Snapshot {
+        samples: state.samples,
+        mean: state.mean.to_vec(),
+        var: state.var.to_vec(),
+        drifting: state.drifting.to_vec(),
+        alerts: state.alerts.iter().cloned().collect(),
+    }
+}
+
+pub fn restore(snapshot: Snapshot) {
+    let (Ok(mean), Ok(var), Ok(drifting)) = (
+        snapshot.mean.try_into(),
+        snapshot.var.try_into(),
+        snapshot.drifting.try_into(),
+    ) else {
+        return;
+    };
+    *DRIFT.lock().unwrap() = DriftState {
+        samples: snapshot.samples,
+        mean,
+        var,
+        drifting,
+        alerts: snapshot.alerts.into(),
+    };
+}
@@ -0,0 +1,108 @@
+// src/ai_service_proxy/fixed.rs
+//! Deterministic fixed-point arithmetic for inference: values are `i128`
+//! scaled by 1e12. Non-finite inputs are mapped to 0 or saturated, and every
+//! operation saturates instead of overflowing, so identical inputs give
+//! bit-identical scores on every replica.
+
+/// Fixed-point value scaled by `ONE`
+pub type Fixed = i128;
+
+pub const ONE: Fixed = 1_000_000_000_000;
+/// ln(2) scaled by `ONE`
+const LN_2: Fixed = 693_147_180_560;
+/// Standardized values beyond this many stds are clamped
+const MAX_STANDARDIZED: Fixed = 1_000_000 * ONE;
+/// |z| beyond this saturates the sigmoid to within 1e-26
+const MAX_LOGIT: Fixed = 60 * ONE;
+const SERIES_TERMS: i128 = 30;
+
+/// Convert a float, mapping NaN to 0 and saturating infinities
+pub fn from_f64(v: f64) -> Fixed {
+    if v.is_nan() {
+        0
+    } else {
+        // `as` saturates out-of-range values
+        (v * ONE as f64) as Fixed
+    }
+}
+
+pub fn to_f64(v: Fixed) -> f64 {
+    v as f64 / ONE as f64
+}
+
+pub fn mul(a: Fixed, b: Fixed) -> Fixed {
+    a.saturating_mul(b) / ONE
+}
+
+pub fn div(a: Fixed, b: Fixed) -> Fixed {
+    if b == 0 {
+        return 0;
+    }
+    a.saturating_mul(ONE) / b
+}
+
+/// (x - mean) / std, clamped to +-1e6 standard deviations
+pub fn standardize(x: Fixed, mean: Fixed, std: Fixed) -> Fixed {
+    if std <= 0 {
+        return 0;
+    }
+    // Divide via the inverse so large USD values do not overflow `a * ONE`
+    let inv_std = div(ONE, std);
+    mul(x.saturating_sub(mean), inv_std).clamp(-MAX_STANDARDIZED, MAX_STANDARDIZED)
+}
+
+/// e^z for z <= 0, via e^z = e^r / 2^k with r in (-ln 2, 0]
+fn exp_non_positive(z: Fixed) -> Fixed {
+    let k = (-z) / LN_2;
+    if k >= 100 {
+        return 0;
+    }
+    let r = z + k * LN_2;
+    let mut term = ONE;
+    let mut sum = ONE;
+    for n in 1..SERIES_TERMS {
+        term = mul(term, r) / n;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+    sum >> k
+}
+
+/// Logistic function of a fixed-point logit
+pub fn sigmoid(z: Fixed) -> Fixed {
+    let z = z.clamp(-MAX_LOGIT, MAX_LOGIT);
+    let e = exp_non_positive(-z.abs());
+    if z >= 0 {
+        div(ONE, ONE + e)
+    } else {
+        div(e, ONE + e)
+    }
+}
+
+/// ln(1 + n) for a non-negative integer
+pub fn ln_1p_int(n: u128) -> Fixed {
+    let x = n.saturating_add(1);
+    // x = m * 2^k with m in [1, 2)
+    let k = 127 - x.leading_zeros() as i128;
+    let m: Fixed = if k > 40 {
+        (((x >> (k - 40)) as i128) * ONE) >> 40
+    } else {
+        ((x as i128) * ONE) >> k
+    };
+    // ln(m) = 2 atanh((m - 1) / (m + 1)), converging fast for m in [1, 2)
+    let y = div(m - ONE, m + ONE);
+    let y2 = mul(y, y);
+    let mut power = y;
+    let mut sum = 0;
+    for n in 0..SERIES_TERMS {
+        let term = power / (2 * n + 1);
+        if term == 0 {
+            break;
+        }
+        sum += term;
+        power = mul(power, y2);
+    }
+    k * LN_2 + 2 * sum
+}
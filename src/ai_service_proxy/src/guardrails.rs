@@ -0,0 +1,93 @@
+// src/ai_service_proxy/guardrails.rs
+//! Hard, non-ML rules over the model's decision. Rules are checked against
+//! the validated features; a matching rule overrides the model's decision and
+//! is named in the response. Deny rules take precedence over approve rules.
+
+use crate::types::{DecisionSource, GuardrailConfig, RiskTier};
+use crate::FEATURE_COUNT;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+const COLLATERAL: usize = 1;
+const BORROWED: usize = 2;
+const CREDIT_SCORE: usize = 4;
+
+static GUARDRAILS: Lazy<Mutex<GuardrailConfig>> =
+    Lazy::new(|| Mutex::new(GuardrailConfig::default()));
+
+/// Outcome forced by a matching rule
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Approve,
+    Deny,
+}
+
+pub fn config() -> GuardrailConfig {
+    GUARDRAILS.lock().unwrap().clone()
+}
+
+pub fn set_config(config: GuardrailConfig) -> Result<(), String> {
+    if let Some(ratio) = config.max_borrow_ratio {
+        if !(ratio > 0.0 && ratio.is_finite()) {
+            return Err("max_borrow_ratio must be positive and finite".to_string());
+        }
+    }
+    *GUARDRAILS.lock().unwrap() = config;
+    Ok(())
+}
+
+/// First rule that matches, deny rules first
+pub fn check(x: &[f64; FEATURE_COUNT]) -> Option<(Verdict, &'static str)> {
+    let config = GUARDRAILS.lock().unwrap();
+    if !config.enabled {
+        return None;
+    }
+    if let Some(ratio) = config.max_borrow_ratio {
+        if x[BORROWED] > ratio * x[COLLATERAL] {
+            return Some((Verdict::Deny, "max_borrow_ratio"));
+        }
+    }
+    if let Some(min) = config.min_credit_score {
+        if x[CREDIT_SCORE] < min as f64 {
+            return Some((Verdict::Deny, "min_credit_score"));
+        }
+    }
+    if config.approve_zero_debt && x[BORROWED] == 0.0 {
+        return Some((Verdict::Approve, "approve_zero_debt"));
+    }
+    None
+}
+
+/// Probability as seen by rule-aware consumers: 1 when denied, 0 when approved
+pub fn bound(x: &[f64; FEATURE_COUNT], prob: f64) -> f64 {
+    match check(x) {
+        Some((Verdict::Deny, _)) => 1.0,
+        Some((Verdict::Approve, _)) => 0.0,
+        None => prob,
+    }
+}
+
+/// Final risk score, tier and decision source for a model probability
+pub fn decide(
+    x: &[f64; FEATURE_COUNT],
+    pred: u8,
+    tier: RiskTier,
+) -> (u8, RiskTier, DecisionSource) {
+    match check(x) {
+        Some((Verdict::Deny, rule)) => {
+            let tier = match tier {
+                RiskTier::Low | RiskTier::Moderate => RiskTier::High,
+                other => other,
+            };
+            (1, tier, DecisionSource::Guardrail(rule.to_string()))
+        }
+        Some((Verdict::Approve, rule)) => {
+            let tier = match tier {
+                RiskTier::High | RiskTier::Critical => RiskTier::Moderate,
+                other => other,
+            };
+            (0, tier, DecisionSource::Guardrail(rule.to_string()))
+        }
+        None => (pred, tier, DecisionSource::Model),
+    }
+}
@@ -0,0 +1,49 @@
+// src/ai_service_proxy/history.rs
+//! Per-account score history, so callers can chart how an account's risk
+//! evolved instead of only seeing the latest advice.
+
+use crate::types::RiskHistoryEntry;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Entries kept per account, oldest dropped first
+const MAX_ENTRIES_PER_ACCOUNT: usize = 500;
+/// Longest accepted account identifier
+pub const MAX_ACCOUNT_LEN: usize = 128;
+
+static HISTORY: Lazy<Mutex<HashMap<String, VecDeque<RiskHistoryEntry>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn record(account: &str, entry: RiskHistoryEntry) {
+    let mut history = HISTORY.lock().unwrap();
+    let entries = history.entry(account.to_string()).or_default();
+    if entries.len() >= MAX_ENTRIES_PER_ACCOUNT {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
+
+/// Most recent `limit` entries, newest first
+pub fn get(account: &str, limit: u64) -> Vec<RiskHistoryEntry> {
+    HISTORY
+        .lock()
+        .unwrap()
+        .get(account)
+        .map(|e| e.iter().rev().take(limit as usize).cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn snapshot() -> Vec<(String, Vec<RiskHistoryEntry>)> {
+    HISTORY
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(account, entries)| (account.clone(), entries.iter().cloned().collect()))
+        .collect()
+}
+
+pub fn restore(history: Vec<(String, Vec<RiskHistoryEntry>)>) {
+    *HISTORY.lock().unwrap() =
+        history.into_iter().map(|(account, entries)| (account, entries.into())).collect();
+}
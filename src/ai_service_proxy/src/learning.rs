@@ -0,0 +1,266 @@
+// src/ai_service_proxy/learning.rs
+//! Online learning: labeled outcomes submitted by the pool are batched and
+//! folded into the logistic regression weights with plain SGD on log loss.
+//! Every batch produces a versioned checkpoint that can be rolled back to.
+//! Scaling statistics can be replaced on their own, which also bumps the version.
+
+use crate::types::{LearningConfig, LearningStatus, ModelCheckpoint, ScalingStats};
+use crate::{LogisticRegressionBrain, DEFAULT_BRAIN, FEATURE_COUNT, MODELS};
+use candid::CandidType;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How often the timer checks whether an automatic batch should run
+pub const TRAINING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Pending outcomes kept before new submissions drop the oldest
+const MAX_PENDING_OUTCOMES: usize = 50_000;
+/// Checkpoints kept for rollback
+const MAX_CHECKPOINTS: usize = 50;
+
+struct LearningState {
+    pending: Vec<([f64; FEATURE_COUNT], bool)>,
+    config: LearningConfig,
+    version: u64,
+    checkpoints: Vec<ModelCheckpoint>,
+}
+
+impl Default for LearningState {
+    fn default() -> Self {
+        LearningState {
+            pending: vec![],
+            config: LearningConfig {
+                learning_rate: 0.01,
+                max_abs_weight: 10.0,
+                min_batch_size: 100,
+                auto_train: false,
+            },
+            version: 1,
+            // Version 1 is the model baked in from training
+            checkpoints: vec![checkpoint(&DEFAULT_BRAIN, 1, 0)],
+        }
+    }
+}
+
+static LEARNING: Lazy<Mutex<LearningState>> = Lazy::new(|| Mutex::new(LearningState::default()));
+
+fn checkpoint(brain: &LogisticRegressionBrain, version: u64, samples: u64) -> ModelCheckpoint {
+    ModelCheckpoint {
+        version,
+        weights: brain.weights.to_vec(),
+        intercept: brain.intercept,
+        means: brain.means.to_vec(),
+        stds: brain.stds.to_vec(),
+        timestamp: ic_cdk::api::time(),
+        samples,
+    }
+}
+
+pub fn submit(features: [f64; FEATURE_COUNT], defaulted: bool) {
+    let mut state = LEARNING.lock().unwrap();
+    if state.pending.len() >= MAX_PENDING_OUTCOMES {
+        state.pending.remove(0);
+    }
+    state.pending.push((features, defaulted));
+}
+
+/// One SGD pass over the batch; label 1 = defaulted (the high risk class)
+fn sgd_epoch(
+    brain: &mut LogisticRegressionBrain,
+    batch: &[([f64; FEATURE_COUNT], bool)],
+    config: &LearningConfig,
+) {
+    let bound = config.max_abs_weight;
+    for (x, defaulted) in batch {
+        let scaled = brain.scale(x);
+        let y = if *defaulted { 1.0 } else { 0.0 };
+        let err = brain.predict_proba(x) - y;
+        for (w, xi) in brain.weights.iter_mut().zip(scaled.iter()) {
+            *w = (*w - config.learning_rate * err * xi).clamp(-bound, bound);
+        }
+        brain.intercept = (brain.intercept - config.learning_rate * err).clamp(-bound, bound);
+    }
+}
+
+/// Train on every pending outcome and checkpoint the result
+pub fn train() -> Result<u64, String> {
+    let mut state = LEARNING.lock().unwrap();
+    if state.pending.is_empty() {
+        return Err("no pending outcomes".to_string());
+    }
+    let batch = std::mem::take(&mut state.pending);
+
+    let mut models = MODELS.lock().unwrap();
+    let mut brain = models.brain.clone();
+    sgd_epoch(&mut brain, &batch, &state.config);
+    if brain.weights.iter().any(|w| !w.is_finite()) || !brain.intercept.is_finite() {
+        // Keep the outcomes for a retry, e.g. after lowering the learning rate
+        state.pending = batch;
+        return Err("training diverged, weights left unchanged".to_string());
+    }
+    models.brain = brain;
+    models.activated_at = ic_cdk::api::time();
+
+    state.version += 1;
+    let version = state.version;
+    let cp = checkpoint(&models.brain, version, batch.len() as u64);
+    state.checkpoints.push(cp);
+    if state.checkpoints.len() > MAX_CHECKPOINTS {
+        state.checkpoints.remove(0);
+    }
+    ic_cdk::println!("Online learning: model v{} trained on {} outcomes", version, batch.len());
+    Ok(version)
+}
+
+/// Timer entry point: train only when enabled and the batch is large enough
+pub fn auto_train() {
+    let ready = {
+        let state = LEARNING.lock().unwrap();
+        state.config.auto_train && state.pending.len() as u64 >= state.config.min_batch_size
+    };
+    if ready {
+        if let Err(err) = train() {
+            ic_cdk::println!("Automatic training skipped: {}", err);
+        }
+    }
+}
+
+pub fn set_config(config: LearningConfig) -> Result<(), String> {
+    if !(config.learning_rate > 0.0 && config.learning_rate <= 1.0) {
+        return Err("learning_rate must be in (0, 1]".to_string());
+    }
+    if !(config.max_abs_weight > 0.0 && config.max_abs_weight.is_finite()) {
+        return Err("max_abs_weight must be positive and finite".to_string());
+    }
+    LEARNING.lock().unwrap().config = config;
+    Ok(())
+}
+
+/// Restore checkpointed weights; the rollback itself becomes a new version
+pub fn rollback(version: u64) -> Result<(), String> {
+    let mut state = LEARNING.lock().unwrap();
+    let cp = state
+        .checkpoints
+        .iter()
+        .find(|c| c.version == version)
+        .cloned()
+        .ok_or(format!("no checkpoint for version {}", version))?;
+
+    let mut models = MODELS.lock().unwrap();
+    for (w, saved) in models.brain.weights.iter_mut().zip(cp.weights.iter()) {
+        *w = *saved;
+    }
+    models.brain.intercept = cp.intercept;
+    apply_scaling(&mut models.brain, &cp);
+    models.activated_at = ic_cdk::api::time();
+
+    state.version += 1;
+    let new_version = state.version;
+    let restored = checkpoint(&models.brain, new_version, 0);
+    state.checkpoints.push(restored);
+    if state.checkpoints.len() > MAX_CHECKPOINTS {
+        state.checkpoints.remove(0);
+    }
+    Ok(())
+}
+
+/// Use a checkpoint's scaling stats when it recorded a full set
+fn apply_scaling(brain: &mut LogisticRegressionBrain, cp: &ModelCheckpoint) {
+    if let (Ok(means), Ok(stds)) = (cp.means.clone().try_into(), cp.stds.clone().try_into()) {
+        brain.means = means;
+        brain.stds = stds;
+    }
+}
+
+/// Replace the standardization statistics, keeping the weights; returns the new version
+pub fn set_scaling(stats: ScalingStats) -> Result<u64, String> {
+    let means: [f64; FEATURE_COUNT] = stats
+        .means
+        .try_into()
+        .map_err(|_| format!("means need {} values", FEATURE_COUNT))?;
+    let stds: [f64; FEATURE_COUNT] = stats
+        .stds
+        .try_into()
+        .map_err(|_| format!("stds need {} values", FEATURE_COUNT))?;
+    if means.iter().any(|m| !m.is_finite()) {
+        return Err("means must be finite".to_string());
+    }
+    if stds.iter().any(|s| !(*s > 0.0 && s.is_finite())) {
+        return Err("stds must be positive and finite".to_string());
+    }
+
+    let mut state = LEARNING.lock().unwrap();
+    let mut models = MODELS.lock().unwrap();
+    models.brain.means = means;
+    models.brain.stds = stds;
+    models.activated_at = ic_cdk::api::time();
+
+    state.version += 1;
+    let version = state.version;
+    let cp = checkpoint(&models.brain, version, 0);
+    state.checkpoints.push(cp);
+    if state.checkpoints.len() > MAX_CHECKPOINTS {
+        state.checkpoints.remove(0);
+    }
+    ic_cdk::println!("Scaling statistics replaced: model v{}", version);
+    Ok(version)
+}
+
+/// A checkpoint's weights and scaling stats
+pub fn brain_for_version(version: u64) -> Option<LogisticRegressionBrain> {
+    let state = LEARNING.lock().unwrap();
+    let cp = state.checkpoints.iter().find(|c| c.version == version)?;
+    let mut brain = MODELS.lock().unwrap().brain.clone();
+    for (w, saved) in brain.weights.iter_mut().zip(cp.weights.iter()) {
+        *w = *saved;
+    }
+    brain.intercept = cp.intercept;
+    apply_scaling(&mut brain, cp);
+    Some(brain)
+}
+
+pub fn status() -> LearningStatus {
+    let state = LEARNING.lock().unwrap();
+    LearningStatus {
+        model_version: state.version,
+        pending_outcomes: state.pending.len() as u64,
+        config: state.config.clone(),
+        checkpoint_count: state.checkpoints.len() as u64,
+    }
+}
+
+pub fn checkpoints() -> Vec<ModelCheckpoint> {
+    LEARNING.lock().unwrap().checkpoints.clone()
+}
+
+/// Learning state carried across upgrades
+#[derive(CandidType, Serialize, Deserialize)]
+pub struct Snapshot {
+    pending: Vec<(Vec<f64>, bool)>,
+    config: LearningConfig,
+    version: u64,
+    checkpoints: Vec<ModelCheckpoint>,
+}
+
+pub fn snapshot() -> Snapshot {
+    let state = LEARNING.lock().unwrap();
+    Snapshot {
+        pending: state.pending.iter().map(|(x, d)| (x.to_vec(), *d)).collect(),
+        config: state.config.clone(),
+        version: state.version,
+        checkpoints: state.checkpoints.clone(),
+    }
+}
+
+pub fn restore(snapshot: Snapshot) {
+    let mut state = LEARNING.lock().unwrap();
+    state.pending = snapshot
+        .pending
+        .into_iter()
+        .filter_map(|(x, d)| Some((x.try_into().ok()?, d)))
+        .collect();
+    state.config = snapshot.config;
+    state.version = snapshot.version;
+    state.checkpoints = snapshot.checkpoints;
+}
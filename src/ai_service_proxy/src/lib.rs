@@ -1,10 +1,66 @@
 // src/ai_service_proxy/lib.rs
-use ic_cdk_macros::{init, query, update};
+use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 mod types;
-use types::{RiskRequest, RiskResponse};
+mod tree;
+mod scorecard;
+mod onnx;
+mod liquidation;
+mod trend;
+mod learning;
+mod memory;
+mod dataset;
+mod backtest;
+mod calibration;
+mod drift;
+mod ab;
+mod llm;
+mod market;
+mod advice;
+mod history;
+mod recommend;
+mod metering;
+mod fixed;
+mod persist;
+mod guardrails;
+mod metrics;
+use types::{
+    AbTestConfig, AbTestStats, AnomalyAction, CallerUsage, MeteringConfig, AnomalyResponse, AssetFeatures, BacktestReport, Calibration, CollateralValuation, CreditFeatures,
+    CreditScoreResponse, DriftAlert, GuardrailConfig, DriftReport, FeatureContribution, FeatureInfo, ModelInfo, LearningConfig, LearningStatus,
+    LiquidationFeatures, LiquidationForecast, LlmConfig, MarketModel, MarketSnapshot, MarketFeatures, ModelCheckpoint, ModelType,
+    OutcomeChunk, PriceSeries, RiskHistoryEntry, PriceTrend, ProxyInitArgs, RateSuggestion, RiskError, RiskRequest,
+    RiskRequestV2, RiskTier,
+    RiskResponse, ScalingStats, Scorecard, ScoringLogEntry, ScoringMetrics, TreeEnsemble, UserEventSummary,
+    HttpRequest, HttpResponse,
+};
+use candid::{Nat, Principal};
 use num_traits::cast::ToPrimitive;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+
+/// Number of features every model consumes
+pub(crate) const FEATURE_COUNT: usize = 5;
+
+/// Feature names in model input order
+pub(crate) const FEATURE_NAMES: [&str; FEATURE_COUNT] =
+    ["volatility", "collateral", "borrowed", "deposits", "credit_score"];
+
+/// Probability at or above which a request is classed high risk
+pub(crate) const HIGH_RISK_THRESHOLD: f64 = 0.5;
+
+/// Largest accepted raw value per feature (volatility is x1000, USD values in whole dollars)
+pub(crate) const FEATURE_MAX: [u64; FEATURE_COUNT] = [
+    100_000,
+    1_000_000_000_000_000,
+    1_000_000_000_000_000,
+    1_000_000_000_000_000,
+    1_000,
+];
 
 /// Logistic Regression Brain using exact numbers from model.pkl
+#[derive(Clone)]
 struct LogisticRegressionBrain {
     means: [f64; 5],
     stds: [f64; 5],
@@ -22,58 +78,1210 @@ impl LogisticRegressionBrain {
         scaled
     }
 
-    /// Compute probability using sigmoid
-    fn predict_proba(&self, x: &[f64; 5]) -> f64 {
-        let scaled = self.scale(x);
-        let mut z = self.intercept;
+    /// Per-feature contribution to the logit in fixed point
+    fn contributions_fixed(&self, x: &[f64; 5]) -> [fixed::Fixed; 5] {
+        let mut contrib = [0; 5];
         for i in 0..5 {
-            z += self.weights[i] * scaled[i];
+            let scaled = fixed::standardize(
+                fixed::from_f64(x[i]),
+                fixed::from_f64(self.means[i]),
+                fixed::from_f64(self.stds[i]),
+            );
+            contrib[i] = fixed::mul(fixed::from_f64(self.weights[i]), scaled);
         }
-        1.0 / (1.0 + (-z).exp())
+        contrib
     }
 
-    /// Predict class 0 = safe, 1 = high risk
-    fn predict(&self, x: &[f64; 5]) -> u8 {
-        let prob = self.predict_proba(x);
-        if prob >= 0.5 { 1 } else { 0 }
+    /// Per-feature contribution to the logit: weight * standardized value
+    fn contributions(&self, x: &[f64; 5]) -> [f64; 5] {
+        self.contributions_fixed(x).map(fixed::to_f64)
     }
+
+    /// Compute probability using a fixed-point sigmoid, identical on every replica
+    fn predict_proba(&self, x: &[f64; 5]) -> f64 {
+        let z = self
+            .contributions_fixed(x)
+            .iter()
+            .fold(fixed::from_f64(self.intercept), |acc, c| acc.saturating_add(*c));
+        fixed::to_f64(fixed::sigmoid(z))
+    }
+}
+
+/// Maximum number of drivers reported in a response
+const MAX_TOP_DRIVERS: usize = 2;
+
+/// Explain a logistic regression score feature by feature
+fn explain(
+    brain: &LogisticRegressionBrain,
+    features: &[f64; FEATURE_COUNT],
+) -> (Vec<FeatureContribution>, Vec<String>) {
+    let contributions: Vec<FeatureContribution> = brain
+        .contributions(features)
+        .iter()
+        .enumerate()
+        .map(|(i, c)| FeatureContribution {
+            feature: FEATURE_NAMES[i].to_string(),
+            value: features[i],
+            contribution: *c,
+        })
+        .collect();
+    let top_drivers = top_drivers(&contributions);
+    (contributions, top_drivers)
+}
+
+/// Explain a scorecard score by the points each feature fell short
+fn explain_scorecard(
+    card: &Scorecard,
+    features: &[f64; FEATURE_COUNT],
+) -> (Vec<FeatureContribution>, Vec<String>) {
+    let contributions = card.contributions(features);
+    let top_drivers = top_drivers(&contributions);
+    (contributions, top_drivers)
+}
+
+/// Features pushing hardest towards high risk, strongest first
+fn top_drivers(contributions: &[FeatureContribution]) -> Vec<String> {
+    let mut drivers: Vec<&FeatureContribution> =
+        contributions.iter().filter(|c| c.contribution > 0.0).collect();
+    drivers.sort_by(|a, b| b.contribution.total_cmp(&a.contribution));
+    drivers
+        .into_iter()
+        .take(MAX_TOP_DRIVERS)
+        .map(|c| c.feature.clone())
+        .collect()
 }
 
 // Initialize brain with updated 2.5M-user model constants
-static BRAIN: LogisticRegressionBrain = LogisticRegressionBrain {
+const DEFAULT_BRAIN: LogisticRegressionBrain = LogisticRegressionBrain {
     means: [0.254960, 774717.027074, 499839.415540, 1000172.144719, 574.696362],
     stds: [0.141482, 418514.422291, 288655.995022, 577065.613148, 158.832794],
     weights: [1.893918, -1.209705, 0.795901, 0.000843, -1.698044],
     intercept: 2.262179,
 };
 
+/// Credit scoring model over log-transformed repayment history
+struct CreditBrain {
+    /// [ln(1 + repayments), ln(1 + repaid USD), liquidations, ln(1 + age days)]
+    weights: [f64; 4],
+    intercept: f64,
+    min_score: f64,
+    max_score: f64,
+}
+
+impl CreditBrain {
+    /// Probability the account stays in good standing
+    fn predict_proba(&self, f: &CreditFeatures) -> f64 {
+        let x = [
+            fixed::ln_1p_int(f.repayment_count as u128),
+            fixed::ln_1p_int(f.total_repaid_usd.0.to_u128().unwrap_or(u128::MAX)),
+            (f.liquidation_count as i128).saturating_mul(fixed::ONE),
+            fixed::ln_1p_int(f.account_age_days as u128),
+        ];
+        let mut z = fixed::from_f64(self.intercept);
+        for (w, v) in self.weights.iter().zip(x.iter()) {
+            z = z.saturating_add(fixed::mul(fixed::from_f64(*w), *v));
+        }
+        fixed::to_f64(fixed::sigmoid(z))
+    }
+
+    /// Map probability linearly onto the score range
+    fn score(&self, f: &CreditFeatures) -> CreditScoreResponse {
+        let prob = self.predict_proba(f);
+        let score = self.min_score + (self.max_score - self.min_score) * prob;
+        CreditScoreResponse {
+            credit_score: score.round() as u32,
+            probability_good: prob,
+        }
+    }
+}
+
+// Starting coefficients: a fresh account with no history lands around 575
+static CREDIT_BRAIN: CreditBrain = CreditBrain {
+    weights: [0.45, 0.12, -1.10, 0.25],
+    intercept: 0.0,
+    min_score: 300.0,
+    max_score: 850.0,
+};
+
+/// In-progress chunked ONNX upload
+struct OnnxUpload {
+    expected_len: u64,
+    received: u64,
+}
+
+/// Uploaded models and which one `risk` routes to
+struct ModelRegistry {
+    active: ModelType,
+    /// Logistic regression parameters, updated by online learning
+    brain: LogisticRegressionBrain,
+    tree: Option<TreeEnsemble>,
+    scorecard: Option<Scorecard>,
+    /// Size of the finalized ONNX model stored in stable memory
+    onnx_len: u64,
+    onnx_upload: Option<OnnxUpload>,
+    /// Applied to the active model's raw probability
+    calibration: Calibration,
+    /// SHA-256 of the finalized ONNX model bytes
+    onnx_hash: Option<[u8; 32]>,
+    /// When the active model or its parameters last changed
+    activated_at: u64,
+}
+
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        ModelRegistry {
+            active: ModelType::default(),
+            brain: DEFAULT_BRAIN,
+            tree: None,
+            scorecard: None,
+            onnx_len: 0,
+            onnx_upload: None,
+            calibration: Calibration::None,
+            onnx_hash: None,
+            activated_at: 0,
+        }
+    }
+}
+
+static MODELS: Lazy<Mutex<ModelRegistry>> = Lazy::new(|| Mutex::new(ModelRegistry::default()));
+
+/// Principals allowed to call scoring endpoints besides controllers
+static AUTHORIZED_CALLERS: Lazy<Mutex<HashSet<Principal>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Off-chain trainers allowed to export the outcome dataset besides controllers
+static TRAINERS: Lazy<Mutex<HashSet<Principal>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// DAO governance canister; once set it is the only admin
+static GOVERNANCE_CANISTER: Lazy<Mutex<Option<Principal>>> = Lazy::new(|| Mutex::new(None));
+
+/// Maximum number of entries retained in the scoring log
+const MAX_LOG_ENTRIES: usize = 10_000;
+
+/// Bounded scoring log plus all-time counters
+#[derive(Default)]
+struct ScoringLog {
+    entries: VecDeque<ScoringLogEntry>,
+    total_requests: u64,
+    total_approved: u64,
+    probability_sum: f64,
+}
+
+static SCORING_LOG: Lazy<Mutex<ScoringLog>> = Lazy::new(|| Mutex::new(ScoringLog::default()));
+
+thread_local! {
+    /// Optimized ONNX plan, rebuilt from stable memory on finalize
+    static ONNX_PLAN: RefCell<Option<onnx::Plan>> = const { RefCell::new(None) };
+}
+
+/// Probability of high risk under the active model, and the model that produced it
+fn predict_proba(features: &[f64; FEATURE_COUNT]) -> (f64, ModelType) {
+    let models = MODELS.lock().unwrap();
+    let (raw, model) = match (models.active, models.tree.as_ref()) {
+        (ModelType::TreeEnsemble, Some(tree)) => {
+            let prob = if tree.standardize {
+                tree.predict_proba(&models.brain.scale(features))
+            } else {
+                tree.predict_proba(features)
+            };
+            (prob, ModelType::TreeEnsemble)
+        }
+        (ModelType::Scorecard, _) if models.scorecard.is_some() => {
+            let card = models.scorecard.as_ref().unwrap();
+            (card.predict_proba(features), ModelType::Scorecard)
+        }
+        (ModelType::Onnx, _) => {
+            let result = ONNX_PLAN.with(|plan| match plan.borrow().as_ref() {
+                Some(plan) => onnx::predict_proba(plan, features),
+                None => Err("no ONNX model loaded".to_string()),
+            });
+            match result {
+                Ok(prob) => (prob, ModelType::Onnx),
+                Err(err) => {
+                    ic_cdk::println!("ONNX scoring failed, falling back to brain: {}", err);
+                    (models.brain.predict_proba(features), ModelType::LogisticRegression)
+                }
+            }
+        }
+        _ => (models.brain.predict_proba(features), ModelType::LogisticRegression),
+    };
+    (calibration::apply(&models.calibration, raw), model)
+}
+
+/// Validate raw request values and convert them into model features
+fn validate_features(req: &RiskRequest) -> Result<[f64; FEATURE_COUNT], RiskError> {
+    let raw = [
+        &req.volatility,
+        &req.collateral,
+        &req.borrowed,
+        &req.deposits,
+        &req.credit_score,
+    ];
+    let mut features = [0.0; FEATURE_COUNT];
+    for (i, value) in raw.into_iter().enumerate() {
+        let max = Nat::from(FEATURE_MAX[i]);
+        if *value > max {
+            return Err(RiskError::InvalidFeature {
+                feature: FEATURE_NAMES[i].to_string(),
+                value: value.clone(),
+                max,
+            });
+        }
+        // Bounded above, so the conversion is exact enough and never fails
+        features[i] = value.0.to_f64().unwrap_or_default();
+    }
+    features[0] /= 1000.0; // scale volatility back
+    Ok(features)
+}
+
+/// Fixed-point scale of the USD and volatility fields of `RiskRequestV2`
+const E8S_PER_UNIT: u128 = 100_000_000;
+
+/// Validate a fixed-point request; limits match `risk` in model units
+fn validate_features_v2(req: &RiskRequestV2) -> Result<[f64; FEATURE_COUNT], RiskError> {
+    // (value, fixed-point scale, scale of the same feature in `RiskRequest`)
+    let raw = [
+        (&req.volatility_e8s, E8S_PER_UNIT, 1000),
+        (&req.collateral_e8s, E8S_PER_UNIT, 1),
+        (&req.borrowed_e8s, E8S_PER_UNIT, 1),
+        (&req.deposits_e8s, E8S_PER_UNIT, 1),
+        (&req.credit_score, 1, 1),
+    ];
+    let mut features = [0.0; FEATURE_COUNT];
+    for (i, (value, scale, v1_scale)) in raw.into_iter().enumerate() {
+        let max = Nat::from(FEATURE_MAX[i] as u128 * scale / v1_scale);
+        if *value > max {
+            return Err(RiskError::InvalidFeature {
+                feature: FEATURE_NAMES[i].to_string(),
+                value: value.clone(),
+                max,
+            });
+        }
+        features[i] = value.0.to_f64().unwrap_or_default() / scale as f64;
+    }
+    Ok(features)
+}
+
+/// Reject oversized account identifiers before they are stored
+fn validate_account(account: Option<&str>) -> Result<Option<&str>, RiskError> {
+    match account {
+        Some(a) if a.len() > history::MAX_ACCOUNT_LEN => Err(RiskError::InvalidFeature {
+            feature: "account".to_string(),
+            value: Nat::from(a.len()),
+            max: Nat::from(history::MAX_ACCOUNT_LEN),
+        }),
+        other => Ok(other),
+    }
+}
+
+/// Append a scored request to the bounded log and update counters
+fn log_scoring(entry: ScoringLogEntry) {
+    let mut log = SCORING_LOG.lock().unwrap();
+    log.total_requests += 1;
+    if entry.risk_score == 0 {
+        log.total_approved += 1;
+    }
+    log.probability_sum += entry.probability;
+    if log.entries.len() >= MAX_LOG_ENTRIES {
+        log.entries.pop_front();
+    }
+    log.entries.push_back(entry);
+}
+
 #[init]
-fn init() {
+fn init(args: Option<ProxyInitArgs>) {
+    let args = args.unwrap_or_default();
+    AUTHORIZED_CALLERS.lock().unwrap().extend(args.authorized_callers);
+    start_timers();
     ic_cdk::println!("AI Service Proxy Initialized with Logistic Regression Brain");
 }
 
+#[pre_upgrade]
+fn pre_upgrade() {
+    persist::save();
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    persist::load();
+    start_timers();
+}
+
+/// Schedule recurring background jobs
+fn start_timers() {
+    ic_cdk_timers::set_timer_interval(learning::TRAINING_INTERVAL, learning::auto_train);
+    ic_cdk_timers::set_timer_interval(market::MARKET_REFRESH_INTERVAL, || {
+        ic_cdk::futures::spawn(market::refresh())
+    });
+}
+
+// ---------------- ACCESS CONTROL ----------------
+
+/// The governance canister once set, canister controllers until then
+fn is_admin(principal: &Principal) -> bool {
+    match *GOVERNANCE_CANISTER.lock().unwrap() {
+        Some(governance) => *principal == governance,
+        None => ic_cdk::api::is_controller(principal),
+    }
+}
+
+/// Guard: admins (see `is_admin`)
+fn caller_is_controller() -> Result<(), String> {
+    if is_admin(&ic_cdk::caller()) {
+        Ok(())
+    } else if GOVERNANCE_CANISTER.lock().unwrap().is_some() {
+        Err("admin calls must come from the governance canister".to_string())
+    } else {
+        Err("caller is not a controller".to_string())
+    }
+}
+
+/// Guard: allowlisted callers or controllers
+fn caller_is_authorized() -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if AUTHORIZED_CALLERS.lock().unwrap().contains(&caller) || is_admin(&caller) {
+        Ok(())
+    } else {
+        Err(format!("caller {} is not authorized to request scoring", caller))
+    }
+}
+
+/// Guard: registered trainers or controllers
+fn caller_is_trainer() -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if TRAINERS.lock().unwrap().contains(&caller) || is_admin(&caller) {
+        Ok(())
+    } else {
+        Err(format!("caller {} is not a registered trainer", caller))
+    }
+}
+
+/// Hand admin rights to a DAO governance canister; can only be done once
+#[update(guard = "caller_is_controller")]
+fn set_governance_canister(principal: Principal) -> Result<(), String> {
+    if principal == Principal::anonymous() {
+        return Err("governance canister cannot be anonymous".to_string());
+    }
+    let mut governance = GOVERNANCE_CANISTER.lock().unwrap();
+    if governance.is_some() {
+        return Err("governance canister already set".to_string());
+    }
+    *governance = Some(principal);
+    Ok(())
+}
+
+#[query]
+fn get_governance_canister() -> Option<Principal> {
+    *GOVERNANCE_CANISTER.lock().unwrap()
+}
+
+#[update(guard = "caller_is_controller")]
+fn add_authorized_caller(principal: Principal) -> bool {
+    AUTHORIZED_CALLERS.lock().unwrap().insert(principal)
+}
+
+#[update(guard = "caller_is_controller")]
+fn remove_authorized_caller(principal: Principal) -> bool {
+    AUTHORIZED_CALLERS.lock().unwrap().remove(&principal)
+}
+
+#[query]
+fn list_authorized_callers() -> Vec<Principal> {
+    AUTHORIZED_CALLERS.lock().unwrap().iter().cloned().collect()
+}
+
 /// Compute risk based on request
-#[update]
-fn risk(req: RiskRequest) -> RiskResponse {
-    let features = [
-        req.volatility.0.to_f64().unwrap_or(f64::MAX) / 1000.0, // scale back
-        req.collateral.0.to_f64().unwrap_or(f64::MAX),
-        req.borrowed.0.to_f64().unwrap_or(f64::MAX),
-        req.deposits.0.to_f64().unwrap_or(f64::MAX),
-        req.credit_score.0.to_f64().unwrap_or(f64::MAX),
-    ];
+#[update(guard = "caller_is_authorized")]
+async fn risk(req: RiskRequest) -> Result<RiskResponse, RiskError> {
+    let start = ic_cdk::api::performance_counter(0);
+    let caller = ic_cdk::caller();
+    metering::admit(caller)?;
+    let features = validate_features(&req)?;
+    let account = validate_account(req.account.as_deref())?;
+    let lang = advice::normalize_lang(req.lang.as_deref());
+    let mut resp = score_request(&features, account, &lang, start);
+    metering::charge(caller, ic_cdk::api::performance_counter(0).saturating_sub(start));
+    llm::enrich(&features, &lang, &mut resp).await;
+    Ok(resp)
+}
+
+/// Compute risk from fixed-point USD values without whole-dollar rounding
+#[update(guard = "caller_is_authorized")]
+async fn risk_v2(req: RiskRequestV2) -> Result<RiskResponse, RiskError> {
+    let start = ic_cdk::api::performance_counter(0);
+    let caller = ic_cdk::caller();
+    metering::admit(caller)?;
+    let features = validate_features_v2(&req)?;
+    let account = validate_account(req.account.as_deref())?;
+    let lang = advice::normalize_lang(req.lang.as_deref());
+    let mut resp = score_request(&features, account, &lang, start);
+    metering::charge(caller, ic_cdk::api::performance_counter(0).saturating_sub(start));
+    llm::enrich(&features, &lang, &mut resp).await;
+    Ok(resp)
+}
+
+/// Query-latency preview of `risk_v2`: same decision under the active model,
+/// but nothing is metered, logged, recorded or sent to the LLM
+#[query(guard = "caller_is_authorized")]
+fn risk_preview(req: RiskRequestV2) -> Result<RiskResponse, RiskError> {
+    let features = validate_features_v2(&req)?;
+    validate_account(req.account.as_deref())?;
+    let lang = advice::normalize_lang(req.lang.as_deref());
+    Ok(evaluate(&features, &lang, None).0)
+}
+
+/// Score validated features, log the decision and build the response
+fn score_request(
+    features: &[f64; FEATURE_COUNT],
+    account: Option<&str>,
+    lang: &str,
+    start: u64,
+) -> RiskResponse {
     ic_cdk::println!("Features: {:?}", features);
 
-    let pred = BRAIN.predict(&features);
-    let prob = BRAIN.predict_proba(&features);
+    drift::observe(features);
+
+    // A running A/B test overrides the active model
+    let ab_arm = ab::score(features);
+    let (resp, model, model_version) = evaluate(features, lang, ab_arm);
+
+    if let Some(account) = account {
+        history::record(
+            account,
+            RiskHistoryEntry {
+                timestamp: ic_cdk::api::time(),
+                probability: resp.probability,
+                risk_score: resp.risk_score,
+                tier: resp.tier,
+                model,
+                model_version,
+            },
+        );
+    }
+
+    log_scoring(ScoringLogEntry {
+        timestamp: ic_cdk::api::time(),
+        caller: ic_cdk::caller(),
+        features: features.to_vec(),
+        probability: resp.probability,
+        risk_score: resp.risk_score,
+        model,
+        model_version,
+        instructions: ic_cdk::api::performance_counter(0).saturating_sub(start),
+    });
 
-    let advice = if pred == 0 {
-        "Safe to borrow".to_string()
+    resp
+}
+
+/// Decide and explain validated features without writing any state; also
+/// returns the model and version that produced the probability
+fn evaluate(
+    features: &[f64; FEATURE_COUNT],
+    lang: &str,
+    ab_arm: Option<(u64, LogisticRegressionBrain, f64)>,
+) -> (RiskResponse, ModelType, Option<u64>) {
+    let (prob, model, brain, model_version) = match &ab_arm {
+        Some((version, brain, prob)) => {
+            (*prob, ModelType::LogisticRegression, Some(brain.clone()), Some(*version))
+        }
+        None => {
+            let (prob, model) = predict_proba(features);
+            if model == ModelType::LogisticRegression {
+                let brain = MODELS.lock().unwrap().brain.clone();
+                (prob, model, Some(brain), Some(learning::status().model_version))
+            } else {
+                (prob, model, None, None)
+            }
+        }
+    };
+    let prob = market::adjust(prob);
+    // Class 0 = safe, 1 = high risk; guardrails may override the model
+    let pred = if prob >= HIGH_RISK_THRESHOLD { 1 } else { 0 };
+    let (pred, tier, decided_by) = guardrails::decide(features, pred, advice::tier(prob));
+
+    // Contributions are only meaningful for the linear model and scorecards
+    let (contributions, top_drivers) = match brain {
+        Some(brain) => explain(&brain, features),
+        None if model == ModelType::Scorecard => {
+            let card = MODELS.lock().unwrap().scorecard.clone();
+            card.map(|c| explain_scorecard(&c, features)).unwrap_or_default()
+        }
+        None => (vec![], vec![]),
+    };
+
+    // Re-score hypothetical positions through the same pipeline
+    let cal = MODELS.lock().unwrap().calibration.clone();
+    let recommendations = recommend::recommend(features, |x| {
+        let raw = match &ab_arm {
+            Some((_, brain, _)) => calibration::apply(&cal, brain.predict_proba(x)),
+            None => predict_proba(x).0,
+        };
+        guardrails::bound(x, market::adjust(raw))
+    });
+
+    let advice = advice::render(tier, lang, prob, top_drivers.first().map(String::as_str));
+
+    let resp = RiskResponse {
+        risk_score: pred,
+        probability: prob,
+        tier,
+        advice,
+        contributions,
+        top_drivers,
+        recommendations,
+        decided_by,
+    };
+    (resp, model, model_version)
+}
+
+/// Score an account's creditworthiness from its repayment history
+#[update(guard = "caller_is_authorized")]
+fn score_credit(features: CreditFeatures) -> CreditScoreResponse {
+    let resp = CREDIT_BRAIN.score(&features);
+    ic_cdk::println!("Credit features: {:?} -> {}", features, resp.credit_score);
+    resp
+}
+
+/// Utilization above which the steeper slope applies (basis points)
+const UTILIZATION_KINK_BPS: u64 = 8_000;
+/// Premium added per unit of utilization below / above the kink
+const UTILIZATION_SLOPE_LOW: f64 = 0.05;
+const UTILIZATION_SLOPE_HIGH: f64 = 0.75;
+/// Premium charged at 100% modeled default probability (basis points)
+const MAX_RISK_PREMIUM_BPS: f64 = 2_000.0;
+/// Largest accepted base rate (100% a year)
+const MAX_BASE_RATE_BPS: u64 = 10_000;
+
+/// Recommend a personalized borrow rate: base + utilization curve + risk premium
+#[update(guard = "caller_is_authorized")]
+fn suggest_rate(market: MarketFeatures) -> Result<RateSuggestion, RiskError> {
+    if market.utilization_bps > 10_000 {
+        return Err(RiskError::InvalidFeature {
+            feature: "utilization_bps".to_string(),
+            value: Nat::from(market.utilization_bps),
+            max: Nat::from(10_000u64),
+        });
+    }
+    if market.base_rate_bps > MAX_BASE_RATE_BPS {
+        return Err(RiskError::InvalidFeature {
+            feature: "base_rate_bps".to_string(),
+            value: Nat::from(market.base_rate_bps),
+            max: Nat::from(MAX_BASE_RATE_BPS),
+        });
+    }
+    let features = validate_features(&market.borrower)?;
+    let (prob, _) = predict_proba(&features);
+
+    let low = market.utilization_bps.min(UTILIZATION_KINK_BPS) as f64;
+    let high = market.utilization_bps.saturating_sub(UTILIZATION_KINK_BPS) as f64;
+    let utilization_premium_bps =
+        (low * UTILIZATION_SLOPE_LOW + high * UTILIZATION_SLOPE_HIGH).round() as u64;
+    let risk_premium_bps = (prob * MAX_RISK_PREMIUM_BPS).round() as u64;
+
+    Ok(RateSuggestion {
+        base_rate_bps: market.base_rate_bps,
+        utilization_premium_bps,
+        risk_premium_bps,
+        rate_bps: market.base_rate_bps + utilization_premium_bps + risk_premium_bps,
+        probability: prob,
+    })
+}
+
+/// Haircut bounds and coefficients for exotic collateral (basis points)
+const MIN_HAIRCUT_BPS: f64 = 1_000.0;
+const MAX_HAIRCUT_BPS: f64 = 9_000.0;
+const BASE_HAIRCUT_BPS: f64 = 1_500.0;
+/// Haircut added per unit of annualized volatility
+const VOLATILITY_HAIRCUT: f64 = 0.35;
+/// Haircut for an asset that never trades, decaying as turnover grows
+const ILLIQUIDITY_HAIRCUT_BPS: f64 = 4_000.0;
+/// Largest accepted annualized volatility (1000%)
+const MAX_ASSET_VOLATILITY_BPS: u64 = 100_000;
+
+/// Estimate a fair price and haircut for NFT / illiquid collateral
+#[update(guard = "caller_is_authorized")]
+fn value_collateral(asset: AssetFeatures) -> Result<CollateralValuation, RiskError> {
+    if asset.volatility_bps > MAX_ASSET_VOLATILITY_BPS {
+        return Err(RiskError::InvalidFeature {
+            feature: "volatility_bps".to_string(),
+            value: Nat::from(asset.volatility_bps),
+            max: Nat::from(MAX_ASSET_VOLATILITY_BPS),
+        });
+    }
+    if asset.floor_price_e8s == 0 {
+        return Ok(CollateralValuation {
+            fair_value_e8s: 0,
+            haircut_bps: 10_000,
+            daily_turnover: 0.0,
+        });
+    }
+
+    let daily_turnover = asset.volume_24h_e8s as f64 / asset.floor_price_e8s as f64;
+    let haircut_bps = (BASE_HAIRCUT_BPS
+        + VOLATILITY_HAIRCUT * asset.volatility_bps as f64
+        + ILLIQUIDITY_HAIRCUT_BPS / (1.0 + daily_turnover.ln_1p()))
+    .clamp(MIN_HAIRCUT_BPS, MAX_HAIRCUT_BPS);
+    let fair_value = asset.floor_price_e8s as f64 * (1.0 - haircut_bps / 10_000.0);
+
+    Ok(CollateralValuation {
+        fair_value_e8s: fair_value as u64,
+        haircut_bps: haircut_bps.round() as u64,
+        daily_turnover,
+    })
+}
+
+/// Deposit/borrow round trips per hour that look like looping
+const LOOP_RATE_THRESHOLD: f64 = 10.0;
+/// Deposits below this USD size count towards structuring
+const STRUCTURING_LIMIT_USD: u64 = 10_000;
+/// Minimum number of small deposits before structuring is considered
+const STRUCTURING_MIN_COUNT: u64 = 20;
+/// Anomaly scores at or above these trigger escalation / freezing
+const ESCALATE_THRESHOLD: f64 = 0.5;
+const FREEZE_THRESHOLD: f64 = 0.8;
+
+/// Flag suspicious activity patterns in a user's recent event summary
+#[update(guard = "caller_is_authorized")]
+fn analyze_activity(summary: UserEventSummary) -> AnomalyResponse {
+    let hours = (summary.window_secs as f64 / 3600.0).max(1.0);
+    let mut signals: Vec<(f64, String)> = vec![];
+
+    // Rapid deposit -> borrow loops inflate positions without new capital
+    let loops_per_hour = summary.deposit_count.min(summary.borrow_count) as f64 / hours;
+    if loops_per_hour > 0.0 {
+        let s = (loops_per_hour / LOOP_RATE_THRESHOLD).min(1.0);
+        signals.push((s, format!("{:.1} deposit/borrow loops per hour", loops_per_hour)));
+    }
+
+    // Many deposits just under a reporting-size threshold
+    if summary.deposit_count >= STRUCTURING_MIN_COUNT {
+        let avg = summary.deposit_volume_usd / summary.deposit_count;
+        if avg < STRUCTURING_LIMIT_USD && summary.deposit_volume_usd >= STRUCTURING_LIMIT_USD {
+            let s = (summary.deposit_count as f64 / (2 * STRUCTURING_MIN_COUNT) as f64).min(1.0);
+            signals.push((
+                s,
+                format!("{} deposits averaging ${} (possible structuring)", summary.deposit_count, avg),
+            ));
+        }
+    }
+
+    // Borrowed funds recycled into crowdfunding contributions
+    if summary.crowdfund_count > 1 && summary.borrow_volume_usd > 0 {
+        let ratio = summary.crowdfund_volume_usd as f64 / summary.borrow_volume_usd as f64;
+        if (0.8..=1.2).contains(&ratio) {
+            let s = (summary.crowdfund_count as f64 / 10.0).min(1.0);
+            signals.push((
+                s,
+                "borrowed funds recycled into crowdfunding (possible wash funding)".to_string(),
+            ));
+        }
+    }
+
+    // Independent signals combine as 1 - prod(1 - s)
+    let anomaly_score = 1.0 - signals.iter().map(|(s, _)| 1.0 - s).product::<f64>();
+    let action = if anomaly_score >= FREEZE_THRESHOLD {
+        AnomalyAction::Freeze
+    } else if anomaly_score >= ESCALATE_THRESHOLD {
+        AnomalyAction::Escalate
     } else {
-        format!("High risk (prob {:.2}), consider increasing collateral", prob)
+        AnomalyAction::None
     };
+    let reasons = signals
+        .into_iter()
+        .filter(|(s, _)| *s >= ESCALATE_THRESHOLD)
+        .map(|(_, reason)| reason)
+        .collect();
+
+    AnomalyResponse { anomaly_score, action, reasons }
+}
 
-    RiskResponse { risk_score: pred, advice }
+/// Largest accepted forecast horizon
+const MAX_LIQUIDATION_HORIZON_DAYS: u32 = 365;
+
+/// Predict the probability a position is liquidated within N days
+#[update(guard = "caller_is_authorized")]
+fn predict_liquidation(features: LiquidationFeatures) -> Result<LiquidationForecast, RiskError> {
+    if features.volatility_bps > MAX_ASSET_VOLATILITY_BPS {
+        return Err(RiskError::InvalidFeature {
+            feature: "volatility_bps".to_string(),
+            value: Nat::from(features.volatility_bps),
+            max: Nat::from(MAX_ASSET_VOLATILITY_BPS),
+        });
+    }
+    if features.horizon_days > MAX_LIQUIDATION_HORIZON_DAYS {
+        return Err(RiskError::InvalidFeature {
+            feature: "horizon_days".to_string(),
+            value: Nat::from(features.horizon_days),
+            max: Nat::from(MAX_LIQUIDATION_HORIZON_DAYS),
+        });
+    }
+    Ok(liquidation::forecast(&features))
+}
+
+/// Forecast short-term price direction from recent oracle prices
+#[update(guard = "caller_is_authorized")]
+fn predict_price_trend(series: PriceSeries) -> Result<PriceTrend, RiskError> {
+    if series.prices_e8s.len() < trend::MIN_OBSERVATIONS {
+        return Err(RiskError::InsufficientData {
+            feature: "prices_e8s".to_string(),
+            required: trend::MIN_OBSERVATIONS as u64,
+            got: series.prices_e8s.len() as u64,
+        });
+    }
+    Ok(trend::predict(&series.token, &series.prices_e8s))
+}
+
+// ---------------- ONLINE LEARNING ----------------
+
+/// Record a labeled loan outcome in the training dataset and the next SGD batch
+#[update(guard = "caller_is_authorized")]
+fn submit_outcome(features: RiskRequest, defaulted: bool) -> Result<(), RiskError> {
+    record_outcome(validate_features(&features)?, defaulted);
+    Ok(())
+}
+
+/// `submit_outcome` for features sent to `risk_v2`
+#[update(guard = "caller_is_authorized")]
+fn submit_outcome_v2(features: RiskRequestV2, defaulted: bool) -> Result<(), RiskError> {
+    record_outcome(validate_features_v2(&features)?, defaulted);
+    Ok(())
+}
+
+fn record_outcome(features: [f64; FEATURE_COUNT], defaulted: bool) {
+    if let Err(err) = dataset::append(&features, defaulted, ic_cdk::api::time()) {
+        ic_cdk::trap(&err);
+    }
+    ab::record_outcome(&features, defaulted);
+    learning::submit(features, defaulted);
+}
+
+/// Run SGD over all pending outcomes now; returns the new model version
+#[update(guard = "caller_is_controller")]
+fn train_on_outcomes() -> Result<u64, String> {
+    learning::train()
+}
+
+#[update(guard = "caller_is_controller")]
+fn set_learning_config(config: LearningConfig) -> Result<(), String> {
+    learning::set_config(config)
+}
+
+/// Restore the logistic regression weights and scaling saved at `version`
+#[update(guard = "caller_is_controller")]
+fn rollback_model(version: u64) -> Result<(), String> {
+    learning::rollback(version)
+}
+
+/// Replace the logistic regression's scaling statistics; returns the new model version
+#[update(guard = "caller_is_controller")]
+fn set_scaling_stats(stats: ScalingStats) -> Result<u64, String> {
+    learning::set_scaling(stats)
+}
+
+/// Adopt the drift monitor's live statistics as the scaling statistics
+#[update(guard = "caller_is_controller")]
+fn adopt_live_scaling() -> Result<u64, String> {
+    learning::set_scaling(drift::live_scaling()?)
+}
+
+#[query]
+fn get_learning_status() -> LearningStatus {
+    learning::status()
+}
+
+#[query]
+fn list_model_checkpoints() -> Vec<ModelCheckpoint> {
+    learning::checkpoints()
+}
+
+/// Evaluate a checkpointed model on outcomes and logged requests in [from_ts, to_ts]
+#[query(guard = "caller_is_controller")]
+fn backtest(model_version: u64, from_ts: u64, to_ts: u64) -> Result<BacktestReport, String> {
+    backtest::run(model_version, from_ts, to_ts)
+}
+
+/// Split traffic between two checkpoint versions
+#[update(guard = "caller_is_controller")]
+fn start_ab_test(config: AbTestConfig) -> Result<(), String> {
+    ab::start(config)
+}
+
+/// Stop the running A/B test and return its final stats
+#[update(guard = "caller_is_controller")]
+fn stop_ab_test() -> Option<AbTestStats> {
+    ab::stop()
+}
+
+#[query]
+fn get_ab_test_stats() -> Option<AbTestStats> {
+    ab::stats()
+}
+
+// ---------------- TRAINING DATA EXPORT ----------------
+
+#[update(guard = "caller_is_controller")]
+fn add_trainer(principal: Principal) -> bool {
+    TRAINERS.lock().unwrap().insert(principal)
+}
+
+#[update(guard = "caller_is_controller")]
+fn remove_trainer(principal: Principal) -> bool {
+    TRAINERS.lock().unwrap().remove(&principal)
+}
+
+/// Export labeled outcomes in chunks, starting at row `start`
+#[query(guard = "caller_is_trainer")]
+fn export_outcomes(start: u64, limit: u64) -> OutcomeChunk {
+    dataset::export(start, limit)
+}
+
+#[query]
+fn get_outcome_count() -> u64 {
+    *dataset::OUTCOME_COUNT.lock().unwrap()
+}
+
+// ---------------- ADVICE TEMPLATES ----------------
+
+/// Override the advice for one tier in one language; supports `{prob}` and `{driver}`
+#[update(guard = "caller_is_controller")]
+fn set_advice_template(lang: String, tier: RiskTier, template: String) -> Result<(), String> {
+    advice::set_template(&lang, tier, template)
+}
+
+#[query]
+fn list_advice_languages() -> Vec<String> {
+    advice::languages()
+}
+
+// ---------------- LLM ADVICE ----------------
+
+/// Configure LLM-written advice; disabled by default
+#[update(guard = "caller_is_controller")]
+fn set_llm_config(config: LlmConfig) -> Result<(), String> {
+    llm::set_config(config)
+}
+
+#[query(guard = "caller_is_controller")]
+fn get_llm_config() -> LlmConfig {
+    llm::config()
+}
+
+/// HTTPS outcall transform for LLM responses
+#[query]
+fn transform_llm_response(args: ic_cdk::management_canister::TransformArgs) -> ic_cdk::management_canister::HttpRequestResult {
+    llm::transform(args)
+}
+
+// ---------------- MARKET SENTIMENT ----------------
+
+#[query]
+fn get_market_snapshot() -> MarketSnapshot {
+    market::snapshot()
+}
+
+/// Weights and standardization for the market terms (all-zero weights disable them)
+#[update(guard = "caller_is_controller")]
+fn set_market_model(model: MarketModel) -> Result<(), String> {
+    market::set_model(model)
+}
+
+#[update(guard = "caller_is_controller")]
+async fn refresh_market_data_now() {
+    market::refresh().await;
+}
+
+/// HTTPS outcall transform for market data sources
+#[query]
+fn transform_market_response(args: ic_cdk::management_canister::TransformArgs) -> ic_cdk::management_canister::HttpRequestResult {
+    market::transform(args)
+}
+
+// ---------------- METERING ----------------
+
+#[update(guard = "caller_is_controller")]
+fn set_metering_config(config: MeteringConfig) {
+    metering::set_config(config);
+}
+
+#[query]
+fn get_metering_config() -> MeteringConfig {
+    metering::config()
+}
+
+// ---------------- GUARDRAILS ----------------
+
+/// Replace the rules layered over the model
+#[update(guard = "caller_is_controller")]
+fn set_guardrails(config: GuardrailConfig) -> Result<(), String> {
+    guardrails::set_config(config)
+}
+
+#[query]
+fn get_guardrails() -> GuardrailConfig {
+    guardrails::config()
+}
+
+/// Consumption of every caller (admin)
+#[query(guard = "caller_is_controller")]
+fn get_usage_stats() -> Vec<CallerUsage> {
+    metering::usage(None)
+}
+
+/// The calling principal's own consumption
+#[query]
+fn get_my_usage() -> Option<CallerUsage> {
+    metering::usage(Some(ic_cdk::caller())).pop()
+}
+
+// ---------------- MONITORING ----------------
+
+#[query]
+fn get_scoring_metrics() -> ScoringMetrics {
+    let log = SCORING_LOG.lock().unwrap();
+    if log.total_requests == 0 {
+        return ScoringMetrics::default();
+    }
+    let mut latencies: Vec<u64> = log.entries.iter().map(|e| e.instructions).collect();
+    latencies.sort_unstable();
+    let p95 = latencies
+        .get((latencies.len() * 95).div_ceil(100).saturating_sub(1))
+        .copied()
+        .unwrap_or(0);
+    ScoringMetrics {
+        request_count: log.total_requests,
+        approval_rate: log.total_approved as f64 / log.total_requests as f64,
+        mean_probability: log.probability_sum / log.total_requests as f64,
+        p95_latency_instructions: p95,
+        log_size: log.entries.len() as u64,
+    }
+}
+
+/// `/metrics` (Prometheus) and `/health` for scraping through the HTTP gateway
+#[query]
+fn http_request(request: HttpRequest) -> HttpResponse {
+    let scoring = {
+        let log = SCORING_LOG.lock().unwrap();
+        metrics::ScoringTotals { requests: log.total_requests, approved: log.total_approved }
+    };
+    metrics::handle(&request, scoring)
+}
+
+/// Most recent scoring log entries, newest first
+#[query(guard = "caller_is_controller")]
+fn get_scoring_log(limit: u64) -> Vec<ScoringLogEntry> {
+    let log = SCORING_LOG.lock().unwrap();
+    log.entries.iter().rev().take(limit as usize).cloned().collect()
+}
+
+/// An account's recent scores, newest first
+#[query]
+fn get_risk_history(account: String, limit: u64) -> Vec<RiskHistoryEntry> {
+    history::get(&account, limit)
+}
+
+/// Live feature statistics compared with the training distribution
+#[query]
+fn get_drift_report() -> DriftReport {
+    drift::report()
+}
+
+#[query]
+fn get_drift_alerts() -> Vec<DriftAlert> {
+    drift::alerts()
+}
+
+#[update(guard = "caller_is_controller")]
+fn reset_drift_stats() {
+    drift::reset();
+}
+
+// ---------------- MODEL MANAGEMENT ----------------
+
+/// Upload (or replace) the tree ensemble model
+#[update(guard = "caller_is_controller")]
+fn upload_tree_model(model: TreeEnsemble) -> Result<(), String> {
+    model.validate()?;
+    let mut models = MODELS.lock().unwrap();
+    ic_cdk::println!("Tree ensemble uploaded: {} trees", model.trees.len());
+    models.tree = Some(model);
+    if models.active == ModelType::TreeEnsemble {
+        models.activated_at = ic_cdk::api::time();
+    }
+    Ok(())
+}
+
+/// Upload (or replace) the points scorecard; edits go through the same call
+#[update(guard = "caller_is_controller")]
+fn upload_scorecard(card: Scorecard) -> Result<(), String> {
+    card.validate()?;
+    let mut models = MODELS.lock().unwrap();
+    ic_cdk::println!("Scorecard uploaded: {} attributes", card.attributes.len());
+    models.scorecard = Some(card);
+    if models.active == ModelType::Scorecard {
+        models.activated_at = ic_cdk::api::time();
+    }
+    Ok(())
+}
+
+#[query]
+fn get_scorecard() -> Option<Scorecard> {
+    MODELS.lock().unwrap().scorecard.clone()
+}
+
+/// Start a chunked ONNX upload of `total_len` bytes
+#[update(guard = "caller_is_controller")]
+fn begin_onnx_upload(total_len: u64) -> bool {
+    if total_len == 0 || total_len > memory::ONNX_MAX_BYTES {
+        return false;
+    }
+    let mut models = MODELS.lock().unwrap();
+    models.onnx_len = 0;
+    models.onnx_upload = Some(OnnxUpload { expected_len: total_len, received: 0 });
+    true
+}
+
+/// Append the next chunk to stable memory; returns bytes received so far
+#[update(guard = "caller_is_controller")]
+fn upload_onnx_chunk(chunk: Vec<u8>) -> Result<u64, String> {
+    let mut models = MODELS.lock().unwrap();
+    let upload = models.onnx_upload.as_mut().ok_or("no ONNX upload in progress")?;
+    if upload.received + chunk.len() as u64 > upload.expected_len {
+        return Err("chunk exceeds declared model size".to_string());
+    }
+    onnx::write_chunk(upload.received, &chunk)?;
+    upload.received += chunk.len() as u64;
+    Ok(upload.received)
+}
+
+/// Load the uploaded bytes into an executable plan
+#[update(guard = "caller_is_controller")]
+fn finalize_onnx_upload() -> Result<(), String> {
+    let mut models = MODELS.lock().unwrap();
+    let len = match models.onnx_upload.as_ref() {
+        Some(u) if u.received == u.expected_len => u.expected_len,
+        Some(u) => return Err(format!("upload incomplete: {}/{} bytes", u.received, u.expected_len)),
+        None => return Err("no ONNX upload in progress".to_string()),
+    };
+    let blob = onnx::read_blob(len);
+    let plan = onnx::load(&blob)?;
+    ONNX_PLAN.with(|p| *p.borrow_mut() = Some(plan));
+    models.onnx_len = len;
+    models.onnx_hash = Some(Sha256::digest(&blob).into());
+    models.onnx_upload = None;
+    if models.active == ModelType::Onnx {
+        models.activated_at = ic_cdk::api::time();
+    }
+    ic_cdk::println!("ONNX model loaded: {} bytes", len);
+    Ok(())
+}
+
+/// Route `risk` through the given model type
+#[update(guard = "caller_is_controller")]
+fn set_active_model(model_type: ModelType) -> bool {
+    let mut models = MODELS.lock().unwrap();
+    let available = match model_type {
+        ModelType::LogisticRegression => true,
+        ModelType::TreeEnsemble => models.tree.is_some(),
+        ModelType::Onnx => models.onnx_len > 0,
+        ModelType::Scorecard => models.scorecard.is_some(),
+    };
+    if !available {
+        return false;
+    }
+    models.active = model_type;
+    models.activated_at = ic_cdk::api::time();
+    true
+}
+
+#[query]
+fn get_active_model() -> ModelType {
+    MODELS.lock().unwrap().active
+}
+
+/// Where the baked-in logistic regression parameters come from
+const TRAINING_DATA_DESCRIPTION: &str =
+    "model.pkl: logistic regression trained on a 2.5M-user borrower dataset";
+
+/// Units of each feature as the model receives it
+const FEATURE_SCALES: [&str; FEATURE_COUNT] = [
+    "borrowed/deposits ratio (sent x1000 to risk, e8s to risk_v2)",
+    "USD",
+    "USD",
+    "USD",
+    "credit score points (300-850)",
+];
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// SHA-256 over the active model's parameters
+fn parameter_hash(models: &ModelRegistry) -> Option<String> {
+    match models.active {
+        ModelType::LogisticRegression => {
+            let brain = &models.brain;
+            let mut hasher = Sha256::new();
+            for v in brain.means.iter().chain(&brain.stds).chain(&brain.weights) {
+                hasher.update(v.to_le_bytes());
+            }
+            hasher.update(brain.intercept.to_le_bytes());
+            Some(hex(&hasher.finalize()))
+        }
+        ModelType::TreeEnsemble => models
+            .tree
+            .as_ref()
+            .and_then(|t| candid::Encode!(t).ok())
+            .map(|bytes| hex(&Sha256::digest(&bytes))),
+        ModelType::Onnx => models.onnx_hash.map(|h| hex(&h)),
+        ModelType::Scorecard => models
+            .scorecard
+            .as_ref()
+            .and_then(|c| candid::Encode!(c).ok())
+            .map(|bytes| hex(&Sha256::digest(&bytes))),
+    }
+}
+
+/// Which model is making decisions, with its parameters and a hash to verify them
+#[query]
+fn get_model_info() -> ModelInfo {
+    let models = MODELS.lock().unwrap();
+    let brain = &models.brain;
+    ModelInfo {
+        active_model: models.active,
+        model_version: learning::status().model_version,
+        training_data: TRAINING_DATA_DESCRIPTION.to_string(),
+        features: (0..FEATURE_COUNT)
+            .map(|i| FeatureInfo {
+                name: FEATURE_NAMES[i].to_string(),
+                scale: FEATURE_SCALES[i].to_string(),
+                max: FEATURE_MAX[i],
+                mean: brain.means[i],
+                std: brain.stds[i],
+                weight: brain.weights[i],
+            })
+            .collect(),
+        intercept: brain.intercept,
+        calibration: models.calibration.clone(),
+        activated_at: models.activated_at,
+        parameter_hash: parameter_hash(&models),
+        scorecard: match models.active {
+            ModelType::Scorecard => models.scorecard.clone(),
+            _ => None,
+        },
+    }
+}
+
+/// Calibrate raw model probabilities before they reach responses
+#[update(guard = "caller_is_controller")]
+fn set_calibration(cal: Calibration) -> Result<(), String> {
+    calibration::validate(&cal)?;
+    MODELS.lock().unwrap().calibration = cal;
+    Ok(())
+}
+
+#[query]
+fn get_calibration() -> Calibration {
+    MODELS.lock().unwrap().calibration.clone()
 }
 
 #[query]
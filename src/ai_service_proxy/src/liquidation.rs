@@ -0,0 +1,67 @@
+// src/ai_service_proxy/liquidation.rs
+//! Liquidation forecasting: the collateral price is modeled as a geometric
+//! Brownian motion and a position is liquidated the first time the price falls
+//! by a factor of `1 / health_factor`.
+
+use crate::types::{LiquidationFeatures, LiquidationForecast, WarningLevel};
+
+/// Longest horizon searched for `days_to_danger`
+const MAX_FORECAST_DAYS: u32 = 365;
+
+/// Standard normal CDF via the Abramowitz-Stegun erf approximation (|err| < 1.5e-7)
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Probability that log-price with daily drift `mu` and daily vol `sigma`
+/// touches `barrier` (< 0) within `days`
+fn first_passage(barrier: f64, mu: f64, sigma: f64, days: f64) -> f64 {
+    if barrier >= 0.0 {
+        return 1.0;
+    }
+    if sigma <= 0.0 {
+        return if mu * days <= barrier { 1.0 } else { 0.0 };
+    }
+    let s = sigma * days.sqrt();
+    let p = normal_cdf((barrier - mu * days) / s)
+        + (2.0 * mu * barrier / (sigma * sigma)).exp() * normal_cdf((barrier + mu * days) / s);
+    p.clamp(0.0, 1.0)
+}
+
+fn warning_level(probability: f64) -> WarningLevel {
+    if probability >= 0.5 {
+        WarningLevel::Critical
+    } else if probability >= 0.2 {
+        WarningLevel::Warning
+    } else if probability >= 0.05 {
+        WarningLevel::Watch
+    } else {
+        WarningLevel::None
+    }
+}
+
+pub fn forecast(f: &LiquidationFeatures) -> LiquidationForecast {
+    let barrier = -(f.health_factor_bps.max(1) as f64 / 10_000.0).ln();
+    let sigma = f.volatility_bps as f64 / 10_000.0 / 365f64.sqrt();
+    let mu = f.price_trend_bps as f64 / 10_000.0;
+
+    let probability = first_passage(barrier, mu, sigma, f.horizon_days as f64);
+    let days_to_danger = (1..=MAX_FORECAST_DAYS)
+        .find(|d| first_passage(barrier, mu, sigma, *d as f64) >= 0.5);
+
+    LiquidationForecast {
+        probability,
+        warning_level: warning_level(probability),
+        days_to_danger,
+    }
+}
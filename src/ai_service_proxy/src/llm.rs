@@ -0,0 +1,157 @@
+// src/ai_service_proxy/llm.rs
+//! Optional LLM-written advice. After the numeric score is computed, the
+//! proxy asks an OpenAI-compatible chat completions API for a short,
+//! user-specific explanation via an HTTPS outcall. Any failure keeps the
+//! templated advice, so scoring never depends on the external API.
+//!
+//! All replicas send the same request with the same `Idempotency-Key`, and
+//! the transform reduces the response to the advice text alone, so replicas
+//! reach consensus as long as the API (or a caching gateway in front of it)
+//! honours the key.
+
+use crate::types::{LlmConfig, RiskResponse};
+use crate::{FEATURE_COUNT, FEATURE_NAMES};
+use ic_cdk::management_canister::{
+    http_request, HttpHeader, HttpMethod, HttpRequestArgs, HttpRequestResult, TransformArgs,
+    TransformContext,
+};
+use num_traits::cast::ToPrimitive;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+/// Longest advice accepted from the model, in characters
+const MAX_ADVICE_CHARS: usize = 600;
+/// Upper bound on `max_response_bytes`, keeping outcall fees predictable
+const MAX_RESPONSE_BYTES_LIMIT: u64 = 16 * 1024;
+
+static LLM_CONFIG: Lazy<Mutex<LlmConfig>> = Lazy::new(|| Mutex::new(LlmConfig::default()));
+
+pub fn set_config(config: LlmConfig) -> Result<(), String> {
+    if config.enabled && !config.endpoint.starts_with("https://") {
+        return Err("endpoint must be an https:// URL".to_string());
+    }
+    if config.max_response_bytes == 0 || config.max_response_bytes > MAX_RESPONSE_BYTES_LIMIT {
+        return Err(format!("max_response_bytes must be in 1..={}", MAX_RESPONSE_BYTES_LIMIT));
+    }
+    *LLM_CONFIG.lock().unwrap() = config;
+    Ok(())
+}
+
+/// Current config with the API key redacted
+pub fn config() -> LlmConfig {
+    let mut config = LLM_CONFIG.lock().unwrap().clone();
+    if !config.api_key.is_empty() {
+        config.api_key = "<redacted>".to_string();
+    }
+    config
+}
+
+fn prompt(features: &[f64; FEATURE_COUNT], lang: &str, resp: &RiskResponse) -> String {
+    let values: Vec<String> = FEATURE_NAMES
+        .iter()
+        .zip(features.iter())
+        .map(|(name, v)| format!("{}={:.4}", name, v))
+        .collect();
+    format!(
+        "A DeFi lending risk model scored a borrower. Features: {}. \
+         Probability of high risk: {:.3}. Decision: {}. Main risk drivers: {}. \
+         In at most three sentences, explain the decision to the borrower and \
+         suggest concrete steps to lower their risk. Answer in the language \
+         with ISO 639-1 code \"{}\".",
+        values.join(", "),
+        resp.probability,
+        if resp.risk_score == 0 { "safe" } else { "high risk" },
+        if resp.top_drivers.is_empty() { "none".to_string() } else { resp.top_drivers.join(", ") },
+        lang,
+    )
+}
+
+/// Deterministic key for identical prompts (FNV-1a)
+fn idempotency_key(body: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in body {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Replace the templated advice with LLM output when enabled and available
+pub async fn enrich(features: &[f64; FEATURE_COUNT], lang: &str, resp: &mut RiskResponse) {
+    let config = LLM_CONFIG.lock().unwrap().clone();
+    if !config.enabled {
+        return;
+    }
+    match generate(&config, features, lang, resp).await {
+        Ok(advice) => resp.advice = advice,
+        Err(err) => ic_cdk::println!("LLM advice unavailable, using template: {}", err),
+    }
+}
+
+async fn generate(
+    config: &LlmConfig,
+    features: &[f64; FEATURE_COUNT],
+    lang: &str,
+    resp: &RiskResponse,
+) -> Result<String, String> {
+    let body = json!({
+        "model": config.model,
+        "temperature": 0,
+        "messages": [
+            { "role": "system", "content": "You are a concise, factual DeFi lending assistant." },
+            { "role": "user", "content": prompt(features, lang, resp) },
+        ],
+    })
+    .to_string()
+    .into_bytes();
+
+    let request = HttpRequestArgs {
+        url: config.endpoint.clone(),
+        max_response_bytes: Some(config.max_response_bytes),
+        method: HttpMethod::POST,
+        headers: vec![
+            HttpHeader { name: "Content-Type".to_string(), value: "application/json".to_string() },
+            HttpHeader { name: "Authorization".to_string(), value: format!("Bearer {}", config.api_key) },
+            HttpHeader { name: "Idempotency-Key".to_string(), value: idempotency_key(&body) },
+        ],
+        body: Some(body),
+        transform: Some(TransformContext::from_name("transform_llm_response".to_string(), vec![])),
+        is_replicated: None,
+    };
+
+    let result = http_request(&request)
+        .await
+        .map_err(|e| format!("outcall failed: {:?}", e))?;
+    if result.status.0.to_u64() != Some(200) {
+        return Err(format!("LLM API returned status {}", result.status));
+    }
+    let advice = String::from_utf8(result.body).map_err(|_| "advice is not UTF-8".to_string())?;
+    if advice.is_empty() {
+        return Err("LLM API returned no advice".to_string());
+    }
+    Ok(advice)
+}
+
+/// Transform: keep only the status and the trimmed advice text
+pub fn transform(args: TransformArgs) -> HttpRequestResult {
+    let advice = serde_json::from_slice::<Value>(&args.response.body)
+        .ok()
+        .and_then(|v| v["choices"][0]["message"]["content"].as_str().map(str::to_string))
+        .map(|s| s.trim().chars().take(MAX_ADVICE_CHARS).collect::<String>())
+        .unwrap_or_default();
+    HttpRequestResult {
+        status: args.response.status,
+        headers: vec![],
+        body: advice.into_bytes(),
+    }
+}
+
+/// Unredacted config for upgrade snapshots
+pub fn snapshot() -> LlmConfig {
+    LLM_CONFIG.lock().unwrap().clone()
+}
+
+pub fn restore(config: LlmConfig) {
+    *LLM_CONFIG.lock().unwrap() = config;
+}
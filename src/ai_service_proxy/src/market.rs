@@ -0,0 +1,181 @@
+// src/ai_service_proxy/market.rs
+//! External market-sentiment signals fetched by timer through HTTPS outcalls:
+//! the Crypto Fear & Greed index, the BTC perpetual funding rate and 30-day
+//! realized BTC volatility. Cached values enter scoring as extra standardized
+//! terms on the logit of the model's probability. A signal that is missing or
+//! stale contributes nothing, which is the same as imputing its mean, so
+//! failed fetches degrade scoring back to the base model.
+
+use crate::types::{MarketModel, MarketSignal, MarketSnapshot};
+use ic_cdk::management_canister::{
+    http_request, HttpMethod, HttpRequestArgs, HttpRequestResult, TransformArgs, TransformContext,
+};
+use num_traits::cast::ToPrimitive;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How often market signals are refetched
+pub const MARKET_REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Number of market signals, in `MarketModel` order
+pub const MARKET_SIGNAL_COUNT: usize = 3;
+pub const MARKET_SIGNAL_NAMES: [&str; MARKET_SIGNAL_COUNT] =
+    ["fear_greed", "funding_rate", "realized_volatility"];
+
+const FEAR_GREED_URL: &str = "https://api.alternative.me/fng/?limit=1";
+const FUNDING_RATE_URL: &str = "https://fapi.binance.com/fapi/v1/premiumIndex?symbol=BTCUSDT";
+const CANDLES_URL: &str = "https://api.exchange.coinbase.com/products/BTC-USD/candles?granularity=86400";
+/// Daily closes used for realized volatility
+const VOLATILITY_WINDOW_DAYS: usize = 30;
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+#[derive(Default)]
+struct MarketState {
+    signals: [Option<MarketSignal>; MARKET_SIGNAL_COUNT],
+    last_error: Option<String>,
+    model: MarketModel,
+}
+
+static MARKET: Lazy<Mutex<MarketState>> = Lazy::new(|| Mutex::new(MarketState::default()));
+
+/// Parse a source's body down to a single number (runs inside the transform)
+fn extract(source: usize, body: &[u8]) -> Option<f64> {
+    let json: Value = serde_json::from_slice(body).ok()?;
+    match source {
+        0 => json["data"][0]["value"].as_str()?.parse().ok(),
+        1 => json["lastFundingRate"].as_str()?.parse().ok(),
+        2 => {
+            // Candles are [time, low, high, open, close, volume], newest first
+            let closes: Vec<f64> = json
+                .as_array()?
+                .iter()
+                .take(VOLATILITY_WINDOW_DAYS + 1)
+                .filter_map(|c| c[4].as_f64())
+                .collect();
+            if closes.len() < 3 {
+                return None;
+            }
+            let returns: Vec<f64> = closes.windows(2).map(|w| (w[0] / w[1]).ln()).collect();
+            let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+            let var = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>()
+                / (returns.len() - 1) as f64;
+            Some(var.sqrt() * 365f64.sqrt())
+        }
+        _ => None,
+    }
+}
+
+/// Transform: the context byte names the source; the body becomes the parsed
+/// number as text (empty when parsing fails), so replicas agree
+pub fn transform(args: TransformArgs) -> HttpRequestResult {
+    let source = args.context.first().copied().unwrap_or(u8::MAX) as usize;
+    let body = extract(source, &args.response.body)
+        .filter(|v| v.is_finite())
+        .map(|v| format!("{}", v))
+        .unwrap_or_default();
+    HttpRequestResult { status: args.response.status, headers: vec![], body: body.into_bytes() }
+}
+
+async fn fetch(source: usize, url: &str) -> Result<f64, String> {
+    let request = HttpRequestArgs {
+        url: url.to_string(),
+        max_response_bytes: Some(MAX_RESPONSE_BYTES),
+        method: HttpMethod::GET,
+        headers: vec![],
+        body: None,
+        transform: Some(TransformContext::from_name(
+            "transform_market_response".to_string(),
+            vec![source as u8],
+        )),
+        is_replicated: None,
+    };
+    let result = http_request(&request)
+        .await
+        .map_err(|e| format!("{}: outcall failed: {:?}", MARKET_SIGNAL_NAMES[source], e))?;
+    if result.status.0.to_u64() != Some(200) {
+        return Err(format!("{}: status {}", MARKET_SIGNAL_NAMES[source], result.status));
+    }
+    String::from_utf8(result.body)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or(format!("{}: unparseable response", MARKET_SIGNAL_NAMES[source]))
+}
+
+/// Timer entry point: refetch every source, keeping old values on failure
+pub async fn refresh() {
+    let urls = [FEAR_GREED_URL, FUNDING_RATE_URL, CANDLES_URL];
+    let mut errors = vec![];
+    for (source, url) in urls.iter().enumerate() {
+        match fetch(source, url).await {
+            Ok(value) => {
+                MARKET.lock().unwrap().signals[source] =
+                    Some(MarketSignal { value, updated_at: ic_cdk::api::time() });
+            }
+            Err(err) => errors.push(err),
+        }
+    }
+    if !errors.is_empty() {
+        ic_cdk::println!("Market data refresh incomplete: {}", errors.join("; "));
+    }
+    MARKET.lock().unwrap().last_error = if errors.is_empty() { None } else { Some(errors.join("; ")) };
+}
+
+/// Shift a probability by the standardized terms of fresh signals
+pub fn adjust(prob: f64) -> f64 {
+    let state = MARKET.lock().unwrap();
+    let model = &state.model;
+    let now = ic_cdk::api::time();
+    let max_age = model.max_age_secs.saturating_mul(NANOS_PER_SEC);
+
+    let mut shift = 0.0;
+    for (i, signal) in state.signals.iter().enumerate() {
+        if let Some(s) = signal {
+            if now.saturating_sub(s.updated_at) <= max_age {
+                shift += model.weights[i] * (s.value - model.means[i]) / model.stds[i];
+            }
+        }
+    }
+    if shift == 0.0 {
+        return prob;
+    }
+    let p = prob.clamp(1e-12, 1.0 - 1e-12);
+    let logit = (p / (1.0 - p)).ln() + shift;
+    1.0 / (1.0 + (-logit).exp())
+}
+
+pub fn set_model(model: MarketModel) -> Result<(), String> {
+    let lens = [model.weights.len(), model.means.len(), model.stds.len()];
+    if lens.iter().any(|l| *l != MARKET_SIGNAL_COUNT) {
+        return Err(format!("weights, means and stds need {} values each", MARKET_SIGNAL_COUNT));
+    }
+    if model.stds.iter().any(|s| !(*s > 0.0 && s.is_finite())) {
+        return Err("stds must be positive and finite".to_string());
+    }
+    if model.weights.iter().chain(model.means.iter()).any(|v| !v.is_finite()) {
+        return Err("weights and means must be finite".to_string());
+    }
+    MARKET.lock().unwrap().model = model;
+    Ok(())
+}
+
+pub fn snapshot() -> MarketSnapshot {
+    let state = MARKET.lock().unwrap();
+    MarketSnapshot {
+        fear_greed: state.signals[0].clone(),
+        funding_rate: state.signals[1].clone(),
+        realized_volatility: state.signals[2].clone(),
+        last_error: state.last_error.clone(),
+        model: state.model.clone(),
+    }
+}
+
+/// Only the model is persisted; signals are refetched after an upgrade
+pub fn model_snapshot() -> MarketModel {
+    MARKET.lock().unwrap().model.clone()
+}
+
+pub fn restore_model(model: MarketModel) {
+    MARKET.lock().unwrap().model = model;
+}
@@ -0,0 +1,41 @@
+// src/ai_service_proxy/memory.rs
+//! Fixed stable memory layout shared by everything the proxy keeps there:
+//!
+//! | region   | start   | size      | contents                         |
+//! |----------|---------|-----------|----------------------------------|
+//! | ONNX     | 0       | 32 MiB    | uploaded ONNX model bytes        |
+//! | STATE    | 32 MiB  | 16 MiB    | heap state saved across upgrades |
+//! | OUTCOMES | 48 MiB  | unbounded | fixed-size labeled outcome rows  |
+
+use ic_cdk::stable::{stable_grow, stable_read, stable_size, stable_write, WASM_PAGE_SIZE_IN_BYTES};
+
+const MIB: u64 = 1024 * 1024;
+
+pub const ONNX_START: u64 = 0;
+pub const ONNX_MAX_BYTES: u64 = 32 * MIB;
+pub const STATE_START: u64 = ONNX_START + ONNX_MAX_BYTES;
+pub const STATE_MAX_BYTES: u64 = 16 * MIB;
+pub const OUTCOMES_START: u64 = STATE_START + STATE_MAX_BYTES;
+
+/// Grow stable memory so that `[0, end)` is addressable
+fn ensure_capacity(end: u64) -> Result<(), String> {
+    let needed_pages = end.div_ceil(WASM_PAGE_SIZE_IN_BYTES);
+    let current_pages = stable_size();
+    if needed_pages > current_pages {
+        stable_grow(needed_pages - current_pages)
+            .map_err(|e| format!("failed to grow stable memory: {}", e))?;
+    }
+    Ok(())
+}
+
+pub fn write(offset: u64, bytes: &[u8]) -> Result<(), String> {
+    ensure_capacity(offset + bytes.len() as u64)?;
+    stable_write(offset, bytes);
+    Ok(())
+}
+
+pub fn read(offset: u64, len: u64) -> Vec<u8> {
+    let mut buf = vec![0u8; len as usize];
+    stable_read(offset, &mut buf);
+    buf
+}
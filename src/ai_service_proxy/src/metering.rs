@@ -0,0 +1,124 @@
+// src/ai_service_proxy/metering.rs
+//! Cycle accounting and daily quotas for scoring calls. Each call is charged
+//! an estimate of the cycles it burned (update base fee plus executed
+//! instructions) to its caller. Callers get a daily call quota, raised per
+//! principal for the pool, and may be required to attach cycles per call.
+
+use crate::types::{CallerUsage, MeteringConfig, RiskError};
+use candid::Principal;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const NANOS_PER_DAY: u64 = 86_400 * 1_000_000_000;
+/// Base fee of an update call on a 13-node subnet
+const UPDATE_BASE_FEE_CYCLES: u64 = 5_000_000;
+/// Cycles per executed instruction on a 13-node subnet, as a ratio
+const CYCLES_PER_INSTRUCTION: (u64, u64) = (4, 10);
+
+#[derive(Default)]
+struct MeteringState {
+    config: MeteringConfig,
+    usage: HashMap<Principal, CallerUsage>,
+}
+
+static METERING: Lazy<Mutex<MeteringState>> = Lazy::new(|| Mutex::new(MeteringState::default()));
+
+fn day(now: u64) -> u64 {
+    now / NANOS_PER_DAY
+}
+
+fn usage_entry(state: &mut MeteringState, caller: Principal, now: u64) -> &mut CallerUsage {
+    let limit = state.config.quota_overrides.iter().find(|(p, _)| *p == caller).map(|(_, l)| *l);
+    let daily_limit = limit.unwrap_or(state.config.default_daily_calls);
+    let usage = state.usage.entry(caller).or_insert_with(|| CallerUsage {
+        caller,
+        day: day(now),
+        daily_limit,
+        calls_today: 0,
+        cycles_today: 0,
+        total_calls: 0,
+        total_cycles: 0,
+        cycles_paid: 0,
+    });
+    usage.daily_limit = daily_limit;
+    if usage.day != day(now) {
+        usage.day = day(now);
+        usage.calls_today = 0;
+        usage.cycles_today = 0;
+    }
+    usage
+}
+
+/// Check the caller's quota and take attached cycles before scoring.
+/// Controllers are exempt.
+pub fn admit(caller: Principal) -> Result<(), RiskError> {
+    if ic_cdk::api::is_controller(&caller) {
+        return Ok(());
+    }
+    let now = ic_cdk::api::time();
+    let mut state = METERING.lock().unwrap();
+    let required = state.config.required_cycles_per_call;
+    let usage = usage_entry(&mut state, caller, now);
+    if usage.calls_today >= usage.daily_limit {
+        return Err(RiskError::QuotaExceeded {
+            limit: usage.daily_limit,
+            resets_at: (usage.day + 1) * NANOS_PER_DAY,
+        });
+    }
+    if required > 0 {
+        let attached = ic_cdk::api::msg_cycles_available();
+        if attached < required as u128 {
+            return Err(RiskError::InsufficientCycles { required, attached: attached as u64 });
+        }
+        ic_cdk::api::msg_cycles_accept(required as u128);
+        usage.cycles_paid += required;
+    }
+    usage.calls_today += 1;
+    usage.total_calls += 1;
+    Ok(())
+}
+
+/// Charge the estimated cost of a finished call to its caller
+pub fn charge(caller: Principal, instructions: u64) {
+    let cycles = UPDATE_BASE_FEE_CYCLES
+        + instructions / CYCLES_PER_INSTRUCTION.1 * CYCLES_PER_INSTRUCTION.0;
+    let now = ic_cdk::api::time();
+    let mut state = METERING.lock().unwrap();
+    let usage = usage_entry(&mut state, caller, now);
+    usage.cycles_today += cycles;
+    usage.total_cycles += cycles;
+}
+
+pub fn set_config(config: MeteringConfig) {
+    METERING.lock().unwrap().config = config;
+}
+
+pub fn config() -> MeteringConfig {
+    METERING.lock().unwrap().config.clone()
+}
+
+pub fn usage(caller: Option<Principal>) -> Vec<CallerUsage> {
+    let state = METERING.lock().unwrap();
+    state
+        .usage
+        .values()
+        .filter(|u| match caller {
+            Some(c) => u.caller == c,
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+pub fn snapshot() -> (MeteringConfig, Vec<CallerUsage>) {
+    let state = METERING.lock().unwrap();
+    (state.config.clone(), state.usage.values().cloned().collect())
+}
+
+pub fn restore(config: MeteringConfig, usage: Vec<CallerUsage>) {
+    *METERING.lock().unwrap() = MeteringState {
+        config,
+        usage: usage.into_iter().map(|u| (u.caller, u)).collect(),
+    };
+}
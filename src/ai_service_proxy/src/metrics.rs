@@ -0,0 +1,88 @@
+// src/ai_service_proxy/metrics.rs
+//! Monitoring over the IC HTTP gateway. `/metrics` serves the Prometheus
+//! text format (cycles, memory, scoring totals and metered calls per caller)
+//! and `/health` answers 200 while the canister has cycles to spare, so a
+//! standard scraper can poll the proxy directly.
+use crate::metering;
+use crate::types::{HttpRequest, HttpResponse};
+use ic_cdk::stable::{stable_size, WASM_PAGE_SIZE_IN_BYTES};
+use std::fmt::{Display, Write};
+
+/// Below this cycle balance `/health` reports the canister unhealthy
+const MIN_HEALTHY_CYCLES: u128 = 100_000_000_000;
+
+/// All-time scoring counters kept by the scoring log
+pub struct ScoringTotals {
+    pub requests: u64,
+    pub approved: u64,
+}
+
+pub fn handle(request: &HttpRequest, scoring: ScoringTotals) -> HttpResponse {
+    let path = request.url.split('?').next().unwrap_or_default();
+    match (request.method.as_str(), path) {
+        ("GET", "/metrics") => response(200, "text/plain; version=0.0.4", render(scoring)),
+        ("GET", "/health") => health(),
+        _ => response(404, "text/plain", "not found\n".to_string()),
+    }
+}
+
+fn response(status_code: u16, content_type: &str, body: String) -> HttpResponse {
+    HttpResponse {
+        status_code,
+        headers: vec![("Content-Type".to_string(), content_type.to_string())],
+        body: body.into_bytes(),
+    }
+}
+
+fn health() -> HttpResponse {
+    if ic_cdk::api::canister_cycle_balance() < MIN_HEALTHY_CYCLES {
+        return response(503, "text/plain", "low cycles\n".to_string());
+    }
+    response(200, "text/plain", "ok\n".to_string())
+}
+
+fn heap_bytes() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        core::arch::wasm32::memory_size(0) as u64 * WASM_PAGE_SIZE_IN_BYTES
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        0
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, value: impl Display) {
+    header(out, name, kind, help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn render(scoring: ScoringTotals) -> String {
+    let mut out = String::new();
+    let cycles = ic_cdk::api::canister_cycle_balance();
+    metric(&mut out, "ai_proxy_cycles_balance", "gauge", "Cycle balance", cycles);
+    metric(&mut out, "ai_proxy_heap_memory_bytes", "gauge", "Wasm heap size", heap_bytes());
+    let stable = stable_size() * WASM_PAGE_SIZE_IN_BYTES;
+    metric(&mut out, "ai_proxy_stable_memory_bytes", "gauge", "Stable memory size", stable);
+    let (requests, approved) = (scoring.requests, scoring.approved);
+    metric(&mut out, "ai_proxy_scoring_requests_total", "counter", "Scored requests", requests);
+    metric(&mut out, "ai_proxy_scoring_approved_total", "counter", "Scored safe", approved);
+
+    let mut usage = metering::usage(None);
+    usage.sort_by_key(|u| u.caller);
+    let calls = "ai_proxy_calls_total";
+    header(&mut out, calls, "counter", "Metered scoring calls by caller");
+    for u in &usage {
+        let _ = writeln!(out, "{}{{caller=\"{}\"}} {}", calls, u.caller, u.total_calls);
+    }
+    let charged = "ai_proxy_cycles_charged_total";
+    header(&mut out, charged, "counter", "Estimated cycles burned by caller");
+    for u in &usage {
+        let _ = writeln!(out, "{}{{caller=\"{}\"}} {}", charged, u.caller, u.total_cycles);
+    }
+    out
+}
@@ -0,0 +1,79 @@
+// src/ai_service_proxy/onnx.rs
+//! ONNX model storage and execution.
+//!
+//! Model bytes are uploaded in chunks straight into the ONNX region of stable
+//! memory so multi-megabyte exports never sit in a single ingress message.
+//! Execution goes through `tract` and is only compiled with the `onnx` feature.
+//!
+//! Expected model signature: one `f32[1, 5]` input holding the raw features in
+//! `RiskRequest` order, and a last output that is either a single probability
+//! or `[P(safe), P(high risk)]` (export sklearn classifiers with `zipmap=False`).
+
+use crate::memory::{self, ONNX_MAX_BYTES, ONNX_START};
+use crate::FEATURE_COUNT;
+
+/// Write a chunk at `offset` within the ONNX region
+pub fn write_chunk(offset: u64, bytes: &[u8]) -> Result<(), String> {
+    if offset + bytes.len() as u64 > ONNX_MAX_BYTES {
+        return Err(format!("ONNX models are limited to {} bytes", ONNX_MAX_BYTES));
+    }
+    memory::write(ONNX_START + offset, bytes)
+}
+
+/// Read back the first `len` bytes of the stored model
+pub fn read_blob(len: u64) -> Vec<u8> {
+    memory::read(ONNX_START, len)
+}
+
+#[cfg(feature = "onnx")]
+mod runtime {
+    use super::FEATURE_COUNT;
+    use tract_onnx::prelude::*;
+
+    pub type Plan = TypedRunnableModel<TypedModel>;
+
+    pub fn load(bytes: &[u8]) -> Result<Plan, String> {
+        tract_onnx::onnx()
+            .model_for_read(&mut std::io::Cursor::new(bytes))
+            .and_then(|m| m.with_input_fact(0, f32::fact([1, FEATURE_COUNT]).into()))
+            .and_then(|m| m.into_optimized())
+            .and_then(|m| m.into_runnable())
+            .map_err(|e| format!("failed to load ONNX model: {}", e))
+    }
+
+    pub fn predict_proba(plan: &Plan, x: &[f64; FEATURE_COUNT]) -> Result<f64, String> {
+        let input: Vec<f32> = x.iter().map(|v| *v as f32).collect();
+        let tensor = Tensor::from_shape(&[1, FEATURE_COUNT], &input)
+            .map_err(|e| format!("failed to build input tensor: {}", e))?;
+        let outputs = plan
+            .run(tvec!(tensor.into()))
+            .map_err(|e| format!("ONNX execution failed: {}", e))?;
+        let last = outputs.last().ok_or("ONNX model produced no outputs")?;
+        let view = last
+            .to_array_view::<f32>()
+            .map_err(|e| format!("unexpected ONNX output type: {}", e))?;
+        let values: Vec<f32> = view.iter().copied().collect();
+        match values.as_slice() {
+            [p] => Ok(*p as f64),
+            [_, p] => Ok(*p as f64),
+            _ => Err(format!("expected 1 or 2 output values, got {}", values.len())),
+        }
+    }
+}
+
+#[cfg(not(feature = "onnx"))]
+mod runtime {
+    use super::FEATURE_COUNT;
+
+    pub struct Plan;
+
+    pub fn load(_bytes: &[u8]) -> Result<Plan, String> {
+        Err("ai_service_proxy was built without the `onnx` feature".to_string())
+    }
+
+    pub fn predict_proba(_plan: &Plan, _x: &[f64; FEATURE_COUNT]) -> Result<f64, String> {
+        Err("ai_service_proxy was built without the `onnx` feature".to_string())
+    }
+}
+
+pub use runtime::{load, predict_proba, Plan};
@@ -0,0 +1,200 @@
+// src/ai_service_proxy/persist.rs
+//! Upgrade persistence. `pre_upgrade` candid-encodes every piece of heap state
+//! into the STATE region of stable memory as `[len: u64 LE][bytes]`, and
+//! `post_upgrade` decodes it back. The ONNX model and outcome dataset already
+//! live in stable memory; only their lengths are carried here. A running A/B
+//! test carries on with its arms and tallies.
+//!
+//! If the snapshot outgrows the region, per-account history, then the A/B
+//! test's remembered decisions and then the scoring log entries are dropped
+//! so the upgrade itself can never fail.
+
+use crate::memory::{self, STATE_MAX_BYTES, STATE_START};
+use crate::types::{
+    CallerUsage, Calibration, GuardrailConfig, LlmConfig, MarketModel, MeteringConfig, ModelType,
+    RiskHistoryEntry, RiskTier, Scorecard, ScoringLogEntry, TreeEnsemble,
+};
+use crate::{
+    ab, advice, dataset, drift, guardrails, history, learning, llm, market, metering, onnx,
+    AUTHORIZED_CALLERS, GOVERNANCE_CANISTER, MODELS, ONNX_PLAN, SCORING_LOG, TRAINERS,
+};
+use candid::{CandidType, Decode, Encode, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Serialize, Deserialize)]
+struct ModelsSnapshot {
+    active: ModelType,
+    means: Vec<f64>,
+    stds: Vec<f64>,
+    weights: Vec<f64>,
+    intercept: f64,
+    tree: Option<TreeEnsemble>,
+    scorecard: Option<Scorecard>,
+    onnx_len: u64,
+    onnx_hash: Option<Vec<u8>>,
+    calibration: Calibration,
+    activated_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize)]
+struct ProxySnapshot {
+    authorized_callers: Vec<Principal>,
+    trainers: Vec<Principal>,
+    models: ModelsSnapshot,
+    scoring_log: Vec<ScoringLogEntry>,
+    total_requests: u64,
+    total_approved: u64,
+    probability_sum: f64,
+    learning: learning::Snapshot,
+    outcome_count: u64,
+    drift: drift::Snapshot,
+    metering_config: MeteringConfig,
+    usage: Vec<CallerUsage>,
+    llm: LlmConfig,
+    market_model: MarketModel,
+    advice_overrides: Vec<(String, RiskTier, String)>,
+    history: Vec<(String, Vec<RiskHistoryEntry>)>,
+    /// Absent in snapshots written before guardrails existed
+    guardrails: Option<GuardrailConfig>,
+    /// Set-once admin; losing it would reopen `set_governance_canister`
+    governance_canister: Option<Principal>,
+    /// Running A/B test; absent in snapshots written before tests were carried
+    ab_test: Option<ab::Snapshot>,
+}
+
+fn snapshot() -> ProxySnapshot {
+    let models = MODELS.lock().unwrap();
+    let log = SCORING_LOG.lock().unwrap();
+    let (metering_config, usage) = metering::snapshot();
+    ProxySnapshot {
+        authorized_callers: AUTHORIZED_CALLERS.lock().unwrap().iter().cloned().collect(),
+        trainers: TRAINERS.lock().unwrap().iter().cloned().collect(),
+        models: ModelsSnapshot {
+            active: models.active,
+            means: models.brain.means.to_vec(),
+            stds: models.brain.stds.to_vec(),
+            weights: models.brain.weights.to_vec(),
+            intercept: models.brain.intercept,
+            tree: models.tree.clone(),
+            scorecard: models.scorecard.clone(),
+            onnx_len: models.onnx_len,
+            onnx_hash: models.onnx_hash.map(|h| h.to_vec()),
+            calibration: models.calibration.clone(),
+            activated_at: models.activated_at,
+        },
+        scoring_log: log.entries.iter().cloned().collect(),
+        total_requests: log.total_requests,
+        total_approved: log.total_approved,
+        probability_sum: log.probability_sum,
+        learning: learning::snapshot(),
+        outcome_count: *dataset::OUTCOME_COUNT.lock().unwrap(),
+        drift: drift::snapshot(),
+        metering_config,
+        usage,
+        llm: llm::snapshot(),
+        market_model: market::model_snapshot(),
+        advice_overrides: advice::snapshot(),
+        history: history::snapshot(),
+        guardrails: Some(guardrails::config()),
+        governance_canister: *GOVERNANCE_CANISTER.lock().unwrap(),
+        ab_test: ab::snapshot(),
+    }
+}
+
+/// Encode state into the STATE region
+pub fn save() {
+    let mut snapshot = snapshot();
+    let mut bytes = Encode!(&snapshot).expect("failed to encode proxy state");
+    if bytes.len() as u64 + 8 > STATE_MAX_BYTES {
+        ic_cdk::println!("Proxy state too large, dropping risk history");
+        snapshot.history.clear();
+        bytes = Encode!(&snapshot).expect("failed to encode proxy state");
+    }
+    if bytes.len() as u64 + 8 > STATE_MAX_BYTES {
+        if let Some(test) = snapshot.ab_test.as_mut() {
+            ic_cdk::println!("Proxy state too large, dropping A/B test decisions");
+            test.forget_decisions();
+            bytes = Encode!(&snapshot).expect("failed to encode proxy state");
+        }
+    }
+    if bytes.len() as u64 + 8 > STATE_MAX_BYTES {
+        ic_cdk::println!("Proxy state too large, dropping scoring log entries");
+        snapshot.scoring_log.clear();
+        bytes = Encode!(&snapshot).expect("failed to encode proxy state");
+    }
+    memory::write(STATE_START, &(bytes.len() as u64).to_le_bytes())
+        .and_then(|_| memory::write(STATE_START + 8, &bytes))
+        .expect("failed to write proxy state");
+}
+
+/// Restore state saved by `save`; a missing or unreadable snapshot keeps defaults
+pub fn load() {
+    if ic_cdk::stable::stable_size() * ic_cdk::stable::WASM_PAGE_SIZE_IN_BYTES < STATE_START + 8 {
+        return;
+    }
+    let len = u64::from_le_bytes(memory::read(STATE_START, 8).try_into().unwrap());
+    if len == 0 || len + 8 > STATE_MAX_BYTES {
+        return;
+    }
+    let snapshot = match Decode!(&memory::read(STATE_START + 8, len), ProxySnapshot) {
+        Ok(s) => s,
+        Err(err) => {
+            ic_cdk::println!("Failed to decode proxy state, starting fresh: {}", err);
+            return;
+        }
+    };
+
+    AUTHORIZED_CALLERS.lock().unwrap().extend(snapshot.authorized_callers);
+    TRAINERS.lock().unwrap().extend(snapshot.trainers);
+    {
+        let m = snapshot.models;
+        let mut models = MODELS.lock().unwrap();
+        if let (Ok(means), Ok(stds), Ok(weights)) =
+            (m.means.try_into(), m.stds.try_into(), m.weights.try_into())
+        {
+            models.brain.means = means;
+            models.brain.stds = stds;
+            models.brain.weights = weights;
+            models.brain.intercept = m.intercept;
+        }
+        models.active = m.active;
+        models.tree = m.tree;
+        models.scorecard = m.scorecard;
+        models.onnx_len = m.onnx_len;
+        models.onnx_hash = m.onnx_hash.and_then(|h| h.try_into().ok());
+        models.calibration = m.calibration;
+        models.activated_at = m.activated_at;
+
+        // The model bytes survived in stable memory; rebuild the plan
+        if models.onnx_len > 0 {
+            match onnx::load(&onnx::read_blob(models.onnx_len)) {
+                Ok(plan) => ONNX_PLAN.with(|p| *p.borrow_mut() = Some(plan)),
+                Err(err) => ic_cdk::println!("ONNX model not reloaded: {}", err),
+            }
+        }
+    }
+    {
+        let mut log = SCORING_LOG.lock().unwrap();
+        log.entries = snapshot.scoring_log.into();
+        log.total_requests = snapshot.total_requests;
+        log.total_approved = snapshot.total_approved;
+        log.probability_sum = snapshot.probability_sum;
+    }
+    learning::restore(snapshot.learning);
+    *dataset::OUTCOME_COUNT.lock().unwrap() = snapshot.outcome_count;
+    drift::restore(snapshot.drift);
+    metering::restore(snapshot.metering_config, snapshot.usage);
+    llm::restore(snapshot.llm);
+    market::restore_model(snapshot.market_model);
+    advice::restore(snapshot.advice_overrides);
+    history::restore(snapshot.history);
+    if let Some(config) = snapshot.guardrails {
+        if let Err(err) = guardrails::set_config(config) {
+            ic_cdk::println!("Guardrails not restored: {}", err);
+        }
+    }
+    *GOVERNANCE_CANISTER.lock().unwrap() = snapshot.governance_canister;
+    if let Some(test) = snapshot.ab_test {
+        ab::restore(test);
+    }
+}
@@ -0,0 +1,76 @@
+// src/ai_service_proxy/recommend.rs
+//! Machine-actionable recommendations found by inverting the scoring
+//! pipeline around the decision boundary. Each recommendation moves one
+//! feature (borrowed or collateral) with the others held fixed, and bisects
+//! for the amount that lands just inside the approved region. Bisection
+//! assumes the score is monotone in that feature, which holds for the
+//! logistic regression and is a reasonable approximation for other models.
+
+use crate::types::Recommendations;
+use crate::{FEATURE_COUNT, FEATURE_MAX, HIGH_RISK_THRESHOLD};
+
+/// Recommendations aim this far below the decision threshold
+const TARGET_MARGIN: f64 = 0.02;
+/// Bisection steps; enough for sub-cent precision on any feature range
+const BISECTION_STEPS: u32 = 64;
+
+const COLLATERAL: usize = 1;
+const BORROWED: usize = 2;
+
+fn with(x: &[f64; FEATURE_COUNT], i: usize, value: f64) -> [f64; FEATURE_COUNT] {
+    let mut y = *x;
+    y[i] = value;
+    y
+}
+
+/// Boundary in [lo, hi] between `ok(lo)` and `!ok(hi)`, returning the `ok` side
+fn bisect(mut lo: f64, mut hi: f64, ok: impl Fn(f64) -> bool) -> f64 {
+    for _ in 0..BISECTION_STEPS {
+        let mid = (lo + hi) / 2.0;
+        if ok(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+pub fn recommend(x: &[f64; FEATURE_COUNT], score: impl Fn(&[f64; FEATURE_COUNT]) -> f64) -> Recommendations {
+    let target = HIGH_RISK_THRESHOLD - TARGET_MARGIN;
+    let approved = |y: &[f64; FEATURE_COUNT]| score(y) < target;
+    let max_collateral = FEATURE_MAX[COLLATERAL] as f64;
+    let max_borrowed = FEATURE_MAX[BORROWED] as f64;
+
+    if approved(x) {
+        // How much more can be borrowed before crossing the target
+        let max_additional_borrow_usd = if approved(&with(x, BORROWED, max_borrowed)) {
+            max_borrowed - x[BORROWED]
+        } else {
+            bisect(x[BORROWED], max_borrowed, |b| approved(&with(x, BORROWED, b))) - x[BORROWED]
+        };
+        return Recommendations {
+            target_probability: target,
+            max_additional_borrow_usd,
+            collateral_top_up_usd: Some(0.0),
+            repay_usd: Some(0.0),
+        };
+    }
+
+    // Smallest top-up / repayment that gets back under the target, if any
+    let collateral_top_up_usd = approved(&with(x, COLLATERAL, max_collateral)).then(|| {
+        let needed = bisect(max_collateral, x[COLLATERAL], |c| approved(&with(x, COLLATERAL, c)));
+        needed - x[COLLATERAL]
+    });
+    let repay_usd = approved(&with(x, BORROWED, 0.0)).then(|| {
+        let allowed = bisect(0.0, x[BORROWED], |b| approved(&with(x, BORROWED, b)));
+        x[BORROWED] - allowed
+    });
+
+    Recommendations {
+        target_probability: target,
+        max_additional_borrow_usd: 0.0,
+        collateral_top_up_usd,
+        repay_usd,
+    }
+}
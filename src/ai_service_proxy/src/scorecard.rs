@@ -0,0 +1,98 @@
+// src/ai_service_proxy/scorecard.rs
+//! Points-based credit scorecard. Each feature's raw value falls into one bin
+//! and earns that bin's points; the total maps to a probability through the
+//! usual points-to-double-odds scaling, so every decision reads as a sum of
+//! published points.
+use crate::types::{FeatureContribution, Scorecard};
+use crate::{FEATURE_COUNT, FEATURE_NAMES};
+
+impl Scorecard {
+    /// Check bins are ordered and every feature is scored at most once
+    pub fn validate(&self) -> Result<(), String> {
+        if self.attributes.is_empty() {
+            return Err("scorecard has no attributes".to_string());
+        }
+        if !(self.points_to_double_odds > 0.0 && self.points_to_double_odds.is_finite()) {
+            return Err("points_to_double_odds must be positive and finite".to_string());
+        }
+        if !self.even_odds_score.is_finite() {
+            return Err("even_odds_score must be finite".to_string());
+        }
+        let mut seen = [false; FEATURE_COUNT];
+        for attr in &self.attributes {
+            let f = attr.feature as usize;
+            if f >= FEATURE_COUNT {
+                return Err(format!("feature {} out of range", attr.feature));
+            }
+            if seen[f] {
+                return Err(format!("feature {} is scored twice", FEATURE_NAMES[f]));
+            }
+            seen[f] = true;
+            let Some((last, rest)) = attr.bins.split_last() else {
+                return Err(format!("{} has no bins", FEATURE_NAMES[f]));
+            };
+            if last.upper.is_some() {
+                return Err(format!("last {} bin must be unbounded", FEATURE_NAMES[f]));
+            }
+            let mut prev = f64::NEG_INFINITY;
+            for bin in rest {
+                match bin.upper {
+                    Some(upper) if upper.is_finite() && upper > prev => prev = upper,
+                    _ => {
+                        return Err(format!(
+                            "{} bin bounds must be finite and ascending",
+                            FEATURE_NAMES[f]
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Points earned per attribute, in attribute order
+    fn points(&self, x: &[f64; FEATURE_COUNT]) -> Vec<i64> {
+        self.attributes
+            .iter()
+            .map(|attr| {
+                let value = x[attr.feature as usize];
+                attr.bins
+                    .iter()
+                    // Bins are half-open: a value equal to `upper` falls in the next bin
+                    .find(|bin| match bin.upper {
+                        Some(upper) => value < upper,
+                        None => true,
+                    })
+                    .map_or(0, |bin| bin.points as i64)
+            })
+            .collect()
+    }
+
+    /// Total score; higher is safer
+    pub fn score(&self, x: &[f64; FEATURE_COUNT]) -> i64 {
+        self.base_points as i64 + self.points(x).iter().sum::<i64>()
+    }
+
+    /// Probability of the high risk class; 0.5 at `even_odds_score`,
+    /// with the odds of repaying doubling every `points_to_double_odds`
+    pub fn predict_proba(&self, x: &[f64; FEATURE_COUNT]) -> f64 {
+        let doublings = (self.score(x) as f64 - self.even_odds_score) / self.points_to_double_odds;
+        1.0 / (1.0 + 2f64.powf(doublings))
+    }
+
+    /// Points short of each feature's best bin, the usual adverse-action reasons
+    pub fn contributions(&self, x: &[f64; FEATURE_COUNT]) -> Vec<FeatureContribution> {
+        self.attributes
+            .iter()
+            .zip(self.points(x))
+            .map(|(attr, points)| {
+                let best = attr.bins.iter().map(|b| b.points as i64).max().unwrap_or(0);
+                FeatureContribution {
+                    feature: FEATURE_NAMES[attr.feature as usize].to_string(),
+                    value: x[attr.feature as usize],
+                    contribution: (best - points) as f64,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,144 @@
+// src/ai_service_proxy/tree.rs
+use crate::types::{TreeAggregation, TreeEnsemble, TreeNode};
+use crate::FEATURE_COUNT;
+
+impl TreeEnsemble {
+    /// Check node layout so traversal always terminates inside the arrays
+    pub fn validate(&self) -> Result<(), String> {
+        if self.trees.is_empty() {
+            return Err("ensemble has no trees".to_string());
+        }
+        if !self.base_score.is_finite() {
+            return Err("base_score must be finite".to_string());
+        }
+        for (t, nodes) in self.trees.iter().enumerate() {
+            if nodes.is_empty() {
+                return Err(format!("tree {} has no nodes", t));
+            }
+            for (i, node) in nodes.iter().enumerate() {
+                if is_leaf(node) {
+                    if node.right >= 0 {
+                        return Err(format!("tree {} node {}: leaf has a right child", t, i));
+                    }
+                    if !node.value.is_finite() {
+                        return Err(format!("tree {} node {}: leaf value must be finite", t, i));
+                    }
+                    if self.aggregation == TreeAggregation::Average
+                        && !(0.0..=1.0).contains(&node.value)
+                    {
+                        return Err(format!("tree {} node {}: leaf probability outside [0, 1]", t, i));
+                    }
+                    continue;
+                }
+                if node.feature as usize >= FEATURE_COUNT {
+                    return Err(format!("tree {} node {}: feature {} out of range", t, i, node.feature));
+                }
+                if !node.threshold.is_finite() {
+                    return Err(format!("tree {} node {}: threshold must be finite", t, i));
+                }
+                // Children must point forward, which rules out cycles
+                for child in [node.left, node.right] {
+                    if child <= i as i32 || child as usize >= nodes.len() {
+                        return Err(format!("tree {} node {}: invalid child index {}", t, i, child));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compute probability of the high risk class
+    pub fn predict_proba(&self, x: &[f64; FEATURE_COUNT]) -> f64 {
+        let leaves = self.trees.iter().map(|nodes| leaf_value(nodes, x));
+        match self.aggregation {
+            TreeAggregation::GradientBoosted => {
+                let z = self.base_score + leaves.sum::<f64>();
+                1.0 / (1.0 + (-z).exp())
+            }
+            TreeAggregation::Average => leaves.sum::<f64>() / self.trees.len() as f64,
+        }
+    }
+}
+
+fn is_leaf(node: &TreeNode) -> bool {
+    node.left < 0
+}
+
+/// Walk a single tree from the root down to a leaf
+fn leaf_value(nodes: &[TreeNode], x: &[f64; FEATURE_COUNT]) -> f64 {
+    let mut idx = 0usize;
+    loop {
+        let node = &nodes[idx];
+        if is_leaf(node) {
+            return node.value;
+        }
+        idx = if x[node.feature as usize] <= node.threshold {
+            node.left as usize
+        } else {
+            node.right as usize
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(value: f64) -> TreeNode {
+        TreeNode { feature: 0, threshold: 0.0, left: -1, right: -1, value }
+    }
+
+    fn split(feature: u32, threshold: f64, left: i32, right: i32) -> TreeNode {
+        TreeNode { feature, threshold, left, right, value: 0.0 }
+    }
+
+    /// One stump on feature 1 at 0.5
+    fn stump(aggregation: TreeAggregation, low: f64, high: f64) -> TreeEnsemble {
+        TreeEnsemble {
+            trees: vec![vec![split(1, 0.5, 1, 2), leaf(low), leaf(high)]],
+            aggregation,
+            base_score: 0.0,
+            standardize: false,
+        }
+    }
+
+    #[test]
+    fn traversal_follows_thresholds() {
+        let forest = stump(TreeAggregation::Average, 0.2, 0.9);
+        assert!(forest.validate().is_ok());
+        assert_eq!(forest.predict_proba(&[0.0, 0.5, 0.0, 0.0, 0.0]), 0.2);
+        assert_eq!(forest.predict_proba(&[0.0, 0.6, 0.0, 0.0, 0.0]), 0.9);
+
+        let boosted = stump(TreeAggregation::GradientBoosted, 0.0, 2.0);
+        assert_eq!(boosted.predict_proba(&[0.0; FEATURE_COUNT]), 0.5);
+        let high = boosted.predict_proba(&[1.0; FEATURE_COUNT]);
+        assert!((high - 1.0 / (1.0 + (-2.0f64).exp())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn validate_rejects_cycles_and_dangling_children() {
+        let mut ensemble = stump(TreeAggregation::Average, 0.2, 0.9);
+        ensemble.trees[0][0].right = 0;
+        assert!(ensemble.validate().is_err());
+        ensemble.trees[0][0].right = 3;
+        assert!(ensemble.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_bad_nodes() {
+        let mut ensemble = stump(TreeAggregation::Average, 0.2, 0.9);
+        ensemble.trees[0][0].feature = FEATURE_COUNT as u32;
+        assert!(ensemble.validate().is_err());
+
+        // Forest leaves are probabilities, boosted leaves are margins
+        assert!(stump(TreeAggregation::Average, 0.2, 1.5).validate().is_err());
+        assert!(stump(TreeAggregation::GradientBoosted, 0.2, 1.5).validate().is_ok());
+        assert!(stump(TreeAggregation::GradientBoosted, 0.2, f64::NAN).validate().is_err());
+
+        let mut ensemble = stump(TreeAggregation::Average, 0.2, 0.9);
+        ensemble.trees.push(vec![]);
+        assert!(ensemble.validate().is_err());
+        ensemble.trees.clear();
+        assert!(ensemble.validate().is_err());
+    }
+}
@@ -0,0 +1,54 @@
+// src/ai_service_proxy/trend.rs
+//! Short-term price trend model over log returns: an EWMA of returns gives the
+//! drift, an AR(1) term adds mean-reverting momentum from the latest return.
+
+use crate::types::{PriceTrend, TrendDirection};
+
+/// EWMA smoothing factor for returns and squared returns
+const EWMA_ALPHA: f64 = 0.3;
+/// Expected return must exceed this share of volatility to call a direction
+const DIRECTION_THRESHOLD: f64 = 0.25;
+/// Minimum prices needed (two returns for the AR(1) estimate)
+pub const MIN_OBSERVATIONS: usize = 3;
+
+pub fn predict(token: &str, prices: &[u64]) -> PriceTrend {
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .map(|w| (w[1].max(1) as f64 / w[0].max(1) as f64).ln())
+        .collect();
+
+    let mut mean = returns[0];
+    let mut var = 0.0;
+    for r in &returns[1..] {
+        let d = r - mean;
+        mean += EWMA_ALPHA * d;
+        var = (1.0 - EWMA_ALPHA) * (var + EWMA_ALPHA * d * d);
+    }
+
+    // Lag-1 autocorrelation of returns around the EWMA mean
+    let (num, den) = returns.windows(2).fold((0.0, 0.0), |(num, den), w| {
+        (num + (w[1] - mean) * (w[0] - mean), den + (w[0] - mean).powi(2))
+    });
+    let phi = if den > 0.0 { (num / den).clamp(-1.0, 1.0) } else { 0.0 };
+
+    let last = returns[returns.len() - 1];
+    let expected = mean + phi * (last - mean);
+    let vol = var.sqrt();
+    let confidence = if vol > 0.0 { (expected.abs() / vol).min(1.0) } else { 1.0 };
+
+    let direction = if expected.abs() <= DIRECTION_THRESHOLD * vol || expected == 0.0 {
+        TrendDirection::Flat
+    } else if expected > 0.0 {
+        TrendDirection::Up
+    } else {
+        TrendDirection::Down
+    };
+
+    PriceTrend {
+        token: token.to_string(),
+        direction,
+        expected_return_bps: (expected * 10_000.0).round() as i64,
+        volatility_bps: (vol * 10_000.0).round() as u64,
+        confidence,
+    }
+}
@@ -1,5 +1,6 @@
 use candid::CandidType;
 use candid::Nat;
+use candid::Principal;
 use serde::{Serialize, Deserialize};
 
 #[derive(CandidType, Serialize, Deserialize, Clone)]
@@ -9,10 +10,692 @@ pub struct RiskRequest {
     pub borrowed: Nat,
     pub deposits: Nat,
     pub credit_score: Nat,
+    /// Advice language (ISO 639-1, e.g. "es"); English when absent or unknown
+    pub lang: Option<String>,
+    /// Account the score is recorded under for `get_risk_history`
+    pub account: Option<String>,
+}
+
+/// Risk request with fixed-point values (1e8 = 1 USD, or a volatility of 1.0)
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct RiskRequestV2 {
+    pub volatility_e8s: Nat,
+    pub collateral_e8s: Nat,
+    pub borrowed_e8s: Nat,
+    pub deposits_e8s: Nat,
+    pub credit_score: Nat,
+    /// Advice language (ISO 639-1, e.g. "es"); English when absent or unknown
+    pub lang: Option<String>,
+    /// Account the score is recorded under for `get_risk_history`
+    pub account: Option<String>,
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone)]
 pub struct RiskResponse {
     pub risk_score: u8, // 0 = safe, 1 = high risk
+    /// Calibrated probability of the high risk class
+    pub probability: f64,
+    /// Probability band the advice was chosen for
+    pub tier: RiskTier,
     pub advice: String,
+    /// Per-feature weight x standardized value (logistic regression only)
+    pub contributions: Vec<FeatureContribution>,
+    /// Features pushing hardest towards high risk, strongest first
+    pub top_drivers: Vec<String>,
+    pub recommendations: Recommendations,
+    /// Whether the model or a guardrail made the decision
+    pub decided_by: DecisionSource,
+}
+
+/// What drove a risk decision
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum DecisionSource {
+    Model,
+    /// Name of the rule that overrode the model
+    Guardrail(String),
+}
+
+/// Hard rules layered over the model (see `set_guardrails`)
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GuardrailConfig {
+    pub enabled: bool,
+    /// Deny when borrowed exceeds this fraction of collateral
+    pub max_borrow_ratio: Option<f64>,
+    /// Deny below this credit score
+    pub min_credit_score: Option<u32>,
+    /// Approve when nothing is borrowed
+    pub approve_zero_debt: bool,
+}
+
+impl Default for GuardrailConfig {
+    fn default() -> Self {
+        GuardrailConfig {
+            enabled: true,
+            max_borrow_ratio: Some(0.9),
+            min_credit_score: None,
+            approve_zero_debt: true,
+        }
+    }
+}
+
+/// Position changes that keep or bring the score under the decision
+/// threshold, each with the other features held fixed (USD)
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Recommendations {
+    /// Probability the amounts below aim for
+    pub target_probability: f64,
+    /// Further borrowing that stays approved; 0 when already high risk
+    pub max_additional_borrow_usd: f64,
+    /// Collateral to add; `None` when no top-up is enough
+    pub collateral_top_up_usd: Option<f64>,
+    /// Debt to repay; `None` when even full repayment is not enough
+    pub repay_usd: Option<f64>,
+}
+
+/// Risk band; Low and Moderate are approved (risk_score 0)
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RiskTier {
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+/// How much a single feature moved the logit
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct FeatureContribution {
+    pub feature: String,
+    pub value: f64,
+    /// Logit contribution, or points short of the best bin for scorecards
+    pub contribution: f64,
+}
+
+/// Why a risk request was rejected instead of scored
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum RiskError {
+    /// A feature was outside the range the model can meaningfully score
+    InvalidFeature {
+        feature: String,
+        value: Nat,
+        max: Nat,
+    },
+    /// Not enough observations to fit the model
+    InsufficientData {
+        feature: String,
+        required: u64,
+        got: u64,
+    },
+    /// The caller used up its daily scoring quota
+    QuotaExceeded {
+        limit: u64,
+        /// Start of the next quota day (nanoseconds)
+        resets_at: u64,
+    },
+    /// Fewer cycles were attached than the per-call price
+    InsufficientCycles {
+        required: u64,
+        attached: u64,
+    },
+}
+
+/// Which model family `risk` routes scoring through
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ModelType {
+    #[default]
+    LogisticRegression,
+    TreeEnsemble,
+    Onnx,
+    Scorecard,
+}
+
+/// Single node of a decision tree, laid out like sklearn's `tree_` arrays
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TreeNode {
+    /// Index into the feature vector; ignored for leaves
+    pub feature: u32,
+    /// Split threshold: go left when `x[feature] <= threshold`
+    pub threshold: f64,
+    /// Child node indices within the same tree, -1 marks a leaf
+    pub left: i32,
+    pub right: i32,
+    /// Leaf output (margin for boosted trees, probability for forests)
+    pub value: f64,
+}
+
+/// How per-tree leaf values are combined into a probability
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeAggregation {
+    /// sigmoid(base_score + sum of leaf margins), e.g. XGBoost / sklearn GBT
+    GradientBoosted,
+    /// Mean of leaf probabilities, e.g. random forest or a single decision tree
+    Average,
+}
+
+/// Tree ensemble uploaded as plain data
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TreeEnsemble {
+    pub trees: Vec<Vec<TreeNode>>,
+    pub aggregation: TreeAggregation,
+    pub base_score: f64,
+    /// Standardize features with the brain's means/stds before traversal
+    pub standardize: bool,
+}
+
+/// Points for raw values below `upper`; `None` closes the last bin
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ScorecardBin {
+    pub upper: Option<f64>,
+    pub points: i32,
+}
+
+/// Ascending bins over one feature, in the units `risk` receives
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ScorecardAttribute {
+    /// Index into the feature vector
+    pub feature: u32,
+    pub bins: Vec<ScorecardBin>,
+}
+
+/// Traditional points-based scorecard; higher totals are safer
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Scorecard {
+    pub base_points: i32,
+    pub attributes: Vec<ScorecardAttribute>,
+    /// Total at which the high risk probability is 0.5
+    pub even_odds_score: f64,
+    /// Points that double the odds of repaying
+    pub points_to_double_odds: f64,
+}
+
+/// Install arguments for the proxy canister
+#[derive(CandidType, Serialize, Deserialize, Clone, Default)]
+pub struct ProxyInitArgs {
+    /// Principals allowed to call scoring endpoints (typically the pool canister)
+    pub authorized_callers: Vec<Principal>,
+}
+
+/// One scored risk request, kept in the bounded scoring log
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ScoringLogEntry {
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub features: Vec<f64>,
+    pub probability: f64,
+    pub risk_score: u8,
+    pub model: ModelType,
+    /// Logistic regression version that scored the request, if any
+    pub model_version: Option<u64>,
+    /// Instructions spent scoring, the canister-side latency measure
+    pub instructions: u64,
+}
+
+/// Aggregate scoring metrics for monitoring model behavior
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScoringMetrics {
+    /// All-time number of scored requests
+    pub request_count: u64,
+    /// All-time share of requests scored safe (risk_score 0)
+    pub approval_rate: f64,
+    /// All-time mean high-risk probability
+    pub mean_probability: f64,
+    /// 95th percentile instructions over the entries still in the log
+    pub p95_latency_instructions: u64,
+    pub log_size: u64,
+}
+
+/// Repayment-history features for the credit scoring model
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CreditFeatures {
+    pub repayment_count: u64,
+    pub total_repaid_usd: Nat,
+    pub liquidation_count: u64,
+    pub account_age_days: u64,
+}
+
+/// Credit score on the familiar 300-850 scale
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CreditScoreResponse {
+    pub credit_score: u32,
+    /// Modeled probability the account repays in good standing
+    pub probability_good: f64,
+}
+
+/// Borrower and market inputs for a personalized rate quote
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct MarketFeatures {
+    pub borrower: RiskRequest,
+    /// Market utilization in basis points (0-10000)
+    pub utilization_bps: u64,
+    /// Protocol base borrow rate in basis points (0-10000)
+    pub base_rate_bps: u64,
+}
+
+/// Suggested annual borrow rate, broken down by component (basis points)
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RateSuggestion {
+    pub base_rate_bps: u64,
+    pub utilization_premium_bps: u64,
+    pub risk_premium_bps: u64,
+    pub rate_bps: u64,
+    pub probability: f64,
+}
+
+/// Market features for an NFT or illiquid token supplied by the pool
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AssetFeatures {
+    /// Collection floor / last reliable price in USD e8s
+    pub floor_price_e8s: u64,
+    /// 24h traded volume in USD e8s
+    pub volume_24h_e8s: u64,
+    /// Annualized volatility in basis points
+    pub volatility_bps: u64,
+}
+
+/// Estimated fair collateral value after a liquidity/volatility haircut
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CollateralValuation {
+    pub fair_value_e8s: u64,
+    pub haircut_bps: u64,
+    /// Floor-price units traded per day
+    pub daily_turnover: f64,
+}
+
+/// Per-user activity counters over a monitoring window
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UserEventSummary {
+    pub window_secs: u64,
+    pub deposit_count: u64,
+    pub deposit_volume_usd: u64,
+    pub borrow_count: u64,
+    pub borrow_volume_usd: u64,
+    pub repay_count: u64,
+    pub withdraw_count: u64,
+    pub crowdfund_count: u64,
+    pub crowdfund_volume_usd: u64,
+}
+
+/// What the pool should do about an account
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnomalyAction {
+    None,
+    Escalate,
+    Freeze,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AnomalyResponse {
+    /// Combined suspicion score in [0, 1]
+    pub anomaly_score: f64,
+    pub action: AnomalyAction,
+    pub reasons: Vec<String>,
+}
+
+/// Position and market state for liquidation forecasting
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LiquidationFeatures {
+    /// Collateral value / liquidation threshold, in basis points (10000 = 1.0)
+    pub health_factor_bps: u64,
+    /// Annualized collateral volatility in basis points
+    pub volatility_bps: u64,
+    /// Recent daily collateral price drift in basis points (negative = falling)
+    pub price_trend_bps: i64,
+    pub horizon_days: u32,
+}
+
+/// Graduated warning level derived from liquidation probability
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WarningLevel {
+    None,
+    Watch,
+    Warning,
+    Critical,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LiquidationForecast {
+    /// Probability of liquidation within `horizon_days`
+    pub probability: f64,
+    pub warning_level: WarningLevel,
+    /// First day at which liquidation becomes more likely than not, if within a year
+    pub days_to_danger: Option<u32>,
+}
+
+/// Recent oracle prices for one token, oldest first
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PriceSeries {
+    pub token: String,
+    pub prices_e8s: Vec<u64>,
+}
+
+/// Short-term price direction forecast
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrendDirection {
+    Up,
+    Flat,
+    Down,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PriceTrend {
+    pub token: String,
+    pub direction: TrendDirection,
+    /// Expected next-step return in basis points
+    pub expected_return_bps: i64,
+    /// EWMA per-step volatility in basis points
+    pub volatility_bps: u64,
+    /// |expected return| / volatility, capped at 1
+    pub confidence: f64,
+}
+
+/// Online learning hyperparameters and safeguards
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LearningConfig {
+    pub learning_rate: f64,
+    /// Weights and intercept are clamped to [-max_abs_weight, max_abs_weight]
+    pub max_abs_weight: f64,
+    /// Minimum pending outcomes before a timer-driven batch runs
+    pub min_batch_size: u64,
+    /// Train automatically on the timer instead of only on admin request
+    pub auto_train: bool,
+}
+
+/// Logistic regression parameters saved after each training batch
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ModelCheckpoint {
+    pub version: u64,
+    pub weights: Vec<f64>,
+    pub intercept: f64,
+    /// Standardization statistics the weights were trained against
+    pub means: Vec<f64>,
+    pub stds: Vec<f64>,
+    pub timestamp: u64,
+    /// Outcomes in the batch that produced this checkpoint
+    pub samples: u64,
+}
+
+/// Feature standardization statistics, one value per feature
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ScalingStats {
+    pub means: Vec<f64>,
+    pub stds: Vec<f64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LearningStatus {
+    pub model_version: u64,
+    pub pending_outcomes: u64,
+    pub config: LearningConfig,
+    pub checkpoint_count: u64,
+}
+
+/// One labeled loan outcome from the training dataset
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OutcomeRecord {
+    /// Model features in `RiskRequest` order
+    pub features: Vec<f64>,
+    pub defaulted: bool,
+    pub timestamp: u64,
+}
+
+/// A page of exported outcomes
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OutcomeChunk {
+    pub records: Vec<OutcomeRecord>,
+    /// Row index to request next, `None` once the export is complete
+    pub next_index: Option<u64>,
+    pub total: u64,
+}
+
+/// Confusion-matrix stats for a candidate model; positives are defaults
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BacktestReport {
+    pub model_version: u64,
+    pub labeled_samples: u64,
+    pub true_positives: u64,
+    pub false_positives: u64,
+    pub true_negatives: u64,
+    pub false_negatives: u64,
+    pub accuracy: f64,
+    pub precision: f64,
+    pub recall: f64,
+    /// Logged risk requests re-scored under the candidate
+    pub replayed_decisions: u64,
+    /// Replayed requests whose approve/reject decision would flip
+    pub changed_decisions: u64,
+}
+
+/// Mapping from raw model probability to calibrated probability
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub enum Calibration {
+    #[default]
+    None,
+    /// p = 1 / (1 + exp(a * logit(raw) + b))
+    Platt { a: f64, b: f64 },
+    /// Interpolated bins from an isotonic regression fit
+    Isotonic { thresholds: Vec<f64>, values: Vec<f64> },
+}
+
+/// Live vs training statistics for one feature
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct FeatureDrift {
+    pub feature: String,
+    pub training_mean: f64,
+    pub training_std: f64,
+    pub live_mean: f64,
+    pub live_std: f64,
+    /// |live_mean - training_mean| in training standard deviations
+    pub mean_shift: f64,
+    /// live_std / training_std
+    pub std_ratio: f64,
+    pub drifting: bool,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DriftReport {
+    /// Scored requests folded into the live statistics
+    pub samples: u64,
+    pub features: Vec<FeatureDrift>,
+    /// Whether any feature is drifting
+    pub drifting: bool,
+}
+
+/// Raised when a feature starts drifting
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DriftAlert {
+    pub timestamp: u64,
+    pub feature: String,
+    pub mean_shift: f64,
+    pub std_ratio: f64,
+}
+
+/// Traffic split between two logistic regression checkpoints
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AbTestConfig {
+    pub control_version: u64,
+    pub candidate_version: u64,
+    /// Share of requests routed to the candidate, in basis points
+    pub candidate_share_bps: u16,
+}
+
+/// Per-version results of an A/B test
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AbArmStats {
+    pub version: u64,
+    pub requests: u64,
+    pub high_risk: u64,
+    pub probability_sum: f64,
+    /// Outcomes submitted for requests this arm scored
+    pub outcomes: u64,
+    pub defaults: u64,
+    /// Outcomes where the arm's decision matched the label
+    pub correct: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AbTestStats {
+    pub config: AbTestConfig,
+    pub started_at: u64,
+    pub arms: Vec<AbArmStats>,
+}
+
+/// External LLM used to write advice; the API key is readable by node
+/// providers, so use a key scoped to this purpose
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LlmConfig {
+    pub enabled: bool,
+    /// OpenAI-compatible chat completions URL
+    pub endpoint: String,
+    pub model: String,
+    pub api_key: String,
+    pub max_response_bytes: u64,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        LlmConfig {
+            enabled: false,
+            endpoint: String::new(),
+            model: String::new(),
+            api_key: String::new(),
+            max_response_bytes: 4_096,
+        }
+    }
+}
+
+/// A fetched market value and when it was fetched
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MarketSignal {
+    pub value: f64,
+    pub updated_at: u64,
+}
+
+/// Logit terms for market signals: weight * (value - mean) / std, in
+/// [fear_greed, funding_rate, realized_volatility] order
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MarketModel {
+    pub weights: Vec<f64>,
+    pub means: Vec<f64>,
+    pub stds: Vec<f64>,
+    /// Signals older than this are ignored
+    pub max_age_secs: u64,
+}
+
+impl Default for MarketModel {
+    fn default() -> Self {
+        // Zero weights until a model trained with market inputs is uploaded
+        MarketModel {
+            weights: vec![0.0; 3],
+            means: vec![50.0, 0.0001, 0.5],
+            stds: vec![20.0, 0.0003, 0.2],
+            max_age_secs: 60 * 60,
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MarketSnapshot {
+    /// Crypto Fear & Greed index, 0-100
+    pub fear_greed: Option<MarketSignal>,
+    /// BTC perpetual funding rate per 8h interval
+    pub funding_rate: Option<MarketSignal>,
+    /// Annualized 30-day realized BTC volatility
+    pub realized_volatility: Option<MarketSignal>,
+    pub last_error: Option<String>,
+    pub model: MarketModel,
+}
+
+/// One score in an account's history
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RiskHistoryEntry {
+    pub timestamp: u64,
+    pub probability: f64,
+    pub risk_score: u8,
+    pub tier: RiskTier,
+    pub model: ModelType,
+    pub model_version: Option<u64>,
+}
+
+/// Scoring quotas and pricing
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MeteringConfig {
+    /// Daily `risk`/`risk_v2` calls for callers without an override
+    pub default_daily_calls: u64,
+    /// Per-caller daily limits, e.g. a much higher one for the pool
+    pub quota_overrides: Vec<(Principal, u64)>,
+    /// Cycles each call must attach; 0 keeps scoring free
+    pub required_cycles_per_call: u64,
+}
+
+impl Default for MeteringConfig {
+    fn default() -> Self {
+        MeteringConfig {
+            default_daily_calls: 1_000,
+            quota_overrides: vec![],
+            required_cycles_per_call: 0,
+        }
+    }
+}
+
+/// A caller's scoring consumption
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CallerUsage {
+    pub caller: Principal,
+    /// Day number (nanoseconds since epoch / one day) the daily counters cover
+    pub day: u64,
+    pub daily_limit: u64,
+    pub calls_today: u64,
+    /// Estimated cycles burned today
+    pub cycles_today: u64,
+    pub total_calls: u64,
+    pub total_cycles: u64,
+    /// Cycles attached by the caller and accepted
+    pub cycles_paid: u64,
+}
+
+/// One model input as documented by `get_model_info`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct FeatureInfo {
+    pub name: String,
+    /// Units the feature is expected in
+    pub scale: String,
+    /// Largest accepted raw value in `RiskRequest`
+    pub max: u64,
+    /// Logistic regression standardization and weight
+    pub mean: f64,
+    pub std: f64,
+    pub weight: f64,
+}
+
+/// Provenance of the model making lending decisions
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ModelInfo {
+    pub active_model: ModelType,
+    /// Logistic regression version (see `list_model_checkpoints`)
+    pub model_version: u64,
+    pub training_data: String,
+    pub features: Vec<FeatureInfo>,
+    pub intercept: f64,
+    pub calibration: Calibration,
+    /// When the active model or its parameters last changed (0 = since install)
+    pub activated_at: u64,
+    /// Hex SHA-256 of the active model's parameters
+    pub parameter_hash: Option<String>,
+    /// Points table, when the scorecard is active
+    pub scorecard: Option<Scorecard>,
+}
+
+/// Request through the IC HTTP gateway
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(CandidType, Serialize, Clone, Debug)]
+pub struct HttpResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
 }
@@ -14,6 +14,10 @@ assert_contains() {
   fi
 }
 
+echo "🧪 Running unit tests..."
+cargo test -p defi_pool_backend -p ai_service_proxy
+
+echo ""
 echo "🚀 Rebuilding and deploying canister..."
 dfx deploy defi_pool_backend
 
@@ -0,0 +1,165 @@
+// src/defi_pool_backend/accounts.rs
+//! Linked principals. Internet Identity gives a user a different principal
+//! per frontend origin; linking lets several principals act on one account
+//! (deposits, collateral, credit history). One principal proposes the link
+//! and the other confirms it, and exactly one of the two may already hold an
+//! account, which the other joins. Tokens are still minted to and pulled
+//! from whichever principal makes the call, and crowdfunding contributions
+//! stay with the principal that made them.
+use crate::types::AccountLinks;
+use crate::DeFiPool;
+
+/// How long a proposal waits for the other principal's confirmation
+const PROPOSAL_TTL_NANOS: u64 = 24 * 60 * 60 * 1_000_000_000;
+/// Principals that can be linked to one account besides its own
+const MAX_LINKED_PRINCIPALS: usize = 10;
+
+/// Account a principal acts on: the one it is linked to, or its own
+pub fn account_of(pool: &DeFiPool, principal: &str) -> String {
+    pool.account_links.get(principal).cloned().unwrap_or_else(|| principal.to_string())
+}
+
+/// Whether a principal holds account state of its own
+pub fn has_account(pool: &DeFiPool, principal: &str) -> bool {
+    pool.users.contains_key(principal)
+        || pool.stablecoin_balances.contains_key(principal)
+        || pool.collateral.contains_key(principal)
+        || pool.savings.contains_key(principal)
+        || pool.vaults.contains_key(principal)
+}
+
+fn linked(pool: &DeFiPool, account: &str) -> Vec<String> {
+    let mut linked: Vec<String> = pool
+        .account_links
+        .iter()
+        .filter(|(_, a)| a.as_str() == account)
+        .map(|(p, _)| p.clone())
+        .collect();
+    linked.sort();
+    linked
+}
+
+/// Propose linking the caller with `other`; `other` confirms with `confirm`
+pub fn propose(pool: &mut DeFiPool, caller: &str, other: &str, now: u64) -> Result<(), String> {
+    if caller == other {
+        return Err("cannot link a principal to itself".to_string());
+    }
+    if account_of(pool, caller) == account_of(pool, other) {
+        return Err("principals are already linked".to_string());
+    }
+    pool.link_proposals.retain(|_, expires_at| *expires_at > now);
+    pool.link_proposals.insert((caller.to_string(), other.to_string()), now + PROPOSAL_TTL_NANOS);
+    Ok(())
+}
+
+/// Accept `proposer`'s proposal; returns the account both now act on
+pub fn confirm(
+    pool: &mut DeFiPool,
+    caller: &str,
+    proposer: &str,
+    now: u64,
+) -> Result<String, String> {
+    let key = (proposer.to_string(), caller.to_string());
+    match pool.link_proposals.remove(&key) {
+        Some(expires_at) if expires_at > now => {}
+        Some(_) => return Err("link proposal expired".to_string()),
+        None => return Err("no link proposal from this principal".to_string()),
+    }
+
+    let (caller_account, proposer_account) = (account_of(pool, caller), account_of(pool, proposer));
+    let caller_has = has_account(pool, &caller_account);
+    let (account, joining) = match (caller_has, has_account(pool, &proposer_account)) {
+        (true, true) => return Err("both principals hold an account".to_string()),
+        (false, false) => return Err("neither principal has an account".to_string()),
+        (true, false) => (caller_account, proposer),
+        (false, true) => (proposer_account, caller),
+    };
+    if pool.account_links.contains_key(joining) {
+        return Err("principal is already linked to another account".to_string());
+    }
+    if linked(pool, &account).len() >= MAX_LINKED_PRINCIPALS {
+        return Err(format!("at most {} linked principals", MAX_LINKED_PRINCIPALS));
+    }
+    pool.account_links.insert(joining.to_string(), account.clone());
+    Ok(account)
+}
+
+/// Detach `principal` from the caller's account; it keeps nothing. Any
+/// principal of the account may unlink, except the account's own.
+pub fn unlink(pool: &mut DeFiPool, caller: &str, principal: &str) -> Result<(), String> {
+    let account = account_of(pool, caller);
+    if principal == account {
+        return Err("the account's own principal cannot be unlinked".to_string());
+    }
+    if pool.account_links.get(principal) != Some(&account) {
+        return Err("principal is not linked to this account".to_string());
+    }
+    pool.account_links.remove(principal);
+    Ok(())
+}
+
+pub fn links(pool: &DeFiPool, principal: &str) -> AccountLinks {
+    let account = account_of(pool, principal);
+    AccountLinks { linked: linked(pool, &account), account }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::UserAccount;
+
+    fn pool_with_alice() -> DeFiPool {
+        let mut pool = DeFiPool::default();
+        pool.users.insert("alice".to_string(), UserAccount::default());
+        pool
+    }
+
+    #[test]
+    fn confirmed_link_joins_the_existing_account() {
+        let mut pool = pool_with_alice();
+        propose(&mut pool, "alice", "alice-phone", 0).unwrap();
+        assert_eq!(confirm(&mut pool, "alice-phone", "alice", 1), Ok("alice".to_string()));
+        assert_eq!(account_of(&pool, "alice-phone"), "alice");
+        let AccountLinks { account, linked } = links(&pool, "alice-phone");
+        assert_eq!(account, "alice");
+        assert_eq!(linked, vec!["alice-phone".to_string()]);
+    }
+
+    #[test]
+    fn either_side_may_propose() {
+        let mut pool = pool_with_alice();
+        propose(&mut pool, "alice-phone", "alice", 0).unwrap();
+        assert_eq!(confirm(&mut pool, "alice", "alice-phone", 1), Ok("alice".to_string()));
+    }
+
+    #[test]
+    fn proposals_expire() {
+        let mut pool = pool_with_alice();
+        propose(&mut pool, "alice", "alice-phone", 0).unwrap();
+        assert!(confirm(&mut pool, "alice-phone", "alice", PROPOSAL_TTL_NANOS).is_err());
+        assert_eq!(account_of(&pool, "alice-phone"), "alice-phone");
+    }
+
+    #[test]
+    fn two_accounts_cannot_merge() {
+        let mut pool = pool_with_alice();
+        pool.users.insert("bob".to_string(), UserAccount::default());
+        propose(&mut pool, "alice", "bob", 0).unwrap();
+        assert!(confirm(&mut pool, "bob", "alice", 1).is_err());
+        // Nor can two principals without one
+        propose(&mut pool, "carol", "dave", 0).unwrap();
+        assert!(confirm(&mut pool, "dave", "carol", 1).is_err());
+        assert!(propose(&mut pool, "alice", "alice", 0).is_err());
+    }
+
+    #[test]
+    fn unlink_keeps_the_account_principal() {
+        let mut pool = pool_with_alice();
+        propose(&mut pool, "alice", "alice-phone", 0).unwrap();
+        confirm(&mut pool, "alice-phone", "alice", 1).unwrap();
+        assert!(unlink(&mut pool, "alice-phone", "alice").is_err());
+        assert!(unlink(&mut pool, "bob", "alice-phone").is_err());
+        assert!(unlink(&mut pool, "alice-phone", "alice-phone").is_ok());
+        assert_eq!(account_of(&pool, "alice-phone"), "alice-phone");
+    }
+}
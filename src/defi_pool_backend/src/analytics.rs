@@ -0,0 +1,82 @@
+// src/defi_pool_backend/analytics.rs
+//! Per-campaign funding statistics, updated on every contribution so the
+//! analytics query only copies them out: daily contribution buckets, the
+//! contribution count and a running top list of backers. Backer totals only
+//! grow, so the top list stays exact without rescanning every backer.
+use crate::types::{BackerTotal, ContributionBucket, CrowdfundAnalytics};
+use crate::{crowdfund, CrowdfundingPool};
+use candid::{CandidType, Nat};
+use num_traits::cast::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const BUCKET_SECS: u64 = 86_400;
+/// Backers kept in the top list
+const TOP_BACKERS: usize = 10;
+
+/// Running statistics of one campaign
+#[derive(CandidType, Serialize, Deserialize, Clone, Default)]
+pub struct CampaignStats {
+    buckets: BTreeMap<u64, (Nat, u64)>, // bucket start -> (amount, count)
+    contribution_count: u64,
+    top_backers: Vec<BackerTotal>,
+}
+
+/// Fold one contribution in; `backer_total` is the backer's new total
+pub fn record(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    user: &str,
+    amount: &Nat,
+    backer_total: &Nat,
+    now: u64,
+) {
+    let stats = cf.campaign_stats.entry(campaign_id).or_default();
+    stats.contribution_count += 1;
+
+    let bucket_nanos = BUCKET_SECS * 1_000_000_000;
+    let start = now - now % bucket_nanos;
+    let bucket = stats.buckets.entry(start).or_insert((Nat::from(0u64), 0));
+    bucket.0 = Nat::from(&bucket.0 .0 + &amount.0);
+    bucket.1 += 1;
+
+    let top = &mut stats.top_backers;
+    match top.iter_mut().find(|b| b.user == user) {
+        Some(backer) => backer.amount = backer_total.clone(),
+        None => top.push(BackerTotal { user: user.to_string(), amount: backer_total.clone() }),
+    }
+    top.sort_by(|a, b| b.amount.cmp(&a.amount));
+    top.truncate(TOP_BACKERS);
+}
+
+pub fn analytics(cf: &CrowdfundingPool, campaign_id: u64, now: u64) -> Option<CrowdfundAnalytics> {
+    let campaign = crowdfund::view(cf.campaigns.get(&campaign_id)?, now);
+    let empty = CampaignStats::default();
+    let stats = cf.campaign_stats.get(&campaign_id).unwrap_or(&empty);
+    let average_contribution = if stats.contribution_count == 0 {
+        Nat::from(0u64)
+    } else {
+        Nat::from(&campaign.raised.0 / stats.contribution_count)
+    };
+    let progress = &crowdfund::total_funds(&campaign).0 * 10_000u64 / &campaign.goal.0;
+    Some(CrowdfundAnalytics {
+        campaign_id,
+        token: campaign.token.clone(),
+        bucket_secs: BUCKET_SECS,
+        buckets: stats
+            .buckets
+            .iter()
+            .map(|(start, (amount, count))| ContributionBucket {
+                start: *start,
+                amount: amount.clone(),
+                count: *count,
+            })
+            .collect(),
+        unique_contributors: campaign.contributor_count,
+        contribution_count: stats.contribution_count,
+        raised: campaign.raised,
+        average_contribution,
+        largest_backers: stats.top_backers.clone(),
+        progress_bps: progress.to_u64().unwrap_or(u64::MAX),
+    })
+}
@@ -0,0 +1,541 @@
+// src/defi_pool_backend/backup.rs
+//! Off-chain backup and disaster recovery. `backup_state` candid-encodes the
+//! whole pool and crowdfunding state into a snapshot and hands it out in
+//! bounded chunks; chunk 0 takes a fresh snapshot and later chunks are cut
+//! from that same snapshot, so a backup is consistent however long it takes.
+//! `restore_state` accepts the chunks back in order and replaces all state
+//! once the last chunk arrives and the snapshot matches its hash.
+//!
+//! Maps are encoded in key order, so the encoding, and `state_hash` over it,
+//! depends only on the state: after a restore `get_state_hash` equals the
+//! backup's hash. Calls awaiting a reply (event deliveries, invoice payments)
+//! and the faucet switch, which comes from the install arguments, are not
+//! part of a snapshot. Restore onto a canister that takes no other traffic.
+//!
+//! The same snapshot carries all state across upgrades: `pre_upgrade` writes
+//! it to stable memory as `[len: u64 LE][bytes]` and `post_upgrade` applies
+//! it. A snapshot that fails to decode traps, rolling the upgrade back rather
+//! than losing the records of escrowed funds, so fields added to it later
+//! must be `Option`s.
+use crate::types::{
+    AccountFlag, Campaign, CampaignCategory, CampaignEvent, CampaignMatch, CampaignPost,
+    ContributionReceipt, CrowdfundConfig, EventSubscriber, FaucetConfig, GuardianSet, Invoice,
+    InvoiceNotice, LedgerBlock, MatchingRound, PriceTrend, RecoveryRequest, Redemption,
+    RiskRequestV2, SessionKey, StateChunk, Subscription, SubscriptionNotice, SybilConfig,
+    UserAccount, UserEventSummary, VaultConfig, Verification,
+};
+use crate::{
+    analytics, matching, rates, savings, vaults, CrowdfundingPool, DeFiPool,
+    AI_SERVICE_PROXY_PRINCIPAL, CF_POOL, GOVERNANCE_CANISTER, POOL, SHARD_REGISTRY,
+};
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_cdk::stable::{stable_grow, stable_read, stable_size, stable_write, WASM_PAGE_SIZE_IN_BYTES};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// Bytes per chunk, well under the message size limit
+const CHUNK_BYTES: usize = 1 << 20;
+
+#[derive(CandidType, Serialize, Deserialize)]
+struct PoolSnapshot {
+    users: BTreeMap<String, UserAccount>,
+    stablecoin_balances: BTreeMap<String, BTreeMap<String, Nat>>,
+    collateral: BTreeMap<String, BTreeMap<String, Nat>>,
+    usernames: BTreeMap<String, String>,
+    supported_tokens: Vec<String>,
+    token_canisters: BTreeMap<String, Principal>,
+    mint_logs: Vec<(String, String, Nat)>,
+    per_user_mint_logs: BTreeMap<String, Vec<(String, Nat)>>,
+    activity: BTreeMap<String, UserEventSummary>,
+    activity_window_start: u64,
+    account_flags: Vec<AccountFlag>,
+    price_history: BTreeMap<String, Vec<u64>>,
+    price_trends: BTreeMap<String, PriceTrend>,
+    loan_features: BTreeMap<String, RiskRequestV2>,
+    pending_outcomes: Vec<(RiskRequestV2, bool)>,
+    admitted_users: BTreeSet<String>,
+    event_subscribers: BTreeMap<u64, EventSubscriber>,
+    next_event_subscriber_id: u64,
+    account_links: BTreeMap<String, String>,
+    link_proposals: BTreeMap<(String, String), u64>,
+    sessions: BTreeMap<String, SessionKey>,
+    guardians: BTreeMap<String, GuardianSet>,
+    recoveries: BTreeMap<String, RecoveryRequest>,
+    savings: BTreeMap<String, BTreeMap<String, savings::Position>>,
+    savings_rate_bps: u64,
+    savings_reserve: BTreeMap<String, Nat>,
+    vaults: BTreeMap<String, vaults::Vault>,
+    vault_config: VaultConfig,
+    stablecoin_canister: Option<Principal>,
+    redemptions: Vec<Redemption>,
+    rate_history: BTreeMap<u64, rates::DayRates>,
+    invoices: BTreeMap<u64, Invoice>,
+    next_invoice_id: u64,
+    invoice_notices: BTreeMap<String, Vec<InvoiceNotice>>,
+    faucet_config: FaucetConfig,
+    faucet_claims: BTreeMap<(String, String), u64>,
+    ledger: Vec<LedgerBlock>,
+}
+
+#[derive(CandidType, Serialize, Deserialize)]
+struct CrowdfundingSnapshot {
+    funds: BTreeMap<String, Nat>,
+    contributors: BTreeMap<String, BTreeMap<String, Nat>>,
+    campaigns: BTreeMap<u64, Campaign>,
+    campaign_contributions: BTreeMap<u64, BTreeMap<String, Nat>>,
+    next_campaign_id: u64,
+    refunded: BTreeMap<u64, BTreeSet<String>>,
+    events: Vec<CampaignEvent>,
+    config: CrowdfundConfig,
+    milestone_voters: BTreeMap<(u64, u32), BTreeSet<String>>,
+    refund_queue: Vec<(u64, String)>,
+    rounds: BTreeMap<u64, MatchingRound>,
+    next_round_id: u64,
+    round_contributions: BTreeMap<(u64, u64), BTreeMap<String, Nat>>,
+    round_matches: BTreeMap<u64, Vec<CampaignMatch>>,
+    round_stakes: BTreeMap<u64, BTreeMap<Principal, matching::Stake>>,
+    receipts: BTreeMap<u64, ContributionReceipt>,
+    receipt_index: BTreeMap<(u64, String), u64>,
+    next_receipt_id: u64,
+    posts: BTreeMap<u64, CampaignPost>,
+    next_post_id: u64,
+    subscriptions: BTreeMap<u64, Subscription>,
+    next_subscription_id: u64,
+    subscription_notices: BTreeMap<String, Vec<SubscriptionNotice>>,
+    category_index: BTreeMap<CampaignCategory, BTreeSet<u64>>,
+    tag_index: BTreeMap<String, BTreeSet<u64>>,
+    verifications: BTreeMap<String, Verification>,
+    sybil_config: SybilConfig,
+    campaign_stats: BTreeMap<u64, analytics::CampaignStats>,
+    unpaid_fees: Option<BTreeMap<String, Nat>>,
+}
+
+#[derive(CandidType, Serialize, Deserialize)]
+struct StateSnapshot {
+    pool: PoolSnapshot,
+    crowdfunding: CrowdfundingSnapshot,
+    ai_service_proxy: Option<Principal>,
+    shard_registry: Option<Principal>,
+    governance_canister: Option<Principal>,
+}
+
+/// Encoded snapshot being handed out by `backup_state`
+struct Backup {
+    bytes: Vec<u8>,
+    state_hash: String,
+    taken_at: u64,
+}
+
+/// Chunks received so far by `restore_state`
+struct Restore {
+    total_chunks: u64,
+    state_hash: String,
+    bytes: Vec<u8>,
+    next_index: u64,
+}
+
+static BACKUP: Lazy<Mutex<Option<Backup>>> = Lazy::new(|| Mutex::new(None));
+static RESTORE: Lazy<Mutex<Option<Restore>>> = Lazy::new(|| Mutex::new(None));
+
+fn sorted<K: Ord + Clone, V: Clone>(map: &HashMap<K, V>) -> BTreeMap<K, V> {
+    map.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+fn sorted_nested<K: Ord + Clone, K2: Ord + Clone, V: Clone>(
+    map: &HashMap<K, HashMap<K2, V>>,
+) -> BTreeMap<K, BTreeMap<K2, V>> {
+    map.iter().map(|(k, inner)| (k.clone(), sorted(inner))).collect()
+}
+
+fn sorted_sets<K: Ord + Clone, T: Ord + Clone>(
+    map: &HashMap<K, HashSet<T>>,
+) -> BTreeMap<K, BTreeSet<T>> {
+    map.iter().map(|(k, set)| (k.clone(), set.iter().cloned().collect())).collect()
+}
+
+fn unsorted<K: Hash + Eq, V>(map: BTreeMap<K, V>) -> HashMap<K, V> {
+    map.into_iter().collect()
+}
+
+fn unsorted_nested<K: Hash + Eq, K2: Hash + Eq, V>(
+    map: BTreeMap<K, BTreeMap<K2, V>>,
+) -> HashMap<K, HashMap<K2, V>> {
+    map.into_iter().map(|(k, inner)| (k, unsorted(inner))).collect()
+}
+
+fn unsorted_sets<K: Hash + Eq, T: Hash + Eq>(
+    map: BTreeMap<K, BTreeSet<T>>,
+) -> HashMap<K, HashSet<T>> {
+    map.into_iter().map(|(k, set)| (k, set.into_iter().collect())).collect()
+}
+
+fn pool_snapshot(pool: &DeFiPool) -> PoolSnapshot {
+    PoolSnapshot {
+        users: sorted(&pool.users),
+        stablecoin_balances: sorted_nested(&pool.stablecoin_balances),
+        collateral: sorted_nested(&pool.collateral),
+        usernames: sorted(&pool.usernames),
+        supported_tokens: pool.supported_tokens.clone(),
+        token_canisters: sorted(&pool.token_canisters),
+        mint_logs: pool.mint_logs.clone(),
+        per_user_mint_logs: sorted(&pool.per_user_mint_logs),
+        activity: sorted(&pool.activity),
+        activity_window_start: pool.activity_window_start,
+        account_flags: pool.account_flags.clone(),
+        price_history: sorted(&pool.price_history),
+        price_trends: sorted(&pool.price_trends),
+        loan_features: sorted(&pool.loan_features),
+        pending_outcomes: pool.pending_outcomes.clone(),
+        admitted_users: pool.admitted_users.iter().cloned().collect(),
+        event_subscribers: pool.event_subscribers.clone(),
+        next_event_subscriber_id: pool.next_event_subscriber_id,
+        account_links: sorted(&pool.account_links),
+        link_proposals: sorted(&pool.link_proposals),
+        sessions: sorted(&pool.sessions),
+        guardians: sorted(&pool.guardians),
+        recoveries: sorted(&pool.recoveries),
+        savings: sorted_nested(&pool.savings),
+        savings_rate_bps: pool.savings_rate_bps,
+        savings_reserve: sorted(&pool.savings_reserve),
+        vaults: sorted(&pool.vaults),
+        vault_config: pool.vault_config.clone(),
+        stablecoin_canister: pool.stablecoin_canister,
+        redemptions: pool.redemptions.clone(),
+        rate_history: pool.rate_history.clone(),
+        invoices: pool.invoices.clone(),
+        next_invoice_id: pool.next_invoice_id,
+        invoice_notices: sorted(&pool.invoice_notices),
+        faucet_config: pool.faucet_config.clone(),
+        faucet_claims: sorted(&pool.faucet_claims),
+        ledger: pool.ledger.clone(),
+    }
+}
+
+fn crowdfunding_snapshot(cf: &CrowdfundingPool) -> CrowdfundingSnapshot {
+    CrowdfundingSnapshot {
+        funds: sorted(&cf.funds),
+        contributors: sorted_nested(&cf.contributors),
+        campaigns: cf.campaigns.clone(),
+        campaign_contributions: sorted_nested(&cf.campaign_contributions),
+        next_campaign_id: cf.next_campaign_id,
+        refunded: sorted_sets(&cf.refunded),
+        events: cf.events.clone(),
+        config: cf.config.clone(),
+        milestone_voters: sorted_sets(&cf.milestone_voters),
+        refund_queue: cf.refund_queue.iter().cloned().collect(),
+        rounds: cf.rounds.clone(),
+        next_round_id: cf.next_round_id,
+        round_contributions: sorted_nested(&cf.round_contributions),
+        round_matches: sorted(&cf.round_matches),
+        round_stakes: sorted(&cf.round_stakes),
+        receipts: cf.receipts.clone(),
+        receipt_index: sorted(&cf.receipt_index),
+        next_receipt_id: cf.next_receipt_id,
+        posts: cf.posts.clone(),
+        next_post_id: cf.next_post_id,
+        subscriptions: cf.subscriptions.clone(),
+        next_subscription_id: cf.next_subscription_id,
+        subscription_notices: sorted(&cf.subscription_notices),
+        category_index: sorted(&cf.category_index),
+        tag_index: sorted(&cf.tag_index),
+        verifications: sorted(&cf.verifications),
+        sybil_config: cf.sybil_config.clone(),
+        campaign_stats: sorted(&cf.campaign_stats),
+        unpaid_fees: Some(sorted(&cf.unpaid_fees)),
+    }
+}
+
+fn encode() -> Vec<u8> {
+    let snapshot = StateSnapshot {
+        pool: pool_snapshot(&POOL.lock().unwrap()),
+        crowdfunding: crowdfunding_snapshot(&CF_POOL.lock().unwrap()),
+        ai_service_proxy: *AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap(),
+        shard_registry: *SHARD_REGISTRY.lock().unwrap(),
+        governance_canister: *GOVERNANCE_CANISTER.lock().unwrap(),
+    };
+    Encode!(&snapshot).expect("failed to encode pool state")
+}
+
+fn hex_sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hex SHA-256 of the current state as `backup_state` would encode it
+pub fn state_hash() -> String {
+    hex_sha256(&encode())
+}
+
+/// Chunk `index` of the backup; chunk 0 takes a new snapshot
+pub fn backup_chunk(index: u64, now: u64) -> Result<StateChunk, String> {
+    let mut backup = BACKUP.lock().unwrap();
+    if index == 0 {
+        let bytes = encode();
+        let state_hash = hex_sha256(&bytes);
+        *backup = Some(Backup { bytes, state_hash, taken_at: now });
+    }
+    let backup = backup.as_ref().ok_or("no backup in progress, start at chunk 0")?;
+    let total_chunks = backup.bytes.len().div_ceil(CHUNK_BYTES).max(1) as u64;
+    if index >= total_chunks {
+        return Err(format!("backup has {} chunks", total_chunks));
+    }
+    let start = index as usize * CHUNK_BYTES;
+    let end = (start + CHUNK_BYTES).min(backup.bytes.len());
+    Ok(StateChunk {
+        index,
+        total_chunks,
+        state_hash: backup.state_hash.clone(),
+        taken_at: backup.taken_at,
+        bytes: backup.bytes[start..end].to_vec(),
+    })
+}
+
+/// Take chunks back in order; the last one replaces all state and returns
+/// the restored state's hash
+pub fn restore_chunk(chunk: StateChunk) -> Result<Option<String>, String> {
+    if chunk.bytes.len() > CHUNK_BYTES {
+        return Err(format!("chunks are at most {} bytes", CHUNK_BYTES));
+    }
+    let mut restore = RESTORE.lock().unwrap();
+    if chunk.index == 0 {
+        *restore = Some(Restore {
+            total_chunks: chunk.total_chunks,
+            state_hash: chunk.state_hash.clone(),
+            bytes: Vec::new(),
+            next_index: 0,
+        });
+    }
+    let pending = restore.as_mut().ok_or("no restore in progress, start at chunk 0")?;
+    if chunk.index != pending.next_index {
+        return Err(format!("expected chunk {}", pending.next_index));
+    }
+    if chunk.total_chunks != pending.total_chunks || chunk.state_hash != pending.state_hash {
+        return Err("chunk belongs to a different backup".to_string());
+    }
+    pending.bytes.extend_from_slice(&chunk.bytes);
+    pending.next_index += 1;
+    if pending.next_index < pending.total_chunks {
+        return Ok(None);
+    }
+
+    let Some(pending) = restore.take() else { return Ok(None) };
+    if hex_sha256(&pending.bytes) != pending.state_hash {
+        return Err("backup does not match its hash".to_string());
+    }
+    let snapshot = Decode!(&pending.bytes, StateSnapshot).map_err(|e| e.to_string())?;
+    apply(snapshot);
+    Ok(Some(state_hash()))
+}
+
+/// Write the current state to stable memory ahead of an upgrade
+pub fn save_for_upgrade() {
+    let bytes = encode();
+    let pages = (bytes.len() as u64 + 8).div_ceil(WASM_PAGE_SIZE_IN_BYTES);
+    let current = stable_size();
+    if pages > current {
+        stable_grow(pages - current).expect("failed to grow stable memory");
+    }
+    stable_write(0, &(bytes.len() as u64).to_le_bytes());
+    stable_write(8, &bytes);
+}
+
+/// Apply the state `save_for_upgrade` wrote; empty stable memory (an upgrade
+/// from a release that saved nothing) keeps the defaults
+pub fn load_after_upgrade() {
+    if stable_size() == 0 {
+        return;
+    }
+    let mut len = [0u8; 8];
+    stable_read(0, &mut len);
+    let len = u64::from_le_bytes(len);
+    if len == 0 {
+        return;
+    }
+    let mut bytes = vec![0u8; len as usize];
+    stable_read(8, &mut bytes);
+    let snapshot = Decode!(&bytes, StateSnapshot).expect("failed to decode pool state");
+    apply(snapshot);
+}
+
+fn apply(snapshot: StateSnapshot) {
+    let p = snapshot.pool;
+    {
+        let mut pool = POOL.lock().unwrap();
+        let faucet_enabled = pool.faucet_enabled;
+        *pool = DeFiPool {
+            users: unsorted(p.users),
+            stablecoin_balances: unsorted_nested(p.stablecoin_balances),
+            collateral: unsorted_nested(p.collateral),
+            usernames: unsorted(p.usernames),
+            supported_tokens: p.supported_tokens,
+            token_canisters: unsorted(p.token_canisters),
+            mint_logs: p.mint_logs,
+            per_user_mint_logs: unsorted(p.per_user_mint_logs),
+            activity: unsorted(p.activity),
+            activity_window_start: p.activity_window_start,
+            account_flags: p.account_flags,
+            price_history: unsorted(p.price_history),
+            price_trends: unsorted(p.price_trends),
+            loan_features: unsorted(p.loan_features),
+            pending_outcomes: p.pending_outcomes,
+            admitted_users: p.admitted_users.into_iter().collect(),
+            event_subscribers: p.event_subscribers,
+            next_event_subscriber_id: p.next_event_subscriber_id,
+            events_in_flight: HashSet::new(),
+            account_links: unsorted(p.account_links),
+            link_proposals: unsorted(p.link_proposals),
+            sessions: unsorted(p.sessions),
+            guardians: unsorted(p.guardians),
+            recoveries: unsorted(p.recoveries),
+            savings: unsorted_nested(p.savings),
+            savings_rate_bps: p.savings_rate_bps,
+            savings_reserve: unsorted(p.savings_reserve),
+            vaults: unsorted(p.vaults),
+            vault_config: p.vault_config,
+            stablecoin_canister: p.stablecoin_canister,
+            redemptions: p.redemptions,
+            rate_history: p.rate_history,
+            invoices: p.invoices,
+            next_invoice_id: p.next_invoice_id,
+            invoices_in_flight: HashSet::new(),
+            invoice_notices: unsorted(p.invoice_notices),
+            faucet_enabled,
+            faucet_config: p.faucet_config,
+            faucet_claims: unsorted(p.faucet_claims),
+            ledger: p.ledger,
+        };
+    }
+
+    let c = snapshot.crowdfunding;
+    *CF_POOL.lock().unwrap() = CrowdfundingPool {
+        funds: unsorted(c.funds),
+        contributors: unsorted_nested(c.contributors),
+        campaigns: c.campaigns,
+        campaign_contributions: unsorted_nested(c.campaign_contributions),
+        next_campaign_id: c.next_campaign_id,
+        refunded: unsorted_sets(c.refunded),
+        events: c.events,
+        config: c.config,
+        milestone_voters: unsorted_sets(c.milestone_voters),
+        refund_queue: c.refund_queue.into_iter().collect(),
+        rounds: c.rounds,
+        next_round_id: c.next_round_id,
+        round_contributions: unsorted_nested(c.round_contributions),
+        round_matches: unsorted(c.round_matches),
+        round_stakes: unsorted(c.round_stakes),
+        receipts: c.receipts,
+        receipt_index: unsorted(c.receipt_index),
+        next_receipt_id: c.next_receipt_id,
+        posts: c.posts,
+        next_post_id: c.next_post_id,
+        subscriptions: c.subscriptions,
+        next_subscription_id: c.next_subscription_id,
+        subscription_notices: unsorted(c.subscription_notices),
+        category_index: unsorted(c.category_index),
+        tag_index: unsorted(c.tag_index),
+        verifications: unsorted(c.verifications),
+        sybil_config: c.sybil_config,
+        campaign_stats: unsorted(c.campaign_stats),
+        unpaid_fees: unsorted(c.unpaid_fees.unwrap_or_default()),
+    };
+
+    *AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap() = snapshot.ai_service_proxy;
+    *SHARD_REGISTRY.lock().unwrap() = snapshot.shard_registry;
+    *GOVERNANCE_CANISTER.lock().unwrap() = snapshot.governance_canister;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crowdfund::{self, tests::campaign};
+    use crate::types::{BalanceKind, SessionPermission, TransactionKind};
+    use crate::{ledger, sessions};
+
+    /// Tests here share the global state and the backup in progress
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    fn populate() {
+        let mut pool = POOL.lock().unwrap();
+        pool.users.insert("alice".to_string(), UserAccount::default());
+        let balances = pool.stablecoin_balances.entry("alice".to_string()).or_default();
+        balances.insert("ICP".to_string(), Nat::from(1_000u64));
+        vaults::add_collateral(&mut pool, "alice", "ICP", &Nat::from(500u64), 1);
+        let permissions = vec![SessionPermission::Repay];
+        sessions::authorize(&mut pool, "alice", "bot", permissions, Some(50), 60, 1).unwrap();
+        let credit = ledger::credit("alice", BalanceKind::Pool, "ICP", &Nat::from(1_000u64));
+        ledger::record(&mut pool, TransactionKind::Deposit, vec![credit], 1);
+        pool.events_in_flight.insert(1);
+        pool.invoices_in_flight.insert(1);
+
+        let mut cf = CF_POOL.lock().unwrap();
+        let id = campaign(&mut cf, 1_000);
+        crowdfund::contribute(&mut cf, id, "alice", &Nat::from(300u64), 2).unwrap();
+        crowdfund::owe_fee(&mut cf, "ICP", &Nat::from(3u64));
+        *SHARD_REGISTRY.lock().unwrap() = Some(Principal::anonymous());
+    }
+
+    fn take_backup() -> Vec<StateChunk> {
+        let first = backup_chunk(0, 5).unwrap();
+        let rest = (1..first.total_chunks).map(|index| backup_chunk(index, 5).unwrap());
+        std::iter::once(first).chain(rest).collect()
+    }
+
+    fn wipe() {
+        *POOL.lock().unwrap() = DeFiPool::default();
+        *CF_POOL.lock().unwrap() = CrowdfundingPool::default();
+        *SHARD_REGISTRY.lock().unwrap() = None;
+    }
+
+    #[test]
+    fn restore_reproduces_the_backed_up_state() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        wipe();
+        populate();
+        let hash = state_hash();
+        let chunks = take_backup();
+        assert!(chunks.iter().all(|c| c.state_hash == hash));
+
+        wipe();
+        assert_ne!(state_hash(), hash);
+        let mut restored = None;
+        for chunk in chunks {
+            restored = restore_chunk(chunk).unwrap();
+        }
+        assert_eq!(restored, Some(hash.clone()));
+        assert_eq!(state_hash(), hash);
+
+        let pool = POOL.lock().unwrap();
+        assert_eq!(pool.stablecoin_balances["alice"]["ICP"], Nat::from(1_000u64));
+        assert_eq!(pool.sessions["bot"].daily_limit_usd, Some(50));
+        assert!(pool.events_in_flight.is_empty() && pool.invoices_in_flight.is_empty());
+        assert_eq!(CF_POOL.lock().unwrap().unpaid_fees["ICP"], Nat::from(3u64));
+        assert_eq!(*SHARD_REGISTRY.lock().unwrap(), Some(Principal::anonymous()));
+    }
+
+    #[test]
+    fn restore_rejects_a_tampered_backup() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        wipe();
+        populate();
+        let hash = state_hash();
+        let mut chunks = take_backup();
+        let last = chunks.last_mut().unwrap();
+        *last.bytes.last_mut().unwrap() ^= 1;
+
+        wipe();
+        let mut result = Ok(None);
+        for chunk in chunks {
+            result = restore_chunk(chunk);
+        }
+        assert_eq!(result, Err("backup does not match its hash".to_string()));
+        assert_ne!(state_hash(), hash);
+
+        // Chunks out of order are refused
+        populate();
+        let chunk = StateChunk { index: 1, ..take_backup().remove(0) };
+        assert!(restore_chunk(chunk).is_err());
+    }
+}
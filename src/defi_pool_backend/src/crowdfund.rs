@@ -0,0 +1,936 @@
+// src/defi_pool_backend/crowdfund.rs
+//! Crowdfunding campaigns: each campaign raises one token towards a goal
+//! before a deadline. Status is settled lazily: the first update touching a
+//! campaign after its deadline moves it to Successful or Failed, and queries
+//! report the status the campaign would settle to. A timer settles every
+//! expired campaign so backers of failed ones can claim refunds.
+//!
+//! Contributions are pulled from the backer with `transferFrom` and held in
+//! the pool canister's own token balance; each campaign's `escrowed` tracks
+//! its part of that balance until it is paid out or refunded.
+//!
+//! A creator can cancel an active campaign, and an arbiter can freeze a
+//! campaign under dispute and force refunds. Both settle the campaign as
+//! Failed and queue a refund for every backer, paid out by the timer.
+//!
+//! A campaign may split its funds into milestones. After success each
+//! milestone is released only once a contribution-weighted backer vote
+//! passes: more weight for than against, with at least a quorum of the
+//! contributed weight voting. A failed vote fails the campaign and refunds
+//! the remaining escrow pro rata.
+use crate::types::{
+    Campaign, CampaignEvent, CampaignEventKind, CampaignFilter, CampaignMetadata, CampaignPage,
+    CampaignSort, CampaignStatus, CrowdfundConfig, CrowdfundEntry, Milestone, MilestoneSpec,
+    MilestoneStatus,
+};
+use crate::{analytics, metadata, CrowdfundingPool};
+use candid::{Nat, Principal};
+use num_bigint::BigUint;
+use std::cmp::Reverse;
+use std::time::Duration;
+
+/// How often expired campaigns are settled
+pub const SETTLE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub const MAX_TITLE_LEN: usize = 120;
+pub const MAX_DESCRIPTION_LEN: usize = 5_000;
+/// Highest protocol fee an admin can set (10%)
+const MAX_PROTOCOL_FEE_BPS: u64 = 1_000;
+/// Longest a campaign may run
+const MAX_DURATION_NANOS: u64 = 365 * 86_400 * 1_000_000_000;
+pub const MAX_MILESTONES: usize = 20;
+/// Largest page `search` returns
+const MAX_PAGE_SIZE: u64 = 100;
+/// How long backers have to vote on a milestone release
+const MILESTONE_VOTE_NANOS: u64 = 3 * 86_400 * 1_000_000_000;
+/// Share of contributed weight that must vote for a milestone vote to count
+const MILESTONE_QUORUM_BPS: u64 = 2_000;
+
+/// Validate and register a new campaign; returns its ID
+pub fn create(
+    cf: &mut CrowdfundingPool,
+    creator: Principal,
+    title: String,
+    description: String,
+    token: String,
+    goal: Nat,
+    deadline: u64,
+) -> Result<u64, String> {
+    let now = ic_cdk::api::time();
+    if title.trim().is_empty() || title.len() > MAX_TITLE_LEN {
+        return Err(format!("title must be 1-{} bytes", MAX_TITLE_LEN));
+    }
+    if description.len() > MAX_DESCRIPTION_LEN {
+        return Err(format!("description exceeds {} bytes", MAX_DESCRIPTION_LEN));
+    }
+    if goal == 0u64 {
+        return Err("goal must be positive".to_string());
+    }
+    if deadline <= now || deadline - now > MAX_DURATION_NANOS {
+        return Err("deadline must be in the future and within a year".to_string());
+    }
+
+    cf.next_campaign_id += 1;
+    let id = cf.next_campaign_id;
+    cf.campaigns.insert(
+        id,
+        Campaign {
+            id,
+            creator,
+            title,
+            description,
+            token,
+            goal,
+            raised: Nat::from(0u64),
+            deadline,
+            created_at: now,
+            status: CampaignStatus::Active,
+            contributor_count: 0,
+            paid_out: false,
+            milestones: vec![],
+            released: Nat::from(0u64),
+            matched: Nat::from(0u64),
+            escrowed: Nat::from(0u64),
+            metadata: CampaignMetadata::default(),
+            contribution_cap: None,
+            disputed: false,
+        },
+    );
+    Ok(id)
+}
+
+/// Contributions plus matching funds
+pub fn total_funds(campaign: &Campaign) -> Nat {
+    Nat::from(&campaign.raised.0 + &campaign.matched.0)
+}
+
+/// `a - b`, or zero when `b` is larger
+pub fn saturating_sub(a: &Nat, b: &Nat) -> Nat {
+    if a > b {
+        Nat::from(&a.0 - &b.0)
+    } else {
+        Nat::from(0u64)
+    }
+}
+
+/// Status once the deadline is taken into account
+fn effective_status(campaign: &Campaign, now: u64) -> CampaignStatus {
+    match campaign.status {
+        CampaignStatus::Active if now >= campaign.deadline => {
+            if total_funds(campaign) >= campaign.goal {
+                CampaignStatus::Successful
+            } else {
+                CampaignStatus::Failed
+            }
+        }
+        status => status,
+    }
+}
+
+/// Persist the deadline transition and close an ended milestone vote, logging both
+fn settle(events: &mut Vec<CampaignEvent>, campaign: &mut Campaign, now: u64) {
+    let status = effective_status(campaign, now);
+    if status != campaign.status {
+        set_status(events, campaign, status, now);
+    }
+
+    let ended = campaign.milestones.iter().position(|m| {
+        m.status == MilestoneStatus::Voting && m.vote_ends_at.is_some_and(|end| now >= end)
+    });
+    if let Some(index) = ended {
+        let raised = campaign.raised.clone();
+        let milestone = &mut campaign.milestones[index];
+        let approved = vote_passes(milestone, &raised);
+        milestone.status =
+            if approved { MilestoneStatus::Approved } else { MilestoneStatus::Rejected };
+        events.push(CampaignEvent {
+            campaign_id: campaign.id,
+            timestamp: now,
+            kind: CampaignEventKind::MilestoneResolved { index: index as u32, approved },
+        });
+        if !approved {
+            set_status(events, campaign, CampaignStatus::Failed, now);
+        }
+    }
+}
+
+/// Whether a closed vote approves its milestone: a strict majority of the
+/// weight cast, with turnout of at least the quorum share of `raised`
+fn vote_passes(milestone: &Milestone, raised: &Nat) -> bool {
+    let turnout = &milestone.votes_for.0 + &milestone.votes_against.0;
+    let quorum = &raised.0 * MILESTONE_QUORUM_BPS;
+    turnout * 10_000u64 >= quorum && milestone.votes_for > milestone.votes_against
+}
+
+fn set_status(
+    events: &mut Vec<CampaignEvent>,
+    campaign: &mut Campaign,
+    status: CampaignStatus,
+    now: u64,
+) {
+    campaign.status = status;
+    events.push(CampaignEvent {
+        campaign_id: campaign.id,
+        timestamp: now,
+        kind: CampaignEventKind::Settled { status },
+    });
+}
+
+/// Timer entry point: settle every campaign past its deadline
+pub fn settle_expired(cf: &mut CrowdfundingPool, now: u64) {
+    for campaign in cf.campaigns.values_mut() {
+        settle(&mut cf.events, campaign, now);
+    }
+}
+
+/// A campaign as queries should see it
+pub fn view(campaign: &Campaign, now: u64) -> Campaign {
+    let mut campaign = campaign.clone();
+    settle(&mut vec![], &mut campaign, now);
+    campaign
+}
+
+/// Filter, sort and page campaigns by their settled view
+pub fn search(
+    cf: &CrowdfundingPool,
+    filter: &CampaignFilter,
+    sort: CampaignSort,
+    offset: u64,
+    limit: u64,
+    now: u64,
+) -> CampaignPage {
+    let text = filter.text.as_ref().map(|t| t.to_lowercase());
+    let indexed = metadata::candidates(cf, filter.category, filter.tag.as_deref());
+    let candidates: Box<dyn Iterator<Item = &Campaign>> = match &indexed {
+        Some(ids) => Box::new(ids.iter().filter_map(|id| cf.campaigns.get(id))),
+        None => Box::new(cf.campaigns.values()),
+    };
+    let mut matches: Vec<Campaign> = candidates
+        .map(|c| view(c, now))
+        .filter(|c| filter.status.is_none_or(|s| c.status == s))
+        .filter(|c| filter.token.as_ref().is_none_or(|t| &c.token == t))
+        .filter(|c| {
+            text.as_ref().is_none_or(|t| {
+                c.title.to_lowercase().contains(t)
+                    || c.description.to_lowercase().contains(t)
+                    || c.metadata.tags.iter().any(|tag| tag.contains(t))
+            })
+        })
+        .collect();
+
+    match sort {
+        CampaignSort::Newest => matches.sort_by_key(|c| Reverse(c.created_at)),
+        CampaignSort::EndingSoon => matches.sort_by_key(|c| c.deadline),
+        CampaignSort::MostRaised => matches.sort_by(|a, b| b.raised.cmp(&a.raised)),
+        CampaignSort::MostBackers => matches.sort_by_key(|c| Reverse(c.contributor_count)),
+        // Compare funds/goal cross-multiplied to stay in integers
+        CampaignSort::MostFunded => matches.sort_by(|a, b| {
+            (&total_funds(b).0 * &a.goal.0).cmp(&(&total_funds(a).0 * &b.goal.0))
+        }),
+    }
+
+    let total = matches.len() as u64;
+    let campaigns = matches
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit.min(MAX_PAGE_SIZE) as usize)
+        .collect();
+    CampaignPage { campaigns, total }
+}
+
+/// Check a campaign still takes this contribution from `user`; returns its token
+pub fn check_open(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    user: &str,
+    amount: &Nat,
+    now: u64,
+) -> Result<String, String> {
+    if *amount == 0u64 {
+        return Err("amount must be positive".to_string());
+    }
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    settle(&mut cf.events, campaign, now);
+    if campaign.status != CampaignStatus::Active {
+        return Err(format!("campaign is {:?}", campaign.status));
+    }
+    if campaign.disputed {
+        return Err("campaign is under dispute".to_string());
+    }
+    if let Some(cap) = &campaign.contribution_cap {
+        let so_far = cf
+            .campaign_contributions
+            .get(&campaign_id)
+            .and_then(|backers| backers.get(user))
+            .map_or(BigUint::from(0u64), |n| n.0.clone());
+        if so_far + &amount.0 > cap.0 {
+            return Err(format!("contributions are capped at {} per backer", cap));
+        }
+    }
+    Ok(campaign.token.clone())
+}
+
+/// Cap what any one principal may put into a campaign; `None` lifts the cap
+pub fn set_contribution_cap(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    caller: Principal,
+    cap: Option<Nat>,
+    now: u64,
+) -> Result<(), String> {
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    if campaign.creator != caller {
+        return Err("only the campaign creator can set the cap".to_string());
+    }
+    settle(&mut cf.events, campaign, now);
+    if campaign.status != CampaignStatus::Active {
+        return Err(format!("campaign is {:?}", campaign.status));
+    }
+    if cap.as_ref().is_some_and(|c| *c == 0u64) {
+        return Err("cap must be positive".to_string());
+    }
+    campaign.contribution_cap = cap;
+    Ok(())
+}
+
+/// Record a contribution whose tokens are already escrowed; returns the
+/// campaign's token
+pub fn contribute(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    user: &str,
+    amount: &Nat,
+    now: u64,
+) -> Result<String, String> {
+    let token = check_open(cf, campaign_id, user, amount, now)?;
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    campaign.raised = Nat::from(&campaign.raised.0 + &amount.0);
+    campaign.escrowed = Nat::from(&campaign.escrowed.0 + &amount.0);
+
+    let backers = cf.campaign_contributions.entry(campaign_id).or_default();
+    let entry = backers.entry(user.to_string()).or_insert_with(|| Nat::from(0u64));
+    if *entry == 0u64 {
+        campaign.contributor_count += 1;
+    }
+    *entry = Nat::from(&entry.0 + &amount.0);
+    let backer_total = entry.clone();
+    analytics::record(cf, campaign_id, user, amount, &backer_total, now);
+
+    // Token-wide totals behind `get_crowdfund_status`
+    let total = cf.funds.entry(token.clone()).or_insert(Nat::from(0u64));
+    *total = Nat::from(&total.0 + &amount.0);
+    let contribs = cf.contributors.entry(user.to_string()).or_default();
+    let entry = contribs.entry(token.clone()).or_insert(Nat::from(0u64));
+    *entry = Nat::from(&entry.0 + &amount.0);
+
+    Ok(token)
+}
+
+/// Every backer of a campaign and how much they put in
+pub fn contributions(cf: &CrowdfundingPool, campaign_id: u64) -> Vec<CrowdfundEntry> {
+    let Some(campaign) = cf.campaigns.get(&campaign_id) else {
+        return vec![];
+    };
+    cf.campaign_contributions
+        .get(&campaign_id)
+        .map(|backers| {
+            backers
+                .iter()
+                .map(|(user, amount)| CrowdfundEntry {
+                    user: user.clone(),
+                    token: campaign.token.clone(),
+                    amount: amount.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Take the caller's refund out of a failed campaign; returns the campaign's
+/// token and the amount. A second claim fails, so refunds are paid once.
+pub fn begin_refund(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    user: &str,
+    now: u64,
+) -> Result<(String, Nat), String> {
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    settle(&mut cf.events, campaign, now);
+    if campaign.status != CampaignStatus::Failed {
+        return Err(format!("campaign is {:?}, refunds need Failed", campaign.status));
+    }
+    let contributed = cf
+        .campaign_contributions
+        .get(&campaign_id)
+        .and_then(|backers| backers.get(user))
+        .cloned()
+        .ok_or("no contribution to refund")?;
+    if !cf.refunded.entry(campaign_id).or_default().insert(user.to_string()) {
+        return Err("refund already claimed".to_string());
+    }
+    // Pro rata share of whatever milestones did not release; releases draw on
+    // contributions first, matching funds go back to the round sponsor
+    let remaining = saturating_sub(&campaign.raised, &campaign.released);
+    let amount = Nat::from(&contributed.0 * &remaining.0 / &campaign.raised.0);
+    Ok((campaign.token.clone(), amount))
+}
+
+/// Log a paid refund and take it out of the campaign's escrow
+pub fn complete_refund(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    user: &str,
+    amount: Nat,
+    now: u64,
+) {
+    if let Some(campaign) = cf.campaigns.get_mut(&campaign_id) {
+        campaign.escrowed = saturating_sub(&campaign.escrowed, &amount);
+    }
+    cf.events.push(CampaignEvent {
+        campaign_id,
+        timestamp: now,
+        kind: CampaignEventKind::Refunded { user: user.to_string(), amount },
+    });
+}
+
+/// Allow the refund to be claimed again after the transfer failed
+pub fn abort_refund(cf: &mut CrowdfundingPool, campaign_id: u64, user: &str) {
+    if let Some(users) = cf.refunded.get_mut(&campaign_id) {
+        users.remove(user);
+    }
+}
+
+/// Logged events of one campaign, oldest first
+pub fn events(cf: &CrowdfundingPool, campaign_id: u64) -> Vec<CampaignEvent> {
+    cf.events.iter().filter(|e| e.campaign_id == campaign_id).cloned().collect()
+}
+
+pub fn set_config(cf: &mut CrowdfundingPool, config: CrowdfundConfig) -> Result<(), String> {
+    if config.protocol_fee_bps > MAX_PROTOCOL_FEE_BPS {
+        return Err(format!("protocol_fee_bps must be at most {}", MAX_PROTOCOL_FEE_BPS));
+    }
+    cf.config = config;
+    Ok(())
+}
+
+/// Funds owed to a successful campaign's creator
+pub struct Payout {
+    pub token: String,
+    pub amount: Nat,
+    pub fee: Nat,
+    pub treasury: Option<Principal>,
+    /// The campaign's beneficiary, or its creator
+    pub recipient: Principal,
+    /// Milestones released by this payout
+    milestones: Vec<usize>,
+}
+
+/// Mark a successful campaign paid out and split its funds into payout and fee
+pub fn begin_payout(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    caller: Principal,
+    now: u64,
+) -> Result<Payout, String> {
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    if campaign.creator != caller {
+        return Err("only the campaign creator can withdraw".to_string());
+    }
+    settle(&mut cf.events, campaign, now);
+    if campaign.status != CampaignStatus::Successful {
+        return Err(format!("campaign is {:?}, withdrawals need Successful", campaign.status));
+    }
+    if campaign.disputed {
+        return Err("payouts are frozen while the campaign is under dispute".to_string());
+    }
+    if campaign.paid_out {
+        return Err("funds already withdrawn".to_string());
+    }
+
+    let total = total_funds(campaign);
+    let (gross, milestones) = if campaign.milestones.is_empty() {
+        (total.clone(), vec![])
+    } else {
+        let last = campaign.milestones.len() - 1;
+        let mut gross = Nat::from(0u64);
+        let mut released = vec![];
+        for (i, m) in campaign.milestones.iter_mut().enumerate() {
+            if m.status == MilestoneStatus::Approved {
+                m.status = MilestoneStatus::Released;
+                // The last tranche pays out whatever is left, including
+                // anything raised above the goal and matching funds
+                gross = if i == last {
+                    saturating_sub(&total, &campaign.released)
+                } else {
+                    Nat::from(&gross.0 + &m.amount.0)
+                };
+                released.push(i);
+            }
+        }
+        if released.is_empty() {
+            return Err("no approved milestone to withdraw".to_string());
+        }
+        (gross, released)
+    };
+    campaign.released = Nat::from(&campaign.released.0 + &gross.0);
+    campaign.escrowed = saturating_sub(&campaign.escrowed, &gross);
+    campaign.paid_out = campaign.released == total;
+
+    let fee = Nat::from(&gross.0 * cf.config.protocol_fee_bps / 10_000u64);
+    Ok(Payout {
+        token: campaign.token.clone(),
+        amount: Nat::from(&gross.0 - &fee.0),
+        fee,
+        treasury: cf.config.treasury,
+        recipient: campaign.metadata.beneficiary.unwrap_or(campaign.creator),
+        milestones,
+    })
+}
+
+/// Log a completed payout
+pub fn complete_payout(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    creator: Principal,
+    payout: Payout,
+    now: u64,
+) {
+    cf.events.push(CampaignEvent {
+        campaign_id,
+        timestamp: now,
+        kind: CampaignEventKind::PaidOut { creator, amount: payout.amount, fee: payout.fee },
+    });
+}
+
+/// Hold a protocol fee that did not reach the treasury until `pay_unpaid_fees`
+pub fn owe_fee(cf: &mut CrowdfundingPool, token: &str, fee: &Nat) {
+    if *fee == 0u64 {
+        return;
+    }
+    let owed = cf.unpaid_fees.entry(token.to_string()).or_insert_with(|| Nat::from(0u64));
+    *owed = Nat::from(&owed.0 + &fee.0);
+}
+
+/// Allow the withdrawal again after the creator transfer failed
+pub fn abort_payout(cf: &mut CrowdfundingPool, campaign_id: u64, payout: &Payout) {
+    if let Some(campaign) = cf.campaigns.get_mut(&campaign_id) {
+        campaign.paid_out = false;
+        let gross = Nat::from(&payout.amount.0 + &payout.fee.0);
+        campaign.released = saturating_sub(&campaign.released, &gross);
+        campaign.escrowed = Nat::from(&campaign.escrowed.0 + &gross.0);
+        for &i in &payout.milestones {
+            campaign.milestones[i].status = MilestoneStatus::Approved;
+        }
+    }
+}
+
+/// Split an active campaign's goal into milestones before anyone contributes
+pub fn set_milestones(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    caller: Principal,
+    specs: Vec<MilestoneSpec>,
+    now: u64,
+) -> Result<(), String> {
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    if campaign.creator != caller {
+        return Err("only the campaign creator can set milestones".to_string());
+    }
+    settle(&mut cf.events, campaign, now);
+    if campaign.status != CampaignStatus::Active || campaign.raised != 0u64 {
+        return Err("milestones can only change before the first contribution".to_string());
+    }
+    if specs.len() > MAX_MILESTONES {
+        return Err(format!("at most {} milestones", MAX_MILESTONES));
+    }
+    let mut total = Nat::from(0u64);
+    for spec in &specs {
+        if spec.description.trim().is_empty() || spec.description.len() > MAX_TITLE_LEN {
+            return Err(format!("milestone descriptions must be 1-{} bytes", MAX_TITLE_LEN));
+        }
+        if spec.amount == 0u64 {
+            return Err("milestone amounts must be positive".to_string());
+        }
+        total = Nat::from(&total.0 + &spec.amount.0);
+    }
+    if !specs.is_empty() && total != campaign.goal {
+        return Err(format!("milestone amounts sum to {}, goal is {}", total, campaign.goal));
+    }
+    campaign.milestones = specs
+        .into_iter()
+        .map(|spec| Milestone {
+            description: spec.description,
+            amount: spec.amount,
+            status: MilestoneStatus::Pending,
+            vote_ends_at: None,
+            votes_for: Nat::from(0u64),
+            votes_against: Nat::from(0u64),
+        })
+        .collect();
+    Ok(())
+}
+
+/// Open the vote on the next milestone once the previous one is released
+pub fn request_release(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    caller: Principal,
+    now: u64,
+) -> Result<u32, String> {
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    if campaign.creator != caller {
+        return Err("only the campaign creator can request a release".to_string());
+    }
+    settle(&mut cf.events, campaign, now);
+    if campaign.status != CampaignStatus::Successful {
+        return Err(format!("campaign is {:?}, releases need Successful", campaign.status));
+    }
+    if campaign.disputed {
+        return Err("releases are frozen while the campaign is under dispute".to_string());
+    }
+    let index = campaign
+        .milestones
+        .iter()
+        .position(|m| m.status != MilestoneStatus::Released)
+        .ok_or("no milestone left to release")?;
+    let milestone = &mut campaign.milestones[index];
+    if milestone.status != MilestoneStatus::Pending {
+        return Err(format!("milestone {} is {:?}", index, milestone.status));
+    }
+    let ends_at = now + MILESTONE_VOTE_NANOS;
+    milestone.status = MilestoneStatus::Voting;
+    milestone.vote_ends_at = Some(ends_at);
+    cf.events.push(CampaignEvent {
+        campaign_id,
+        timestamp: now,
+        kind: CampaignEventKind::MilestoneVoteOpened { index: index as u32, ends_at },
+    });
+    Ok(index as u32)
+}
+
+/// Cast a backer's vote, weighted by their contribution, on the open milestone
+pub fn vote(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    user: &str,
+    approve: bool,
+    now: u64,
+) -> Result<(), String> {
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    settle(&mut cf.events, campaign, now);
+    let index = campaign
+        .milestones
+        .iter()
+        .position(|m| m.status == MilestoneStatus::Voting)
+        .ok_or("no milestone vote is open")?;
+    let weight = cf
+        .campaign_contributions
+        .get(&campaign_id)
+        .and_then(|backers| backers.get(user))
+        .cloned()
+        .ok_or("only backers can vote")?;
+    let voters = cf.milestone_voters.entry((campaign_id, index as u32)).or_default();
+    if !voters.insert(user.to_string()) {
+        return Err("already voted on this milestone".to_string());
+    }
+    let milestone = &mut campaign.milestones[index];
+    let tally = if approve { &mut milestone.votes_for } else { &mut milestone.votes_against };
+    *tally = Nat::from(&tally.0 + &weight.0);
+    Ok(())
+}
+
+/// Longest cancellation reason or dispute note
+pub const MAX_REASON_LEN: usize = 500;
+
+fn check_reason(reason: &str) -> Result<(), String> {
+    if reason.trim().is_empty() || reason.len() > MAX_REASON_LEN {
+        return Err(format!("reason must be 1-{} bytes", MAX_REASON_LEN));
+    }
+    Ok(())
+}
+
+/// Queue a refund for every backer that has not been refunded yet
+fn queue_refunds(cf: &mut CrowdfundingPool, campaign_id: u64) {
+    let refunded = cf.refunded.get(&campaign_id);
+    let pending: Vec<String> = cf
+        .campaign_contributions
+        .get(&campaign_id)
+        .into_iter()
+        .flat_map(|backers| backers.keys())
+        .filter(|user| !refunded.is_some_and(|r| r.contains(*user)))
+        .cloned()
+        .collect();
+    cf.refund_queue.extend(pending.into_iter().map(|user| (campaign_id, user)));
+}
+
+/// Call off an active campaign; every backer is refunded automatically
+pub fn cancel(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    caller: Principal,
+    reason: String,
+    now: u64,
+) -> Result<(), String> {
+    check_reason(&reason)?;
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    if campaign.creator != caller {
+        return Err("only the campaign creator can cancel".to_string());
+    }
+    settle(&mut cf.events, campaign, now);
+    if campaign.status != CampaignStatus::Active {
+        return Err(format!("campaign is {:?}, only Active ones can be cancelled", campaign.status));
+    }
+    cf.events.push(CampaignEvent {
+        campaign_id,
+        timestamp: now,
+        kind: CampaignEventKind::Cancelled { reason },
+    });
+    set_status(&mut cf.events, campaign, CampaignStatus::Failed, now);
+    queue_refunds(cf, campaign_id);
+    Ok(())
+}
+
+/// Freeze contributions and payouts of a campaign pending review
+pub fn open_dispute(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    arbiter: Principal,
+    reason: String,
+    now: u64,
+) -> Result<(), String> {
+    check_reason(&reason)?;
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    settle(&mut cf.events, campaign, now);
+    if campaign.status == CampaignStatus::Failed {
+        return Err("campaign already failed".to_string());
+    }
+    if campaign.disputed {
+        return Err("campaign is already under dispute".to_string());
+    }
+    campaign.disputed = true;
+    cf.events.push(CampaignEvent {
+        campaign_id,
+        timestamp: now,
+        kind: CampaignEventKind::DisputeOpened { arbiter, reason },
+    });
+    Ok(())
+}
+
+/// Close a dispute: either lift the freeze, or fail the campaign and refund
+/// what is left in escrow to its backers
+pub fn resolve_dispute(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    arbiter: Principal,
+    refund: bool,
+    note: String,
+    now: u64,
+) -> Result<(), String> {
+    check_reason(&note)?;
+    let campaign = cf.campaigns.get_mut(&campaign_id).ok_or("campaign not found")?;
+    if !campaign.disputed {
+        return Err("campaign is not under dispute".to_string());
+    }
+    campaign.disputed = false;
+    cf.events.push(CampaignEvent {
+        campaign_id,
+        timestamp: now,
+        kind: CampaignEventKind::DisputeResolved { arbiter, refunded: refund, note },
+    });
+    if refund {
+        if campaign.status != CampaignStatus::Failed {
+            set_status(&mut cf.events, campaign, CampaignStatus::Failed, now);
+        }
+        queue_refunds(cf, campaign_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const DEADLINE: u64 = 1_000;
+
+    /// An active ICP campaign created by the anonymous principal; `create`
+    /// reads the clock, so tests insert campaigns directly
+    pub(crate) fn campaign(cf: &mut CrowdfundingPool, goal: u64) -> u64 {
+        cf.next_campaign_id += 1;
+        let id = cf.next_campaign_id;
+        cf.campaigns.insert(
+            id,
+            Campaign {
+                id,
+                creator: Principal::anonymous(),
+                title: "Well".to_string(),
+                description: String::new(),
+                token: "ICP".to_string(),
+                goal: Nat::from(goal),
+                raised: Nat::from(0u64),
+                deadline: DEADLINE,
+                created_at: 0,
+                status: CampaignStatus::Active,
+                contributor_count: 0,
+                paid_out: false,
+                milestones: vec![],
+                released: Nat::from(0u64),
+                matched: Nat::from(0u64),
+                escrowed: Nat::from(0u64),
+                metadata: CampaignMetadata::default(),
+                contribution_cap: None,
+                disputed: false,
+            },
+        );
+        id
+    }
+
+    #[test]
+    fn contributions_are_tracked_per_campaign() {
+        let mut cf = CrowdfundingPool::default();
+        let id = campaign(&mut cf, 1_000);
+        let other = campaign(&mut cf, 1_000);
+        contribute(&mut cf, id, "alice", &Nat::from(600u64), 1).unwrap();
+        contribute(&mut cf, id, "bob", &Nat::from(400u64), 2).unwrap();
+        contribute(&mut cf, id, "alice", &Nat::from(100u64), 3).unwrap();
+        contribute(&mut cf, other, "alice", &Nat::from(50u64), 4).unwrap();
+
+        let campaign = &cf.campaigns[&id];
+        assert_eq!(campaign.raised, Nat::from(1_100u64));
+        assert_eq!(campaign.contributor_count, 2);
+        assert_eq!(cf.campaign_contributions[&id]["alice"], Nat::from(700u64));
+        assert_eq!(contributions(&cf, id).len(), 2);
+        assert_eq!(cf.funds["ICP"], Nat::from(1_150u64));
+    }
+
+    #[test]
+    fn contributions_need_an_active_campaign() {
+        let mut cf = CrowdfundingPool::default();
+        let id = campaign(&mut cf, 1_000);
+        assert!(contribute(&mut cf, id, "alice", &Nat::from(0u64), 1).is_err());
+        assert!(contribute(&mut cf, id + 1, "alice", &Nat::from(10u64), 1).is_err());
+        cf.campaigns.get_mut(&id).unwrap().status = CampaignStatus::Failed;
+        assert!(contribute(&mut cf, id, "alice", &Nat::from(10u64), 1).is_err());
+        assert_eq!(cf.campaigns[&id].raised, Nat::from(0u64));
+    }
+
+    /// A 1000-token campaign with two 500 milestones, backed 600 by alice
+    /// and 400 by bob
+    fn funded_campaign(cf: &mut CrowdfundingPool) -> u64 {
+        let creator = Principal::anonymous();
+        let id = campaign(cf, 1_000);
+        let spec = |description: &str| MilestoneSpec {
+            description: description.to_string(),
+            amount: Nat::from(500u64),
+        };
+        set_milestones(cf, id, creator, vec![spec("dig"), spec("pump")], 1).unwrap();
+        contribute(cf, id, "alice", &Nat::from(600u64), 2).unwrap();
+        contribute(cf, id, "bob", &Nat::from(400u64), 3).unwrap();
+        id
+    }
+
+    fn milestone(votes_for: u64, votes_against: u64) -> Milestone {
+        Milestone {
+            description: "dig".to_string(),
+            amount: Nat::from(500u64),
+            status: MilestoneStatus::Voting,
+            vote_ends_at: Some(DEADLINE),
+            votes_for: Nat::from(votes_for),
+            votes_against: Nat::from(votes_against),
+        }
+    }
+
+    #[test]
+    fn vote_needs_a_majority_and_quorum() {
+        let raised = Nat::from(1_000u64);
+        // 20% of the contributed weight must vote
+        assert!(!vote_passes(&milestone(199, 0), &raised));
+        assert!(vote_passes(&milestone(200, 0), &raised));
+        // Ties and minorities fail
+        assert!(!vote_passes(&milestone(300, 300), &raised));
+        assert!(vote_passes(&milestone(301, 300), &raised));
+        assert!(!vote_passes(&milestone(0, 0), &raised));
+    }
+
+    #[test]
+    fn status_settles_at_the_deadline() {
+        let mut cf = CrowdfundingPool::default();
+        let met = campaign(&mut cf, 1_000);
+        let short = campaign(&mut cf, 1_000);
+        contribute(&mut cf, met, "alice", &Nat::from(1_000u64), 1).unwrap();
+        contribute(&mut cf, short, "alice", &Nat::from(999u64), 1).unwrap();
+
+        let status = |id: u64, now: u64| effective_status(&cf.campaigns[&id], now);
+        assert_eq!(status(met, DEADLINE - 1), CampaignStatus::Active);
+        assert_eq!(status(met, DEADLINE), CampaignStatus::Successful);
+        assert_eq!(status(short, DEADLINE), CampaignStatus::Failed);
+        assert_eq!(view(&cf.campaigns[&short], DEADLINE).status, CampaignStatus::Failed);
+    }
+
+    #[test]
+    fn settle_expired_logs_each_transition_once() {
+        let mut cf = CrowdfundingPool::default();
+        let id = funded_campaign(&mut cf);
+        settle_expired(&mut cf, DEADLINE - 1);
+        assert!(events(&cf, id).is_empty());
+        settle_expired(&mut cf, DEADLINE);
+        settle_expired(&mut cf, DEADLINE + 1);
+        assert_eq!(cf.campaigns[&id].status, CampaignStatus::Successful);
+        assert_eq!(events(&cf, id).len(), 1);
+    }
+
+    #[test]
+    fn refund_is_claimed_once_unless_aborted() {
+        let mut cf = CrowdfundingPool::default();
+        let id = funded_campaign(&mut cf);
+        cf.campaigns.get_mut(&id).unwrap().status = CampaignStatus::Failed;
+        let now = DEADLINE + 1;
+        assert_eq!(begin_refund(&mut cf, id, "bob", now).unwrap().1, Nat::from(400u64));
+        assert!(begin_refund(&mut cf, id, "bob", now).is_err());
+        assert!(begin_refund(&mut cf, id, "carol", now).is_err());
+        abort_refund(&mut cf, id, "bob");
+        assert!(begin_refund(&mut cf, id, "bob", now).is_ok());
+    }
+
+    #[test]
+    fn refunds_need_a_failed_campaign() {
+        let mut cf = CrowdfundingPool::default();
+        let id = funded_campaign(&mut cf);
+        assert!(begin_refund(&mut cf, id, "alice", DEADLINE - 1).is_err());
+        // Goal met by the deadline: Successful, not refundable
+        assert!(begin_refund(&mut cf, id, "alice", DEADLINE).is_err());
+        assert_eq!(cf.campaigns[&id].status, CampaignStatus::Successful);
+    }
+
+    #[test]
+    fn rejected_milestone_fails_the_campaign() {
+        let mut cf = CrowdfundingPool::default();
+        let id = funded_campaign(&mut cf);
+        let creator = Principal::anonymous();
+        assert_eq!(request_release(&mut cf, id, creator, DEADLINE), Ok(0));
+        vote(&mut cf, id, "alice", false, DEADLINE + 1).unwrap();
+        vote(&mut cf, id, "bob", true, DEADLINE + 1).unwrap();
+        assert!(vote(&mut cf, id, "bob", true, DEADLINE + 2).is_err());
+        assert!(vote(&mut cf, id, "carol", true, DEADLINE + 2).is_err());
+
+        settle_expired(&mut cf, DEADLINE + MILESTONE_VOTE_NANOS);
+        let campaign = &cf.campaigns[&id];
+        assert_eq!(campaign.milestones[0].status, MilestoneStatus::Rejected);
+        assert_eq!(campaign.status, CampaignStatus::Failed);
+    }
+
+    #[test]
+    fn refunds_are_pro_rata_to_unreleased_funds() {
+        let mut cf = CrowdfundingPool::default();
+        let id = funded_campaign(&mut cf);
+        let campaign = cf.campaigns.get_mut(&id).unwrap();
+        campaign.released = Nat::from(500u64);
+        campaign.status = CampaignStatus::Failed;
+
+        // contributed * (raised - released) / raised
+        let now = DEADLINE + 1;
+        let refund = begin_refund(&mut cf, id, "alice", now).unwrap();
+        assert_eq!(refund, ("ICP".to_string(), Nat::from(300u64)));
+        assert_eq!(begin_refund(&mut cf, id, "bob", now).unwrap().1, Nat::from(200u64));
+        assert!(begin_refund(&mut cf, id, "carol", now).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+// src/defi_pool_backend/faucet.rs
+//! Test faucet. On deployments installed with `faucet_enabled`, any
+//! authenticated principal can claim a small amount of each test token once
+//! per cooldown, minted by the token canister, so demo users can try
+//! deposits and borrows without asking for an admin mint.
+use crate::types::FaucetConfig;
+use crate::DeFiPool;
+use candid::Nat;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+pub fn set_config(pool: &mut DeFiPool, config: FaucetConfig) -> Result<(), String> {
+    if !pool.faucet_enabled {
+        return Err("faucet is disabled".to_string());
+    }
+    if let Some(entry) = config.amounts.iter().find(|e| !pool.supported_tokens.contains(&e.token)) {
+        return Err(format!("token {} not supported", entry.token));
+    }
+    pool.faucet_config = config;
+    Ok(())
+}
+
+/// Reserve a claim of `token` for `account`, starting its cooldown; returns
+/// the amount to mint and the previous claim time for `undo_claim`
+pub fn begin_claim(
+    pool: &mut DeFiPool,
+    account: &str,
+    token: &str,
+    now: u64,
+) -> Result<(Nat, Option<u64>), String> {
+    if !pool.faucet_enabled {
+        return Err("faucet is disabled".to_string());
+    }
+    let amount = pool
+        .faucet_config
+        .amounts
+        .iter()
+        .find(|e| e.token == token)
+        .map(|e| e.value.clone())
+        .ok_or(format!("faucet does not hand out {}", token))?;
+    let key = (account.to_string(), token.to_string());
+    let previous = pool.faucet_claims.get(&key).copied();
+    if let Some(last) = previous {
+        let next = last + pool.faucet_config.cooldown_secs * NANOS_PER_SEC;
+        if now < next {
+            return Err(format!("next {} claim possible at {}", token, next));
+        }
+    }
+    pool.faucet_claims.insert(key, now);
+    Ok((amount, previous))
+}
+
+/// Give back the cooldown of a claim whose mint failed
+pub fn undo_claim(pool: &mut DeFiPool, account: &str, token: &str, previous: Option<u64>) {
+    let key = (account.to_string(), token.to_string());
+    match previous {
+        Some(last) => pool.faucet_claims.insert(key, last),
+        None => pool.faucet_claims.remove(&key),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::StableBalanceEntry;
+
+    const SEC: u64 = NANOS_PER_SEC;
+
+    fn faucet_pool() -> DeFiPool {
+        let mut pool = DeFiPool { faucet_enabled: true, ..Default::default() };
+        pool.supported_tokens.push("ICP".to_string());
+        let icp = StableBalanceEntry { token: "ICP".to_string(), value: Nat::from(10u64) };
+        set_config(&mut pool, FaucetConfig { amounts: vec![icp], cooldown_secs: 60 }).unwrap();
+        pool
+    }
+
+    #[test]
+    fn claims_wait_for_the_cooldown() {
+        let mut pool = faucet_pool();
+        assert_eq!(begin_claim(&mut pool, "alice", "ICP", SEC), Ok((Nat::from(10u64), None)));
+        assert!(begin_claim(&mut pool, "alice", "ICP", 60 * SEC).is_err());
+        assert!(begin_claim(&mut pool, "bob", "ICP", 60 * SEC).is_ok());
+        assert_eq!(begin_claim(&mut pool, "alice", "ICP", 61 * SEC).unwrap().1, Some(SEC));
+    }
+
+    #[test]
+    fn undone_claim_restores_the_cooldown() {
+        let mut pool = faucet_pool();
+        let (_, previous) = begin_claim(&mut pool, "alice", "ICP", SEC).unwrap();
+        undo_claim(&mut pool, "alice", "ICP", previous);
+        assert!(begin_claim(&mut pool, "alice", "ICP", 2 * SEC).is_ok());
+
+        // The cooldown runs from the claim before the undone one
+        let (_, previous) = begin_claim(&mut pool, "alice", "ICP", 62 * SEC).unwrap();
+        undo_claim(&mut pool, "alice", "ICP", previous);
+        assert!(begin_claim(&mut pool, "alice", "ICP", 63 * SEC).is_ok());
+    }
+
+    #[test]
+    fn disabled_faucet_hands_out_nothing() {
+        let mut pool = faucet_pool();
+        assert!(begin_claim(&mut pool, "alice", "FAKEBTC", SEC).is_err());
+        pool.faucet_enabled = false;
+        assert!(begin_claim(&mut pool, "alice", "ICP", SEC).is_err());
+        assert!(set_config(&mut pool, FaucetConfig { amounts: vec![], cooldown_secs: 0 }).is_err());
+    }
+}
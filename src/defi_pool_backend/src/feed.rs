@@ -0,0 +1,95 @@
+// src/defi_pool_backend/feed.rs
+//! Campaign feed: creators post updates and backers post comments, so a
+//! campaign's story lives next to its funds. The creator and canister
+//! controllers moderate by hiding posts; hidden posts are kept but left out
+//! of the feed.
+use crate::types::{CampaignPost, PostKind, PostPage};
+use crate::CrowdfundingPool;
+use candid::Principal;
+
+pub const MAX_POST_LEN: usize = 2_000;
+/// Posts kept per campaign
+const MAX_POSTS_PER_CAMPAIGN: usize = 10_000;
+/// Largest page `page` returns
+const MAX_PAGE_SIZE: u64 = 100;
+
+/// Add an update (creator only) or comment (backers only); returns the post ID
+pub fn post(
+    cf: &mut CrowdfundingPool,
+    campaign_id: u64,
+    author: Principal,
+    kind: PostKind,
+    body: String,
+    now: u64,
+) -> Result<u64, String> {
+    let campaign = cf.campaigns.get(&campaign_id).ok_or("campaign not found")?;
+    if body.trim().is_empty() || body.len() > MAX_POST_LEN {
+        return Err(format!("post must be 1-{} bytes", MAX_POST_LEN));
+    }
+    let backed = cf
+        .campaign_contributions
+        .get(&campaign_id)
+        .is_some_and(|backers| backers.contains_key(&author.to_text()));
+    match kind {
+        PostKind::Update if campaign.creator != author => {
+            return Err("only the campaign creator can post updates".to_string());
+        }
+        PostKind::Comment if !backed => return Err("only backers can comment".to_string()),
+        _ => {}
+    }
+    let count = cf.posts.values().filter(|p| p.campaign_id == campaign_id).count();
+    if count >= MAX_POSTS_PER_CAMPAIGN {
+        return Err("campaign feed is full".to_string());
+    }
+
+    cf.next_post_id += 1;
+    let id = cf.next_post_id;
+    cf.posts.insert(
+        id,
+        CampaignPost { id, campaign_id, author, kind, body, timestamp: now, hidden: false },
+    );
+    Ok(id)
+}
+
+/// Hide or restore a post; allowed for the campaign creator and controllers
+pub fn moderate(
+    cf: &mut CrowdfundingPool,
+    post_id: u64,
+    caller: Principal,
+    is_admin: bool,
+    hidden: bool,
+) -> Result<(), String> {
+    let post = cf.posts.get_mut(&post_id).ok_or("post not found")?;
+    let is_creator = cf.campaigns.get(&post.campaign_id).is_some_and(|c| c.creator == caller);
+    if !is_creator && !is_admin {
+        return Err("only the campaign creator or an admin can moderate".to_string());
+    }
+    post.hidden = hidden;
+    Ok(())
+}
+
+/// Visible posts of a campaign, newest first, optionally of one kind
+pub fn page(
+    cf: &CrowdfundingPool,
+    campaign_id: u64,
+    kind: Option<PostKind>,
+    offset: u64,
+    limit: u64,
+) -> PostPage {
+    let visible: Vec<&CampaignPost> = cf
+        .posts
+        .values()
+        .rev()
+        .filter(|p| p.campaign_id == campaign_id && !p.hidden)
+        .filter(|p| kind.is_none_or(|k| p.kind == k))
+        .collect();
+    PostPage {
+        total: visible.len() as u64,
+        posts: visible
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit.min(MAX_PAGE_SIZE) as usize)
+            .cloned()
+            .collect(),
+    }
+}
@@ -0,0 +1,106 @@
+// src/defi_pool_backend/governance.rs
+//! Handover to a DAO. Once a governance canister (e.g. an SNS governance
+//! canister) is set, every admin-gated endpoint accepts calls only from it,
+//! so parameter changes go through proposals. The inventory lists every
+//! parameter a proposal can change, with its current value and setter.
+use crate::types::{GovernableParameter, VerificationSource};
+use crate::{CrowdfundingPool, DeFiPool};
+use candid::Principal;
+
+fn principal_text(p: Option<Principal>) -> String {
+    p.map(|p| p.to_text()).unwrap_or_else(|| "unset".to_string())
+}
+
+fn param(name: &str, value: String, setter: &str) -> GovernableParameter {
+    GovernableParameter { name: name.to_string(), value, setter: setter.to_string() }
+}
+
+/// Current value of every governable parameter
+pub fn inventory(
+    pool: &DeFiPool,
+    cf: &CrowdfundingPool,
+    governance: Option<Principal>,
+    ai_proxy: Option<Principal>,
+    shard_registry: Option<Principal>,
+) -> Vec<GovernableParameter> {
+    let mut params = vec![
+        param("governance_canister", principal_text(governance), "set_governance_canister"),
+        param("ai_proxy", principal_text(ai_proxy), "set_ai_proxy"),
+        param("shard_registry", principal_text(shard_registry), "set_shard_registry"),
+    ];
+    let mut tokens: Vec<_> = pool.token_canisters.iter().collect();
+    tokens.sort();
+    for (token, canister) in tokens {
+        params.push(param(&format!("token_canister.{}", token), canister.to_text(), "add_token"));
+    }
+
+    let config = &cf.config;
+    let arbiters: Vec<String> = config.arbiters.iter().map(|a| a.to_text()).collect();
+    params.extend([
+        param(
+            "crowdfund.protocol_fee_bps",
+            config.protocol_fee_bps.to_string(),
+            "set_crowdfund_config",
+        ),
+        param("crowdfund.treasury", principal_text(config.treasury), "set_crowdfund_config"),
+        param("crowdfund.arbiters", arbiters.join(","), "set_crowdfund_config"),
+        param(
+            "sybil.uniqueness_canister",
+            principal_text(cf.sybil_config.uniqueness_canister),
+            "set_sybil_config",
+        ),
+        param(
+            "sybil.attestation_ttl_secs",
+            cf.sybil_config.attestation_ttl_secs.to_string(),
+            "set_sybil_config",
+        ),
+    ]);
+    let allowlisted = cf
+        .verifications
+        .values()
+        .filter(|v| matches!(v.source, VerificationSource::Allowlist))
+        .count();
+    params.push(param(
+        "sybil.allowlisted_backers",
+        allowlisted.to_string(),
+        "set_allowlisted_backers",
+    ));
+    params.push(param(
+        "stablecoin_canister",
+        principal_text(pool.stablecoin_canister),
+        "set_stablecoin_canister",
+    ));
+    params.push(param(
+        "vault.stability_fee_bps",
+        pool.vault_config.stability_fee_bps.to_string(),
+        "set_vault_config",
+    ));
+    for factor in &pool.vault_config.collateral_factors {
+        params.push(param(
+            &format!("vault.collateral_factor_bps.{}", factor.token),
+            factor.factor_bps.to_string(),
+            "set_vault_config",
+        ));
+    }
+    if pool.faucet_enabled {
+        params.push(param(
+            "faucet.cooldown_secs",
+            pool.faucet_config.cooldown_secs.to_string(),
+            "set_faucet_config",
+        ));
+        for entry in &pool.faucet_config.amounts {
+            params.push(param(
+                &format!("faucet.amount.{}", entry.token),
+                entry.value.to_string(),
+                "set_faucet_config",
+            ));
+        }
+    }
+    params.push(param("savings.rate_bps", pool.savings_rate_bps.to_string(), "set_savings_rate"));
+    params.push(param(
+        "event_subscribers",
+        pool.event_subscribers.len().to_string(),
+        "register_event_subscriber",
+    ));
+    params
+}
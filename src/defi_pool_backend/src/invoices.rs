@@ -0,0 +1,316 @@
+// src/defi_pool_backend/invoices.rs
+//! Invoicing. An account bills another account for an amount of a token,
+//! with a memo and due date. The payer settles from their pool balance when
+//! it covers the invoice, otherwise by a token transfer; either way the
+//! amount is credited to the issuer's pool balance. Both parties get notices
+//! when an invoice is received, paid, cancelled or goes overdue.
+use crate::types::{
+    BalanceKind, Invoice, InvoiceNotice, InvoiceNoticeKind, InvoiceStatus, TransactionKind,
+};
+use crate::{ledger, DeFiPool};
+use candid::{Nat, Principal};
+use std::time::Duration;
+
+/// How often open invoices are checked for their due date
+pub const RUN_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const MAX_MEMO_LEN: usize = 500;
+const MAX_OPEN_INVOICES_PER_ISSUER: usize = 200;
+/// Notices kept per user, oldest dropped first
+const MAX_NOTICES_PER_USER: usize = 100;
+
+/// Validate and store a new invoice; `id`, `created_at`, `status`, `paid_at`
+/// and `overdue` of the draft are assigned here
+pub fn create(pool: &mut DeFiPool, draft: Invoice, now: u64) -> Result<u64, String> {
+    if draft.issuer == draft.payer {
+        return Err("cannot invoice your own account".to_string());
+    }
+    if !pool.users.contains_key(&draft.payer) {
+        return Err("payer has no account".to_string());
+    }
+    if !pool.token_canisters.contains_key(&draft.token) {
+        return Err(format!("token {} not supported", draft.token));
+    }
+    if draft.amount == 0u64 {
+        return Err("amount must be positive".to_string());
+    }
+    if draft.memo.len() > MAX_MEMO_LEN {
+        return Err(format!("memo is limited to {} bytes", MAX_MEMO_LEN));
+    }
+    if draft.due_date <= now {
+        return Err("due date must be in the future".to_string());
+    }
+    let open = pool
+        .invoices
+        .values()
+        .filter(|i| i.issuer == draft.issuer && i.status == InvoiceStatus::Open)
+        .count();
+    if open >= MAX_OPEN_INVOICES_PER_ISSUER {
+        return Err(format!("at most {} open invoices", MAX_OPEN_INVOICES_PER_ISSUER));
+    }
+
+    pool.next_invoice_id += 1;
+    let id = pool.next_invoice_id;
+    let (issuer, payer) = (draft.issuer.clone(), draft.payer.clone());
+    pool.invoices.insert(
+        id,
+        Invoice {
+            id,
+            created_at: now,
+            status: InvoiceStatus::Open,
+            paid_at: None,
+            overdue: false,
+            ..draft
+        },
+    );
+    notify_both(pool, &issuer, &payer, id, InvoiceNoticeKind::Received, now);
+    Ok(id)
+}
+
+pub fn cancel(pool: &mut DeFiPool, account: &str, id: u64, now: u64) -> Result<(), String> {
+    let invoice = open_invoice(pool, id)?;
+    if invoice.issuer != account {
+        return Err("only the issuer can cancel an invoice".to_string());
+    }
+    let (issuer, payer) = (invoice.issuer.clone(), invoice.payer.clone());
+    if let Some(invoice) = pool.invoices.get_mut(&id) {
+        invoice.status = InvoiceStatus::Cancelled;
+    }
+    notify_both(pool, &issuer, &payer, id, InvoiceNoticeKind::Cancelled, now);
+    Ok(())
+}
+
+fn open_invoice(pool: &DeFiPool, id: u64) -> Result<&Invoice, String> {
+    let invoice = pool.invoices.get(&id).ok_or("invoice not found")?;
+    if invoice.status != InvoiceStatus::Open || pool.invoices_in_flight.contains(&id) {
+        return Err("invoice is not open".to_string());
+    }
+    Ok(invoice)
+}
+
+/// Start paying invoice `id` as `account`. Settles from the pool balance
+/// when it covers the amount and returns `None`; otherwise reserves the
+/// invoice and returns the token canister and amount the caller must
+/// transfer before `finish_payment`.
+pub fn begin_payment(
+    pool: &mut DeFiPool,
+    account: &str,
+    id: u64,
+    now: u64,
+) -> Result<Option<(Principal, Nat)>, String> {
+    let invoice = open_invoice(pool, id)?;
+    if invoice.payer != account {
+        return Err("invoice is addressed to another account".to_string());
+    }
+    let (token, amount) = (invoice.token.clone(), invoice.amount.clone());
+    let balance = pool
+        .stablecoin_balances
+        .get_mut(account)
+        .and_then(|balances| balances.get_mut(&token))
+        .filter(|balance| **balance >= amount);
+    if let Some(balance) = balance {
+        *balance = Nat::from(&balance.0 - &amount.0);
+        settle(pool, id, true, now);
+        return Ok(None);
+    }
+    let token_principal =
+        pool.token_canisters.get(&token).cloned().ok_or(format!("token {} not supported", token))?;
+    pool.invoices_in_flight.insert(id);
+    Ok(Some((token_principal, amount)))
+}
+
+/// Complete a payment reserved by `begin_payment` once its transfer returned
+pub fn finish_payment(pool: &mut DeFiPool, id: u64, transferred: bool, now: u64) {
+    pool.invoices_in_flight.remove(&id);
+    if transferred {
+        settle(pool, id, false, now);
+    }
+}
+
+/// Credit the issuer and mark the invoice paid; `from_balance` tells whether
+/// the payer's pool balance was debited
+fn settle(pool: &mut DeFiPool, id: u64, from_balance: bool, now: u64) {
+    let Some(invoice) = pool.invoices.get_mut(&id) else { return };
+    invoice.status = InvoiceStatus::Paid;
+    invoice.paid_at = Some(now);
+    let (issuer, payer, token, amount) = (
+        invoice.issuer.clone(),
+        invoice.payer.clone(),
+        invoice.token.clone(),
+        invoice.amount.clone(),
+    );
+    let balance = pool
+        .stablecoin_balances
+        .entry(issuer.clone())
+        .or_default()
+        .entry(token.clone())
+        .or_insert(Nat::from(0u64));
+    *balance = Nat::from(&balance.0 + &amount.0);
+    let mut operations = Vec::new();
+    if from_balance {
+        operations.push(ledger::debit(&payer, BalanceKind::Pool, &token, &amount));
+    }
+    operations.push(ledger::credit(&issuer, BalanceKind::Pool, &token, &amount));
+    ledger::record(pool, TransactionKind::InvoicePayment, operations, now);
+    notify_both(pool, &issuer, &payer, id, InvoiceNoticeKind::Paid, now);
+}
+
+/// Invoices the account issued or owes, newest first
+pub fn of_account(pool: &DeFiPool, account: &str) -> Vec<Invoice> {
+    pool.invoices
+        .values()
+        .rev()
+        .filter(|i| i.issuer == account || i.payer == account)
+        .cloned()
+        .collect()
+}
+
+pub fn notices(pool: &DeFiPool, account: &str) -> Vec<InvoiceNotice> {
+    pool.invoice_notices.get(account).cloned().unwrap_or_default()
+}
+
+fn notify(pool: &mut DeFiPool, account: &str, invoice_id: u64, kind: InvoiceNoticeKind, now: u64) {
+    let notices = pool.invoice_notices.entry(account.to_string()).or_default();
+    if notices.len() >= MAX_NOTICES_PER_USER {
+        notices.remove(0);
+    }
+    notices.push(InvoiceNotice { invoice_id, timestamp: now, kind });
+}
+
+fn notify_both(
+    pool: &mut DeFiPool,
+    issuer: &str,
+    payer: &str,
+    invoice_id: u64,
+    kind: InvoiceNoticeKind,
+    now: u64,
+) {
+    notify(pool, issuer, invoice_id, kind, now);
+    notify(pool, payer, invoice_id, kind, now);
+}
+
+/// Timer entry point: flag open invoices past their due date and notify both
+/// parties once
+pub fn flag_overdue(pool: &mut DeFiPool, now: u64) {
+    let mut flagged = Vec::new();
+    for invoice in pool.invoices.values_mut() {
+        if invoice.status == InvoiceStatus::Open && !invoice.overdue && invoice.due_date <= now {
+            invoice.overdue = true;
+            flagged.push((invoice.id, invoice.issuer.clone(), invoice.payer.clone()));
+        }
+    }
+    for (id, issuer, payer) in flagged {
+        notify_both(pool, &issuer, &payer, id, InvoiceNoticeKind::Overdue, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::UserAccount;
+
+    const DUE: u64 = 1_000;
+
+    /// alice bills bob 100 ICP; bob holds `balance` ICP in the pool
+    fn billed(balance: u64) -> (DeFiPool, u64) {
+        let mut pool = DeFiPool::default();
+        pool.users.insert("bob".to_string(), UserAccount::default());
+        pool.token_canisters.insert("ICP".to_string(), Principal::anonymous());
+        let balances = pool.stablecoin_balances.entry("bob".to_string()).or_default();
+        balances.insert("ICP".to_string(), Nat::from(balance));
+        let draft = Invoice {
+            id: 0,
+            issuer: "alice".to_string(),
+            payer: "bob".to_string(),
+            token: "ICP".to_string(),
+            amount: Nat::from(100u64),
+            memo: "Consulting".to_string(),
+            due_date: DUE,
+            created_at: 0,
+            status: InvoiceStatus::Open,
+            paid_at: None,
+            overdue: false,
+        };
+        let id = create(&mut pool, draft, 0).unwrap();
+        (pool, id)
+    }
+
+    fn kinds(pool: &DeFiPool, account: &str) -> Vec<&'static str> {
+        notices(pool, account)
+            .iter()
+            .map(|n| match n.kind {
+                InvoiceNoticeKind::Received => "received",
+                InvoiceNoticeKind::Paid => "paid",
+                InvoiceNoticeKind::Cancelled => "cancelled",
+                InvoiceNoticeKind::Overdue => "overdue",
+            })
+            .collect()
+    }
+
+    fn pool_balance(pool: &DeFiPool, account: &str) -> Nat {
+        pool.stablecoin_balances[account]["ICP"].clone()
+    }
+
+    #[test]
+    fn payment_from_balance_settles_at_once() {
+        let (mut pool, id) = billed(150);
+        assert_eq!(begin_payment(&mut pool, "bob", id, 1), Ok(None));
+        assert_eq!(pool.invoices[&id].status, InvoiceStatus::Paid);
+        assert_eq!(pool_balance(&pool, "bob"), Nat::from(50u64));
+        assert_eq!(pool_balance(&pool, "alice"), Nat::from(100u64));
+        assert_eq!(kinds(&pool, "alice"), ["received", "paid"]);
+        assert_eq!(kinds(&pool, "bob"), ["received", "paid"]);
+        assert!(begin_payment(&mut pool, "bob", id, 2).is_err());
+    }
+
+    #[test]
+    fn transfer_payment_reserves_the_invoice() {
+        let (mut pool, id) = billed(0);
+        let transfer = begin_payment(&mut pool, "bob", id, 1).unwrap();
+        assert_eq!(transfer, Some((Principal::anonymous(), Nat::from(100u64))));
+        assert!(begin_payment(&mut pool, "bob", id, 1).is_err());
+        assert!(cancel(&mut pool, "alice", id, 1).is_err());
+
+        finish_payment(&mut pool, id, false, 2);
+        assert_eq!(pool.invoices[&id].status, InvoiceStatus::Open);
+        assert!(begin_payment(&mut pool, "bob", id, 3).unwrap().is_some());
+        finish_payment(&mut pool, id, true, 4);
+        assert_eq!(pool.invoices[&id].paid_at, Some(4));
+        assert_eq!(pool_balance(&pool, "alice"), Nat::from(100u64));
+    }
+
+    #[test]
+    fn only_the_issuer_cancels_and_both_hear_of_it() {
+        let (mut pool, id) = billed(0);
+        assert!(cancel(&mut pool, "bob", id, 1).is_err());
+        assert!(begin_payment(&mut pool, "alice", id, 1).is_err());
+        cancel(&mut pool, "alice", id, 1).unwrap();
+        assert_eq!(kinds(&pool, "alice"), ["received", "cancelled"]);
+        assert_eq!(kinds(&pool, "bob"), ["received", "cancelled"]);
+        assert!(begin_payment(&mut pool, "bob", id, 2).is_err());
+    }
+
+    #[test]
+    fn overdue_invoices_are_flagged_once() {
+        let (mut pool, id) = billed(0);
+        flag_overdue(&mut pool, DUE - 1);
+        assert!(!pool.invoices[&id].overdue);
+        flag_overdue(&mut pool, DUE);
+        flag_overdue(&mut pool, DUE + 1);
+        assert!(pool.invoices[&id].overdue);
+        assert_eq!(kinds(&pool, "alice"), ["received", "overdue"]);
+        assert_eq!(kinds(&pool, "bob"), ["received", "overdue"]);
+    }
+
+    #[test]
+    fn create_rejects_bad_drafts() {
+        let (mut pool, id) = billed(0);
+        let draft = pool.invoices[&id].clone();
+        let self_billed = Invoice { payer: "alice".to_string(), ..draft.clone() };
+        assert!(create(&mut pool, self_billed, 0).is_err());
+        let stranger = Invoice { payer: "carol".to_string(), ..draft.clone() };
+        assert!(create(&mut pool, stranger, 0).is_err());
+        let empty = Invoice { amount: Nat::from(0u64), ..draft.clone() };
+        assert!(create(&mut pool, empty, 0).is_err());
+        assert!(create(&mut pool, draft, DUE).is_err());
+    }
+}
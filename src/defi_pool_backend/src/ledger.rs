@@ -0,0 +1,94 @@
+// src/defi_pool_backend/ledger.rs
+//! Operation ledger for indexers and reconciliation. Every update that moves
+//! pool balances appends a block holding that one transaction, with the
+//! signed change of each balance it touched. Blocks are append-only, so a
+//! returned block is final; readers follow the tip from `network_status` and
+//! fetch blocks by index. Token transfers into and out of the pool show up
+//! as the pool-side credit or debit. Campaign escrow is covered by the
+//! campaign event log instead.
+use crate::types::{BalanceKind, LedgerBlock, NetworkStatus, Operation, TransactionKind};
+use crate::DeFiPool;
+use candid::{Int, Nat, Principal};
+use num_bigint::BigInt;
+use std::collections::HashMap;
+
+/// Account holding protocol balances such as the savings reserve
+pub const PROTOCOL_ACCOUNT: &str = "protocol";
+/// Blocks per `get_blocks` page
+pub const MAX_BLOCKS_PER_PAGE: usize = 100;
+
+pub fn credit(account: &str, balance: BalanceKind, token: &str, amount: &Nat) -> Operation {
+    operation(account, balance, token, BigInt::from(amount.0.clone()))
+}
+
+pub fn debit(account: &str, balance: BalanceKind, token: &str, amount: &Nat) -> Operation {
+    operation(account, balance, token, -BigInt::from(amount.0.clone()))
+}
+
+fn operation(account: &str, balance: BalanceKind, token: &str, delta: BigInt) -> Operation {
+    Operation { account: account.to_string(), balance, token: token.to_string(), delta: Int(delta) }
+}
+
+/// Append a block; zero-value operations are left out and a transaction
+/// without any is not recorded
+pub fn record(pool: &mut DeFiPool, kind: TransactionKind, operations: Vec<Operation>, now: u64) {
+    let operations: Vec<Operation> =
+        operations.into_iter().filter(|op| op.delta.0 != BigInt::default()).collect();
+    if operations.is_empty() {
+        return;
+    }
+    let index = pool.ledger.len() as u64;
+    pool.ledger.push(LedgerBlock { index, timestamp: now, kind, operations });
+}
+
+/// Operations moving every balance of `from` to `to`, for account recovery;
+/// call before the balances are moved
+pub fn transfer_all(pool: &DeFiPool, from: &str, to: &str) -> Vec<Operation> {
+    let mut operations = Vec::new();
+    let mut move_all = |balance: BalanceKind, amounts: Vec<(&String, &Nat)>| {
+        for (token, amount) in amounts {
+            operations.push(debit(from, balance, token, amount));
+            operations.push(credit(to, balance, token, amount));
+        }
+    };
+    move_all(BalanceKind::Pool, sorted(pool.stablecoin_balances.get(from)));
+    move_all(BalanceKind::Collateral, sorted(pool.collateral.get(from)));
+    if let Some(positions) = pool.savings.get(from) {
+        let mut savings: Vec<(&String, &Nat)> =
+            positions.iter().map(|(token, p)| (token, &p.principal)).collect();
+        savings.sort();
+        move_all(BalanceKind::Savings, savings);
+    }
+    if let Some(vault) = pool.vaults.get(from) {
+        move_all(BalanceKind::VaultCollateral, vault.collateral.iter().collect());
+        let stable = crate::vaults::STABLE_SYMBOL.to_string();
+        move_all(BalanceKind::VaultDebt, vec![(&stable, &vault.debt)]);
+    }
+    operations
+}
+
+fn sorted(map: Option<&HashMap<String, Nat>>) -> Vec<(&String, &Nat)> {
+    let mut amounts: Vec<(&String, &Nat)> = map.map(|m| m.iter().collect()).unwrap_or_default();
+    amounts.sort();
+    amounts
+}
+
+pub fn network_status(pool: &DeFiPool, network: Principal) -> NetworkStatus {
+    let tip = pool.ledger.last();
+    NetworkStatus {
+        network,
+        current_block_index: tip.map(|b| b.index),
+        current_block_timestamp: tip.map(|b| b.timestamp),
+        oldest_block_index: pool.ledger.first().map(|b| b.index),
+    }
+}
+
+pub fn block(pool: &DeFiPool, index: u64) -> Option<LedgerBlock> {
+    pool.ledger.get(index as usize).cloned()
+}
+
+/// Up to `limit` blocks starting at `from`
+pub fn blocks(pool: &DeFiPool, from: u64, limit: u64) -> Vec<LedgerBlock> {
+    let limit = (limit as usize).min(MAX_BLOCKS_PER_PAGE);
+    pool.ledger.iter().skip(from as usize).take(limit).cloned().collect()
+}
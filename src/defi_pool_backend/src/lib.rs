@@ -1,7 +1,7 @@
-use ic_cdk_macros::{init, query, update};
+use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use candid::{CandidType, Nat, Principal, Deserialize};
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
 use num_bigint::BigUint;
@@ -10,7 +10,30 @@ use ic_cdk::api::canister_self;
 use ic_cdk::call;
 
 mod types;
-use types::{UserAccount, BorrowRequest, RiskRequest, RiskResponse, StableBalanceEntry, StableToken, CrowdfundEntry};
+mod monitor;
+mod crowdfund;
+mod matching;
+mod receipts;
+mod feed;
+mod subscriptions;
+mod metadata;
+mod sybil;
+mod analytics;
+mod pubsub;
+mod governance;
+mod accounts;
+mod sessions;
+mod recovery;
+mod savings;
+mod vaults;
+mod invoices;
+mod rates;
+mod faucet;
+mod ledger;
+mod metrics;
+mod backup;
+use types::{UserAccount, BorrowPreview, BorrowRequest, RiskRequestV2, RiskResponse, RiskError, CreditFeatures, CreditScoreResponse, LiquidationFeatures, LiquidationForecast, WarningLevel, PriceSeries, PriceTrend, TrendDirection, UserEventSummary, AccountFlag, StableBalanceEntry, StableToken, CrowdfundEntry, Campaign, CampaignEvent, CrowdfundConfig, MilestoneSpec, MatchingRound, MatchingRoundArgs, CampaignMatch, BackerTier, ContributionReceipt, CampaignFilter, CampaignSort, CampaignPage, CampaignPost, PostKind, PostPage, Subscription, SubscriptionNotice, CampaignMetadata, CampaignCategory, SponsorReport, SybilConfig, Verification, CrowdfundAnalytics, ShardStats, EventTopic, EventSubscriber, PoolEvent, GovernableParameter, AccountLinks, SessionKey, SessionPermission, GuardianSet, RecoveryRequest, SavingsBalance, VaultConfig, VaultView, CollateralFactor, Redemption, RatePoint, Invoice, InvoiceNotice, InvoiceStatus, InitArgs, FaucetConfig, BalanceKind, TransactionKind, LedgerBlock, NetworkStatus, HttpRequest, HttpResponse, StateChunk};
+use monitor::{record_activity, ActivityKind};
 
 /// DIP-20 helper functions
 mod dip20 {
@@ -27,10 +50,22 @@ mod dip20 {
         res.map(|(b,)| b).unwrap_or(Nat::from(0u64))
     }
 
+    /// Send tokens held by the pool
+    pub async fn send(token: Principal, to: Principal, amount: Nat) -> bool {
+        let res: Result<(bool,), _> = call(token, "transfer", (to, amount)).await;
+        res.map(|(ok,)| ok).unwrap_or(false)
+    }
+
     pub async fn mint(token: Principal, to: Principal, amount: Nat) -> bool {
         let res: Result<(bool,), _> = call(token, "mint", (to, amount)).await;
         res.map(|(ok,)| ok).unwrap_or(false)
     }
+
+    /// Destroy tokens held by the pool
+    pub async fn burn(token: Principal, amount: Nat) -> bool {
+        let res: Result<(bool,), _> = call(token, "burn", (amount,)).await;
+        res.map(|(ok,)| ok).unwrap_or(false)
+    }
 }
 
 /// Multi-token collateral entry
@@ -45,6 +80,42 @@ pub struct CollateralEntry {
 pub struct CrowdfundingPool {
     pub funds: HashMap<String, Nat>, 
     pub contributors: HashMap<String, HashMap<String, Nat>>, 
+    // --- Campaigns
+    pub campaigns: BTreeMap<u64, Campaign>,
+    pub campaign_contributions: HashMap<u64, HashMap<String, Nat>>, // campaign -> user -> amount
+    pub next_campaign_id: u64,
+    pub refunded: HashMap<u64, HashSet<String>>, // campaign -> users whose refund was paid
+    pub events: Vec<CampaignEvent>,
+    pub config: CrowdfundConfig,
+    pub milestone_voters: HashMap<(u64, u32), HashSet<String>>, // (campaign, milestone) -> voters
+    pub refund_queue: VecDeque<(u64, String)>, // (campaign, user) refunds the timer pushes out
+    // --- Quadratic funding
+    pub rounds: BTreeMap<u64, MatchingRound>,
+    pub next_round_id: u64,
+    pub round_contributions: HashMap<(u64, u64), HashMap<String, Nat>>, // (round, campaign) -> user -> amount
+    pub round_matches: HashMap<u64, Vec<CampaignMatch>>, // round -> final matches
+    pub round_stakes: HashMap<u64, BTreeMap<Principal, matching::Stake>>, // round -> sponsor
+    // --- Contribution receipts
+    pub receipts: BTreeMap<u64, ContributionReceipt>,
+    pub receipt_index: HashMap<(u64, String), u64>, // (campaign, user) -> receipt
+    pub next_receipt_id: u64,
+    // --- Campaign feed
+    pub posts: BTreeMap<u64, CampaignPost>,
+    pub next_post_id: u64,
+    // --- Recurring contributions
+    pub subscriptions: BTreeMap<u64, Subscription>,
+    pub next_subscription_id: u64,
+    pub subscription_notices: HashMap<String, Vec<SubscriptionNotice>>, // user -> notices
+    // --- Search indexes over campaign metadata
+    pub category_index: HashMap<CampaignCategory, BTreeSet<u64>>,
+    pub tag_index: HashMap<String, BTreeSet<u64>>,
+    // --- Backer verification
+    pub verifications: HashMap<String, Verification>, // user -> verification
+    pub sybil_config: SybilConfig,
+    // --- Funding statistics
+    pub campaign_stats: HashMap<u64, analytics::CampaignStats>,
+    // --- Protocol fees not yet sent to the treasury
+    pub unpaid_fees: HashMap<String, Nat>, // token -> amount
 }
 
 /// Core DeFi pool state
@@ -59,6 +130,52 @@ pub struct DeFiPool {
     // --- Mint logs
     pub mint_logs: Vec<(String, String, Nat)>, // (user, token, amount)
     pub per_user_mint_logs: HashMap<String, Vec<(String, Nat)>>, // user -> Vec<(token, amount)>
+    // --- Activity monitoring
+    pub activity: HashMap<String, UserEventSummary>, // user -> current window counters
+    pub activity_window_start: u64,
+    pub account_flags: Vec<AccountFlag>,
+    // --- Oracle prices and AI trend forecasts
+    pub price_history: HashMap<String, Vec<u64>>, // token -> recent prices (e8s), oldest first
+    pub price_trends: HashMap<String, PriceTrend>,
+    // --- Loan outcomes for model training
+    pub loan_features: HashMap<String, RiskRequestV2>, // user -> features at last approved borrow
+    pub pending_outcomes: Vec<(RiskRequestV2, bool)>, // (features, defaulted) awaiting submission
+    // --- Sharding
+    pub admitted_users: HashSet<String>, // principals the registry assigned to this shard
+    // --- Event delivery
+    pub event_subscribers: BTreeMap<u64, EventSubscriber>,
+    pub next_event_subscriber_id: u64,
+    pub events_in_flight: HashSet<u64>, // subscriber ids with a batch awaiting its reply
+    // --- Linked principals
+    pub account_links: HashMap<String, String>, // principal -> account it acts on
+    pub link_proposals: HashMap<(String, String), u64>, // (proposer, invitee) -> expiry
+    // --- Session keys
+    pub sessions: HashMap<String, SessionKey>, // session principal -> grant
+    // --- Social recovery
+    pub guardians: HashMap<String, GuardianSet>, // account -> guardians
+    pub recoveries: HashMap<String, RecoveryRequest>, // account -> pending recovery
+    // --- Savings
+    pub savings: HashMap<String, HashMap<String, savings::Position>>, // account -> token -> savings
+    pub savings_rate_bps: u64,
+    pub savings_reserve: HashMap<String, Nat>, // token -> stability fees for paying interest
+    // --- Stablecoin vaults
+    pub vaults: HashMap<String, vaults::Vault>, // account -> vault
+    pub vault_config: VaultConfig,
+    pub stablecoin_canister: Option<Principal>,
+    pub redemptions: Vec<Redemption>, // indexed by id
+    // --- Invoicing
+    pub invoices: BTreeMap<u64, Invoice>,
+    pub next_invoice_id: u64,
+    pub invoices_in_flight: HashSet<u64>, // invoice ids with a payment transfer pending
+    pub invoice_notices: HashMap<String, Vec<InvoiceNotice>>, // account -> notices
+    // --- Rate history
+    pub rate_history: BTreeMap<u64, rates::DayRates>, // day start -> rates at its end
+    // --- Test faucet
+    pub faucet_enabled: bool,
+    pub faucet_config: FaucetConfig,
+    pub faucet_claims: HashMap<(String, String), u64>, // (account, token) -> last claim
+    // --- Operation ledger
+    pub ledger: Vec<LedgerBlock>,
 }
 
 /// Global state
@@ -67,8 +184,117 @@ static CF_POOL: Lazy<Mutex<CrowdfundingPool>> =
     Lazy::new(|| Mutex::new(CrowdfundingPool::default()));
 static AI_SERVICE_PROXY_PRINCIPAL: Lazy<Mutex<Option<Principal>>> =
     Lazy::new(|| Mutex::new(None));
+/// Shard registry; when set, only users it admitted may sign up here
+static SHARD_REGISTRY: Lazy<Mutex<Option<Principal>>> = Lazy::new(|| Mutex::new(None));
+/// DAO governance canister; once set it is the only admin
+static GOVERNANCE_CANISTER: Lazy<Mutex<Option<Principal>>> = Lazy::new(|| Mutex::new(None));
+/// Recent risk responses keyed by feature vector, with their expiry time
+static RISK_CACHE: Lazy<Mutex<HashMap<RiskCacheKey, (RiskResponse, u64)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[init]
+fn init(args: Option<InitArgs>) {
+    let mut pool = POOL.lock().unwrap();
+    pool.activity_window_start = ic_cdk::api::time();
+    pool.faucet_enabled = args.unwrap_or_default().faucet_enabled;
+    rates::record(&mut pool, ic_cdk::api::time());
+    drop(pool);
+    start_timers();
+}
 
-#[update]
+#[pre_upgrade]
+fn pre_upgrade() {
+    backup::save_for_upgrade();
+}
+
+#[post_upgrade]
+fn post_upgrade(args: Option<InitArgs>) {
+    backup::load_after_upgrade();
+    POOL.lock().unwrap().faucet_enabled = args.unwrap_or_default().faucet_enabled;
+    start_timers();
+}
+
+/// Schedule recurring background jobs. A tick can land between any two
+/// awaits of an update call, so no handler or job may hold `POOL` or
+/// `CF_POOL` across an await: a tick that finds either lock held traps.
+fn start_timers() {
+    ic_cdk_timers::set_timer_interval(monitor::MONITOR_INTERVAL, || {
+        ic_cdk::futures::spawn(monitor::run_activity_monitor())
+    });
+    ic_cdk_timers::set_timer_interval(PRICE_TREND_INTERVAL, || {
+        ic_cdk::futures::spawn(refresh_price_trends())
+    });
+    ic_cdk_timers::set_timer_interval(OUTCOME_SUBMIT_INTERVAL, || {
+        ic_cdk::futures::spawn(submit_loan_outcomes())
+    });
+    ic_cdk_timers::set_timer_interval(crowdfund::SETTLE_INTERVAL, || {
+        {
+            let mut cf = CF_POOL.lock().unwrap();
+            let now = ic_cdk::api::time();
+            crowdfund::settle_expired(&mut cf, now);
+            matching::close_ended(&mut cf, now);
+        }
+        ic_cdk::futures::spawn(process_refund_queue())
+    });
+    ic_cdk_timers::set_timer_interval(subscriptions::RUN_INTERVAL, || {
+        let mut pool = POOL.lock().unwrap();
+        let mut cf = CF_POOL.lock().unwrap();
+        subscriptions::run_due(&mut pool, &mut cf, ic_cdk::api::time());
+    });
+    ic_cdk_timers::set_timer_interval(pubsub::RUN_INTERVAL, || {
+        ic_cdk::futures::spawn(pubsub::deliver_due())
+    });
+    ic_cdk_timers::set_timer_interval(invoices::RUN_INTERVAL, || {
+        invoices::flag_overdue(&mut POOL.lock().unwrap(), ic_cdk::api::time());
+    });
+}
+
+/// The governance canister once set, canister controllers until then
+fn is_admin(principal: &Principal) -> bool {
+    match *GOVERNANCE_CANISTER.lock().unwrap() {
+        Some(governance) => *principal == governance,
+        None => ic_cdk::api::is_controller(principal),
+    }
+}
+
+/// Guard: admins (see `is_admin`)
+fn caller_is_controller() -> Result<(), String> {
+    if is_admin(&ic_cdk::caller()) {
+        Ok(())
+    } else if GOVERNANCE_CANISTER.lock().unwrap().is_some() {
+        Err("admin calls must come from the governance canister".to_string())
+    } else {
+        Err("caller is not a controller".to_string())
+    }
+}
+
+/// Guard: callers other than session keys, which may only use the
+/// operations they were granted
+fn caller_is_not_session() -> Result<(), String> {
+    if sessions::is_session(&POOL.lock().unwrap(), &ic_cdk::caller().to_text()) {
+        Err("session keys cannot call this method".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Controllers and the configured crowdfunding arbiters
+fn caller_is_arbiter() -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let arbiter = CF_POOL.lock().unwrap().config.arbiters.contains(&caller);
+    if arbiter || is_admin(&caller) {
+        Ok(())
+    } else {
+        Err("caller is not an arbiter".to_string())
+    }
+}
+
+/// Whether the activity monitor has frozen this user
+fn is_frozen(pool: &DeFiPool, user: &str) -> bool {
+    pool.users.get(&accounts::account_of(pool, user)).map(|a| a.frozen).unwrap_or(false)
+}
+
+#[update(guard = "caller_is_not_session")]
 fn init_tokens() -> bool {
     let mut pool = POOL.lock().unwrap();
     if pool.supported_tokens.is_empty() {
@@ -86,21 +312,150 @@ fn init_tokens() -> bool {
 
 // ---------------- USER MANAGEMENT ----------------
 
-#[update]
+#[update(guard = "caller_is_not_session")]
 fn signup(user: String, username: String) -> bool {
     let mut pool = POOL.lock().unwrap();
-    if pool.users.contains_key(&user) {
+    // A linked principal already acts on another account
+    if pool.users.contains_key(&user) || pool.account_links.contains_key(&user) {
+        return false;
+    }
+    if SHARD_REGISTRY.lock().unwrap().is_some() && !pool.admitted_users.contains(&user) {
         return false;
     }
 
     let mut account = UserAccount::default();
     account.credit_score = Nat::from(700u64);
+    account.created_at = ic_cdk::api::time();
 
     pool.users.insert(user.clone(), account);
     pool.usernames.insert(user.clone(), username);
     true
 }
 
+/// Account the caller acts on for `permission`: a session key's account,
+/// charging `usd` to its daily allowance, otherwise the caller's own (or
+/// the one it is linked to)
+fn acting_account(permission: SessionPermission, usd: f64) -> Result<String, String> {
+    let caller = ic_cdk::caller().to_text();
+    let mut pool = POOL.lock().unwrap();
+    if sessions::is_session(&pool, &caller) {
+        return sessions::check(&mut pool, &caller, permission, usd, ic_cdk::api::time());
+    }
+    Ok(accounts::account_of(&pool, &caller))
+}
+
+/// Offer to link the caller and `other` into one account; `other` must
+/// accept with `confirm_link`
+#[update(guard = "caller_is_not_session")]
+fn propose_link(other: Principal) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() || other == Principal::anonymous() {
+        return Err("anonymous principals cannot be linked".to_string());
+    }
+    let mut pool = POOL.lock().unwrap();
+    accounts::propose(&mut pool, &caller.to_text(), &other.to_text(), ic_cdk::api::time())
+}
+
+/// Accept a link proposed by `proposer`; returns the shared account
+#[update(guard = "caller_is_not_session")]
+fn confirm_link(proposer: Principal) -> Result<String, String> {
+    let caller = ic_cdk::caller().to_text();
+    let mut pool = POOL.lock().unwrap();
+    accounts::confirm(&mut pool, &caller, &proposer.to_text(), ic_cdk::api::time())
+}
+
+/// Detach a principal from the caller's account
+#[update(guard = "caller_is_not_session")]
+fn unlink_principal(principal: Principal) -> Result<(), String> {
+    let caller = ic_cdk::caller().to_text();
+    accounts::unlink(&mut POOL.lock().unwrap(), &caller, &principal.to_text())
+}
+
+#[query]
+fn get_account_links() -> AccountLinks {
+    accounts::links(&POOL.lock().unwrap(), &ic_cdk::caller().to_text())
+}
+
+/// Let `session` act on the caller's account with `permissions` for
+/// `ttl_secs`, moving at most `daily_limit_usd` per day
+#[update(guard = "caller_is_not_session")]
+fn authorize_session(
+    session: Principal,
+    permissions: Vec<SessionPermission>,
+    daily_limit_usd: Option<u64>,
+    ttl_secs: u64,
+) -> Result<SessionKey, String> {
+    if session == Principal::anonymous() {
+        return Err("anonymous principal cannot be a session".to_string());
+    }
+    let caller = ic_cdk::caller().to_text();
+    let mut pool = POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    let session = session.to_text();
+    sessions::authorize(&mut pool, &caller, &session, permissions, daily_limit_usd, ttl_secs, now)
+}
+
+/// Revoke a session of the caller's account, effective immediately
+#[update]
+fn revoke_session(session: Principal) -> Result<(), String> {
+    let caller = ic_cdk::caller().to_text();
+    sessions::revoke(&mut POOL.lock().unwrap(), &caller, &session.to_text())
+}
+
+#[query]
+fn get_my_sessions() -> Vec<SessionKey> {
+    let pool = POOL.lock().unwrap();
+    let account = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    sessions::of_account(&pool, &account)
+}
+
+/// Name the guardians who can recover the caller's account and how many
+/// must agree; an empty list removes them
+#[update(guard = "caller_is_not_session")]
+fn set_guardians(guardians: Vec<Principal>, threshold: u32) -> Result<(), String> {
+    let mut pool = POOL.lock().unwrap();
+    let account = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    recovery::set_guardians(&mut pool, &account, guardians, threshold, ic_cdk::api::time())
+}
+
+#[query]
+fn get_guardians(account: String) -> Option<GuardianSet> {
+    POOL.lock().unwrap().guardians.get(&account).cloned()
+}
+
+/// As a guardian of `account`, propose moving it to `new_principal`
+#[update(guard = "caller_is_not_session")]
+fn propose_recovery(account: String, new_principal: Principal) -> Result<(), String> {
+    let mut pool = POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    recovery::propose(&mut pool, &account, ic_cdk::caller(), new_principal, now)
+}
+
+/// As a guardian of `account`, approve its pending recovery
+#[update(guard = "caller_is_not_session")]
+fn approve_recovery(account: String) -> Result<u32, String> {
+    recovery::approve(&mut POOL.lock().unwrap(), &account, ic_cdk::caller())
+}
+
+/// Complete an approved recovery once its timelock has passed
+#[update(guard = "caller_is_not_session")]
+fn execute_recovery(account: String) -> Result<String, String> {
+    recovery::execute(&mut POOL.lock().unwrap(), &account, ic_cdk::api::time())
+}
+
+/// Stop a pending recovery of the caller's account
+#[update(guard = "caller_is_not_session")]
+fn cancel_recovery() -> Result<(), String> {
+    let mut pool = POOL.lock().unwrap();
+    let account = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    recovery::cancel(&mut pool, &account)
+}
+
+#[query]
+fn get_recovery_request(account: String) -> Option<RecoveryRequest> {
+    POOL.lock().unwrap().recoveries.get(&account).cloned()
+}
+
 #[query]
 fn list_users() -> Vec<String> {
     let pool = POOL.lock().unwrap();
@@ -113,14 +468,14 @@ fn get_username(user: String) -> Option<String> {
     pool.usernames.get(&user).cloned()
 }
 
-#[update]
+#[update(guard = "caller_is_controller")]
 fn set_ai_proxy(principal: Principal) -> bool {
     let mut p = AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap();
     *p = Some(principal);
     true
 }
 
-#[update]
+#[update(guard = "caller_is_controller")]
 fn add_token(token: String, principal: Principal) -> bool {
     let mut pool = POOL.lock().unwrap();
     if pool.supported_tokens.contains(&token) {
@@ -131,6 +486,96 @@ fn add_token(token: String, principal: Principal) -> bool {
     }
 }
 
+// ---------------- GOVERNANCE ----------------
+
+/// Hand admin rights to a DAO governance canister; can only be done once
+#[update(guard = "caller_is_controller")]
+fn set_governance_canister(principal: Principal) -> Result<(), String> {
+    if principal == Principal::anonymous() {
+        return Err("governance canister cannot be anonymous".to_string());
+    }
+    let mut governance = GOVERNANCE_CANISTER.lock().unwrap();
+    if governance.is_some() {
+        return Err("governance canister already set".to_string());
+    }
+    *governance = Some(principal);
+    Ok(())
+}
+
+#[query]
+fn get_governance_canister() -> Option<Principal> {
+    *GOVERNANCE_CANISTER.lock().unwrap()
+}
+
+/// Every parameter the admin can change, with its current value
+#[query]
+fn get_governable_parameters() -> Vec<GovernableParameter> {
+    governance::inventory(
+        &POOL.lock().unwrap(),
+        &CF_POOL.lock().unwrap(),
+        *GOVERNANCE_CANISTER.lock().unwrap(),
+        *AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap(),
+        *SHARD_REGISTRY.lock().unwrap(),
+    )
+}
+
+// ---------------- SHARDING ----------------
+
+/// Point this shard at the registry that assigns users to it
+#[update(guard = "caller_is_controller")]
+fn set_shard_registry(principal: Principal) {
+    *SHARD_REGISTRY.lock().unwrap() = Some(principal);
+}
+
+/// Called by the registry when it assigns `user` to this shard
+#[update]
+fn admit_user(user: Principal) -> bool {
+    let registry = *SHARD_REGISTRY.lock().unwrap();
+    if registry != Some(ic_cdk::caller()) {
+        return false;
+    }
+    POOL.lock().unwrap().admitted_users.insert(user.to_text());
+    true
+}
+
+fn token_totals(per_user: &HashMap<String, HashMap<String, Nat>>) -> Vec<StableBalanceEntry> {
+    let mut totals: BTreeMap<String, BigUint> = BTreeMap::new();
+    for balances in per_user.values() {
+        for (token, amount) in balances {
+            *totals.entry(token.clone()).or_default() += &amount.0;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(token, value)| StableBalanceEntry { token, value: Nat::from(value) })
+        .collect()
+}
+
+/// This shard's totals, polled by the registry's roll-up
+#[query]
+fn get_shard_stats() -> ShardStats {
+    let pool = POOL.lock().unwrap();
+    let mut borrowed = BigUint::from(0u32);
+    for account in pool.users.values() {
+        borrowed += &account.borrowed.0;
+    }
+    let tvl_usd = tvl_usd(&pool);
+    ShardStats {
+        user_count: pool.users.len() as u64,
+        deposits: token_totals(&pool.stablecoin_balances),
+        collateral: token_totals(&pool.collateral),
+        total_borrowed: Nat::from(borrowed),
+        stable_supply: compute_total_supply(&pool),
+        tvl_usd,
+        timestamp: ic_cdk::api::time(),
+    }
+}
+
+/// USD value of pool balances and collateral
+fn tvl_usd(pool: &DeFiPool) -> f64 {
+    pool.stablecoin_balances.values().chain(pool.collateral.values()).map(aggregate_collateral).sum()
+}
+
 /// Compute total supply
 fn compute_total_supply(pool: &DeFiPool) -> Nat {
     let mut total = BigUint::from(0u32);
@@ -142,86 +587,183 @@ fn compute_total_supply(pool: &DeFiPool) -> Nat {
     Nat::from(total)
 }
 
+/// Static USD price per token unit
+fn token_price(token: &str) -> f64 {
+    match token {
+        "ICP" => 1.0,
+        "FAKEBTC" => 50000.0,
+        "FAKEETH" => 3000.0,
+        _ => 1.0,
+    }
+}
+
+/// Static annualized volatility per token, in basis points
+fn token_volatility_bps(token: &str) -> u64 {
+    match token {
+        "ICP" => 8_000,
+        "FAKEBTC" => 6_000,
+        "FAKEETH" => 7_000,
+        _ => 8_000,
+    }
+}
+
+/// USD value of a token amount
+fn usd_value(token: &str, amount: &Nat) -> f64 {
+    amount.0.to_f64().unwrap_or(0.0) * token_price(token)
+}
+
 fn aggregate_collateral(account_collateral: &HashMap<String, Nat>) -> f64 {
     account_collateral
         .iter()
-        .map(|(token, amt)| {
-            let price = match token.as_str() {
-                "ICP" => 1.0,
-                "FAKEBTC" => 50000.0,
-                "FAKEETH" => 3000.0,
-                _ => 1.0,
-            };
-            amt.0.to_f64().unwrap_or(0.0) * price
-        })
+        .map(|(token, amt)| usd_value(token, amt))
         .sum()
 }
 
 fn aggregate_borrowed(account_borrowed: &HashMap<String, Nat>) -> f64 {
     account_borrowed
         .iter()
-        .map(|(token, amt)| {
-            let price = match token.as_str() {
-                "ICP" => 1.0,
-                "FAKEBTC" => 50000.0,
-                "FAKEETH" => 3000.0,
-                _ => 1.0,
-            };
-            amt.0.to_f64().unwrap_or(0.0) * price
-        })
+        .map(|(token, amt)| usd_value(token, amt))
         .sum()
 }
 
 fn aggregate_deposits(account_balances: &HashMap<String, Nat>) -> f64 {
     account_balances
         .iter()
-        .map(|(token, amt)| {
-            let price = match token.as_str() {
-                "ICP" => 1.0,
-                "FAKEBTC" => 50000.0,
-                "FAKEETH" => 3000.0,
-                _ => 1.0,
-            };
-            amt.0.to_f64().unwrap_or(0.0) * price
-        })
+        .map(|(token, amt)| usd_value(token, amt))
         .sum()
 }
 
-/// AI risk check
-async fn risk_check(
-    account: &mut UserAccount,
+/// Fixed-point scale of `RiskRequestV2` values
+const E8S_PER_UNIT: f64 = 100_000_000.0;
+
+/// Model features for a user's current position
+fn build_risk_request(
+    user: &str,
+    account: &UserAccount,
     coll_usd: f64,
     borrowed_usd: f64,
     deposits_usd: f64,
-) -> Option<RiskResponse> {
-    let principal = {
-        let guard = AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap();
-        guard.clone()?
-    };
-
+) -> RiskRequestV2 {
     let volatility = if deposits_usd > 0.0 {
         borrowed_usd / deposits_usd
     } else {
         0.01
     };
-    let scaled_vol = (volatility.clamp(0.01, 0.5) * 1000.0).round() as u64;
+    let e8s = |v: f64| Nat::from((v * E8S_PER_UNIT).round() as u128);
 
-    let request = RiskRequest {
-        collateral: Nat::from(coll_usd as u64),
-        borrowed: Nat::from(borrowed_usd as u64),
-        deposits: Nat::from(deposits_usd as u64),
-        volatility: Nat::from(scaled_vol),
+    RiskRequestV2 {
+        volatility_e8s: e8s(volatility.clamp(0.01, 0.5)),
+        collateral_e8s: e8s(coll_usd),
+        borrowed_e8s: e8s(borrowed_usd),
+        deposits_e8s: e8s(deposits_usd),
         credit_score: Nat::from(account.credit_score.0.clone()),
+        lang: None,
+        account: Some(user.to_string()),
+    }
+}
+
+/// Model features for a user's current position, `None` without an account
+fn position_risk_request(pool: &DeFiPool, user: &str) -> Option<RiskRequestV2> {
+    let account = pool.users.get(user)?;
+    let coll = pool.collateral.get(user).cloned().unwrap_or_default();
+    let borrowed = pool.stablecoin_balances.get(user).cloned().unwrap_or_default();
+    let deposits = pool.stablecoin_balances.get(user).cloned().unwrap_or_default();
+    Some(build_risk_request(
+        user,
+        account,
+        aggregate_collateral(&coll),
+        aggregate_borrowed(&borrowed),
+        aggregate_deposits(&deposits),
+    ))
+}
+
+// ---------------- RISK RESPONSE CACHE ----------------
+
+/// How long a risk response is reused for an identical request
+const RISK_CACHE_TTL_NANOS: u64 = 5 * 60 * 1_000_000_000;
+/// Entries kept before expired ones are swept
+const MAX_RISK_CACHE_ENTRIES: usize = 5_000;
+/// Cache buckets: whole cents for USD values, 1e-4 for volatility
+const CACHE_QUANTUM_E8S: u64 = 1_000_000;
+const CACHE_VOLATILITY_QUANTUM_E8S: u64 = 10_000;
+
+/// Includes the account so every user's checks still reach their proxy history
+type RiskCacheKey = (Option<String>, Nat, Nat, Nat, Nat, Nat);
+
+fn risk_cache_key(req: &RiskRequestV2) -> RiskCacheKey {
+    let q = |v: &Nat, quantum: u64| Nat::from(&v.0 / quantum);
+    (
+        req.account.clone(),
+        q(&req.volatility_e8s, CACHE_VOLATILITY_QUANTUM_E8S),
+        q(&req.collateral_e8s, CACHE_QUANTUM_E8S),
+        q(&req.borrowed_e8s, CACHE_QUANTUM_E8S),
+        q(&req.deposits_e8s, CACHE_QUANTUM_E8S),
+        req.credit_score.clone(),
+    )
+}
+
+fn cached_risk(req: &RiskRequestV2) -> Option<RiskResponse> {
+    let cache = RISK_CACHE.lock().unwrap();
+    match cache.get(&risk_cache_key(req)) {
+        Some((resp, expires_at)) if *expires_at > ic_cdk::api::time() => Some(resp.clone()),
+        _ => None,
+    }
+}
+
+fn cache_risk(req: &RiskRequestV2, resp: &RiskResponse) {
+    let now = ic_cdk::api::time();
+    let mut cache = RISK_CACHE.lock().unwrap();
+    if cache.len() >= MAX_RISK_CACHE_ENTRIES {
+        cache.retain(|_, (_, expires_at)| *expires_at > now);
+        if cache.len() >= MAX_RISK_CACHE_ENTRIES {
+            cache.clear();
+        }
+    }
+    cache.insert(risk_cache_key(req), (resp.clone(), now + RISK_CACHE_TTL_NANOS));
+}
+
+/// Drop cached responses, e.g. after the proxy's model changes (admin)
+#[update(guard = "caller_is_controller")]
+fn clear_risk_cache() -> u64 {
+    let mut cache = RISK_CACHE.lock().unwrap();
+    let cleared = cache.len() as u64;
+    cache.clear();
+    cleared
+}
+
+/// AI risk check, answered from the cache when the features are unchanged;
+/// an error carries the advice to show instead. Callers must not hold a
+/// state lock across it.
+async fn risk_check(request: RiskRequestV2) -> Result<RiskResponse, String> {
+    if let Some(resp) = cached_risk(&request) {
+        return Ok(resp);
+    }
+
+    let principal = {
+        let guard = AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap();
+        guard.ok_or("AI service not configured")?
     };
 
-    let result: Result<(RiskResponse,), _> = call(principal, "risk", (request,)).await;
+    let result: Result<(Result<RiskResponse, RiskError>,), _> =
+        call(principal, "risk_v2", (request.clone(),)).await;
 
-    if let Ok((resp,)) = result {
-        account.risk_advice = Some(resp.advice.clone());
-        Some(resp)
-    } else {
-        account.risk_advice = Some("AI service unavailable".to_string());
-        None
+    match result {
+        Ok((Ok(resp),)) => {
+            cache_risk(&request, &resp);
+            Ok(resp)
+        }
+        Ok((Err(err),)) => Err(format!("Risk check rejected input: {}", err)),
+        Err(_) => Err("AI service unavailable".to_string()),
+    }
+}
+
+/// Store the outcome of a risk check as the user's advice
+fn record_risk_advice(pool: &mut DeFiPool, user: &str, checked: &Result<RiskResponse, String>) {
+    if let Some(account) = pool.users.get_mut(user) {
+        account.risk_advice = Some(match checked {
+            Ok(resp) => resp.advice.clone(),
+            Err(advice) => advice.clone(),
+        });
     }
 }
 
@@ -243,6 +785,13 @@ fn log_mint(pool: &mut DeFiPool, user: &str, token: &str, amount: &Nat) {
 #[update]
 async fn deposit(token: String, amount: Nat) -> bool {
     let caller = ic_cdk::caller();
+    let account_id = match acting_account(SessionPermission::Deposit, usd_value(&token, &amount)) {
+        Ok(account) => account,
+        Err(err) => {
+            ic_cdk::print(format!("Deposit rejected: {}", err));
+            return false;
+        }
+    };
 
     // Get token canister principal safely
     let principal = {
@@ -271,23 +820,19 @@ async fn deposit(token: String, amount: Nat) -> bool {
     }
     ic_cdk::print("Transfer successful");
 
-    // Step 2: Mint stablecoin to caller
-    let minted = dip20::mint(principal, caller, amount.clone()).await;
-    if !minted {
-        ic_cdk::print("Deposit failed: mint returned false");
-        return false;
-    }
-    ic_cdk::print("Mint successful");
-
-    // Step 3: Update balances and log mint inside one mutex lock
+    // Step 2: Credit the pool balance; the stablecoin is minted against
+    // vaults (`mint_stable`), not 1:1 on deposit
     {
         let mut pool = POOL.lock().unwrap();
-        let caller_text = caller.to_text();
+        let caller_text = account_id;
         let balances = pool.stablecoin_balances.entry(caller_text.clone()).or_default();
         let entry = balances.entry(token.clone()).or_insert(Nat::from(0u64));
         *entry = Nat::from(&entry.0 + &amount.0);
 
-        log_mint(&mut pool, &caller_text, &token, &amount);
+        let credit = ledger::credit(&caller_text, BalanceKind::Pool, &token, &amount);
+        ledger::record(&mut pool, TransactionKind::Deposit, vec![credit], ic_cdk::api::time());
+        let usd = usd_value(&token, &amount);
+        record_activity(&mut pool, &caller_text, ActivityKind::Deposit, usd);
     }
 
     ic_cdk::print(format!(
@@ -299,13 +844,29 @@ async fn deposit(token: String, amount: Nat) -> bool {
 
 // ---------------- WITHDRAW COLLATERAL ----------------
 #[update]
-fn withdraw_collateral(user: String, token: String, amount: Nat) -> bool {
+fn withdraw_collateral(token: String, amount: Nat) -> bool {
+    let usd = usd_value(&token, &amount);
+    let user = match acting_account(SessionPermission::WithdrawCollateral, usd) {
+        Ok(account) => account,
+        Err(err) => {
+            ic_cdk::print(format!("Collateral withdrawal rejected: {}", err));
+            return false;
+        }
+    };
     let mut pool = POOL.lock().unwrap();
+    if !pool.users.contains_key(&user) || is_frozen(&pool, &user) {
+        return false;
+    }
     let user_coll = pool.collateral.entry(user.clone()).or_default();
     let coll = user_coll.entry(token.clone()).or_insert(Nat::from(0u64));
     if *coll < amount { return false; }
     let diff = &coll.0 - &amount.0;
     *coll = Nat::from(diff);
+    let debit = ledger::debit(&user, BalanceKind::Collateral, &token, &amount);
+    let now = ic_cdk::api::time();
+    ledger::record(&mut pool, TransactionKind::CollateralWithdrawal, vec![debit], now);
+    let usd = usd_value(&token, &amount);
+    record_activity(&mut pool, &user, ActivityKind::Withdraw, usd);
     true
 }
 
@@ -313,52 +874,128 @@ fn withdraw_collateral(user: String, token: String, amount: Nat) -> bool {
 #[update]
 async fn borrow(token: String, amount: Nat) -> bool {
     let caller = ic_cdk::caller();
+    // The stablecoin is only minted against vaults (`mint_stable`)
+    if token == vaults::STABLE_SYMBOL {
+        return false;
+    }
+    let account_id = match acting_account(SessionPermission::Borrow, usd_value(&token, &amount)) {
+        Ok(account) => account,
+        Err(err) => {
+            ic_cdk::print(format!("Borrow rejected: {}", err));
+            return false;
+        }
+    };
+    // Borrowed tokens go to the account, never to a session key acting on it
+    let recipient = if sessions::is_session(&POOL.lock().unwrap(), &caller.to_text()) {
+        match Principal::from_text(&account_id) {
+            Ok(owner) => owner,
+            Err(_) => return false,
+        }
+    } else {
+        caller
+    };
 
-    // Step 1: Get collateral, borrowed, and deposits for risk check
-    let (coll_clone, borrowed_clone, deposits_clone) = {
+    // Step 1: Snapshot the position for the risk check
+    let request = {
         let pool = POOL.lock().unwrap();
-        let coll = pool.collateral.get(&caller.to_text()).cloned().unwrap_or_default();
-        let borrowed = pool.stablecoin_balances.get(&caller.to_text()).cloned().unwrap_or_default();
-        let deposits = pool.stablecoin_balances.get(&caller.to_text()).cloned().unwrap_or_default();
-        (coll, borrowed, deposits)
+        if is_frozen(&pool, &account_id) {
+            return false;
+        }
+        match position_risk_request(&pool, &account_id) {
+            Some(request) => request,
+            None => return false,
+        }
     };
 
-    let coll_usd = aggregate_collateral(&coll_clone);
-    let borrowed_usd = aggregate_borrowed(&borrowed_clone);
-    let deposits_usd = aggregate_deposits(&deposits_clone);
+    // Step 2: Risk check with AI, without holding the pool across the call
+    let checked = risk_check(request.clone()).await;
 
-    // Step 2: Risk check with AI
-    let mut pool = POOL.lock().unwrap();
-    let account = match pool.users.get_mut(&caller.to_text()) {
-        Some(acc) => acc,
-        None => return false,
-    };
-    if risk_check(account, coll_usd, borrowed_usd, deposits_usd).await.is_none() {
-        return false;
-    }
+    // Step 3: Re-validate against the current position and update borrowed balances
+    let token_principal = {
+        let mut pool = POOL.lock().unwrap();
+        record_risk_advice(&mut pool, &account_id, &checked);
+        if checked.is_err() {
+            return false;
+        }
+        // The position may have changed while the check was in flight
+        if position_risk_request(&pool, &account_id).as_ref() != Some(&request)
+            || is_frozen(&pool, &account_id)
+        {
+            return false;
+        }
+        pool.loan_features.insert(account_id.clone(), request);
 
-    // Step 3: Update borrowed balances
-    let balances = pool.stablecoin_balances.entry(caller.to_text()).or_default();
-    let entry = balances.entry(token.clone()).or_insert(Nat::from(0u64));
-    *entry = Nat::from(&entry.0 + &amount.0);
+        let balances = pool.stablecoin_balances.entry(account_id.clone()).or_default();
+        let entry = balances.entry(token.clone()).or_insert(Nat::from(0u64));
+        *entry = Nat::from(&entry.0 + &amount.0);
+        let credit = ledger::credit(&account_id, BalanceKind::Pool, &token, &amount);
+        ledger::record(&mut pool, TransactionKind::Borrow, vec![credit], ic_cdk::api::time());
+        let usd = usd_value(&token, &amount);
+        record_activity(&mut pool, &account_id, ActivityKind::Borrow, usd);
+        pool.token_canisters.get(&token).cloned()
+    };
 
-    // Step 4: Mint token to caller
-    if let Some(token_principal) = pool.token_canisters.get(&token) {
-        dip20::mint(*token_principal, caller, amount.clone()).await;
-        log_mint(&mut pool, &caller.to_text(), &token, &amount);
+    // Step 4: Mint token to the borrower
+    if let Some(token_principal) = token_principal {
+        dip20::mint(token_principal, recipient, amount.clone()).await;
+        log_mint(&mut POOL.lock().unwrap(), &account_id, &token, &amount);
     }
 
     true
 }
 
+/// Instant risk feedback for a prospective borrow, via the proxy's query-mode
+/// `risk_preview`; nothing is cached, logged or charged
+#[query(composite = true)]
+async fn preview_borrow(token: String, amount: Nat) -> Result<BorrowPreview, String> {
+    let caller = acting_account(SessionPermission::Borrow, 0.0)?;
+    let (request, principal) = {
+        let pool = POOL.lock().unwrap();
+        if is_frozen(&pool, &caller) {
+            return Err("account is frozen".to_string());
+        }
+        let account = pool.users.get(&caller).ok_or("account not found")?;
+        let coll = pool.collateral.get(&caller).cloned().unwrap_or_default();
+        let balances = pool.stablecoin_balances.get(&caller).cloned().unwrap_or_default();
+        let request = build_risk_request(
+            &caller,
+            account,
+            aggregate_collateral(&coll),
+            aggregate_borrowed(&balances),
+            aggregate_deposits(&balances),
+        );
+        let principal = AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap().ok_or("AI service not configured")?;
+        (request, principal)
+    };
+
+    let result: Result<(Result<RiskResponse, RiskError>,), _> =
+        call(principal, "risk_preview", (request,)).await;
+    let risk = match result {
+        Ok((Ok(resp),)) => resp,
+        Ok((Err(err),)) => return Err(format!("Risk check rejected input: {}", err)),
+        Err(_) => return Err("AI service unavailable".to_string()),
+    };
+    let amount_usd = usd_value(&token, &amount);
+    Ok(BorrowPreview {
+        within_headroom: amount_usd <= risk.recommendations.max_additional_borrow_usd,
+        amount_usd,
+        risk,
+    })
+}
 
 // ---------------- REPAY ----------------
 #[update]
 fn repay(token: String, amount: Nat) -> bool {
-    let caller = ic_cdk::caller();
+    let account_id = match acting_account(SessionPermission::Repay, usd_value(&token, &amount)) {
+        Ok(account) => account,
+        Err(err) => {
+            ic_cdk::print(format!("Repay rejected: {}", err));
+            return false;
+        }
+    };
 
     let mut pool = POOL.lock().unwrap();
-    let balances = pool.stablecoin_balances.entry(caller.to_text()).or_default();
+    let balances = pool.stablecoin_balances.entry(account_id.clone()).or_default();
     let entry = balances.entry(token.clone()).or_insert(Nat::from(0u64));
 
     if *entry < amount {
@@ -367,79 +1004,1427 @@ fn repay(token: String, amount: Nat) -> bool {
 
     let diff = &entry.0 - &amount.0;
     *entry = Nat::from(diff);
+    let debit = ledger::debit(&account_id, BalanceKind::Pool, &token, &amount);
+    ledger::record(&mut pool, TransactionKind::Repay, vec![debit], ic_cdk::api::time());
+
+    // Record repayment history for the credit engine
+    let repaid_usd = usd_value(&token, &amount) as u64;
+    if let Some(account) = pool.users.get_mut(&account_id) {
+        account.repayment_count += 1;
+        account.total_repaid_usd = Nat::from(&account.total_repaid_usd.0 + repaid_usd);
+    }
+    record_activity(&mut pool, &account_id, ActivityKind::Repay, repaid_usd as f64);
+
+    // A fully repaid position is a non-default outcome for the training set
+    let fully_repaid = pool
+        .stablecoin_balances
+        .get(&account_id)
+        .map(|b| b.values().all(|amt| amt.0 == BigUint::from(0u32)))
+        .unwrap_or(true);
+    if fully_repaid {
+        if let Some(features) = pool.loan_features.remove(&account_id) {
+            pool.pending_outcomes.push((features, false));
+        }
+    }
 
     true
 }
 
+// ---------------- LOAN OUTCOMES ----------------
 
-// ---------------- DEPOSIT COLLATERAL (caller-centric) ----------------
-#[update]
-async fn deposit_collateral(token: String, amount: Nat) -> bool {
-    let caller = ic_cdk::caller();
+/// How often queued loan outcomes are sent to the AI proxy
+const OUTCOME_SUBMIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
 
-    // Step 1: Update user collateral inside mutex
-    {
-        let mut pool = POOL.lock().unwrap();
-        let user_coll = pool.collateral.entry(caller.to_text()).or_default();
-        let coll = user_coll.entry(token.clone()).or_insert(Nat::from(0u64));
-        *coll = Nat::from(&coll.0 + &amount.0);
+/// Label a user's open loan as defaulted (admin, e.g. after a liquidation)
+#[update(guard = "caller_is_controller")]
+fn record_loan_default(user: String) -> bool {
+    let mut pool = POOL.lock().unwrap();
+    let features = match pool.loan_features.remove(&user) {
+        Some(f) => f,
+        None => return false,
+    };
+    if let Some(account) = pool.users.get_mut(&user) {
+        account.liquidation_count += 1;
+    }
+    pool.pending_outcomes.push((features, true));
+    true
+}
+
+/// Send queued outcomes to the AI proxy's training dataset
+async fn submit_loan_outcomes() {
+    let principal = match *AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap() {
+        Some(p) => p,
+        None => return,
+    };
+    let outcomes = std::mem::take(&mut POOL.lock().unwrap().pending_outcomes);
+
+    let mut failed = Vec::new();
+    for (features, defaulted) in outcomes {
+        let result: Result<(Result<(), RiskError>,), _> =
+            call(principal, "submit_outcome_v2", (features.clone(), defaulted)).await;
+        match result {
+            Ok((Ok(()),)) => {}
+            // Rejected features will never be accepted, so drop them
+            Ok((Err(err),)) => ic_cdk::print(format!("submit_outcome_v2 rejected: {}", err)),
+            Err(_) => failed.push((features, defaulted)),
+        }
     }
+    // Retry transport failures on the next run
+    POOL.lock().unwrap().pending_outcomes.extend(failed);
+}
+
+#[update(guard = "caller_is_controller")]
+async fn submit_loan_outcomes_now() {
+    submit_loan_outcomes().await;
+}
+
+#[query]
+fn get_pending_outcome_count() -> u64 {
+    POOL.lock().unwrap().pending_outcomes.len() as u64
+}
+
+// ---------------- CREDIT ENGINE ----------------
+
+/// Nanoseconds per day, for account age
+const NANOS_PER_DAY: u64 = 86_400 * 1_000_000_000;
+
+/// Rescore the caller's credit from repayment history via the AI proxy
+#[update]
+async fn refresh_credit_score() -> Option<Nat> {
+    let account_id = acting_account(SessionPermission::RiskQueries, 0.0).ok()?;
 
-    // Step 2: Risk check
-    let (coll_clone, borrowed_clone, deposits_clone) = {
+    let features = {
         let pool = POOL.lock().unwrap();
-        let coll = pool.collateral.get(&caller.to_text()).cloned().unwrap_or_default();
-        let borrowed = pool.stablecoin_balances.get(&caller.to_text()).cloned().unwrap_or_default();
-        let deposits = pool.stablecoin_balances.get(&caller.to_text()).cloned().unwrap_or_default();
-        (coll, borrowed, deposits)
+        let account = pool.users.get(&account_id)?;
+        CreditFeatures {
+            repayment_count: account.repayment_count,
+            total_repaid_usd: account.total_repaid_usd.clone(),
+            liquidation_count: account.liquidation_count,
+            account_age_days: ic_cdk::api::time().saturating_sub(account.created_at) / NANOS_PER_DAY,
+        }
     };
 
-    let coll_usd = aggregate_collateral(&coll_clone);
-    let borrowed_usd = aggregate_borrowed(&borrowed_clone);
-    let deposits_usd = aggregate_deposits(&deposits_clone);
+    let principal = (*AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap())?;
+    let result: Result<(CreditScoreResponse,), _> =
+        call(principal, "score_credit", (features,)).await;
+    let (resp,) = result.ok()?;
 
+    let score = Nat::from(resp.credit_score);
     let mut pool = POOL.lock().unwrap();
-    if let Some(account) = pool.users.get_mut(&caller.to_text()) {
-        risk_check(account, coll_usd, borrowed_usd, deposits_usd).await;
-    }
-
-    true
+    let account = pool.users.get_mut(&account_id)?;
+    account.credit_score = score.clone();
+    Some(score)
 }
 
-// ---------------- CROWDFUND (caller-centric) ----------------
-#[update]
-async fn contribute_crowdfund(token: String, amount: Nat) -> bool {
-    let caller = ic_cdk::caller();
 
-    // Step 1: Update crowdfunding pool inside mutex
-    {
-        let mut cf = CF_POOL.lock().unwrap();
-        let total = cf.funds.entry(token.clone()).or_insert(Nat::from(0u64));
-        *total = Nat::from(&total.0 + &amount.0);
+// ---------------- PRICE TRENDS / DYNAMIC LTV ----------------
 
-        let contribs = cf.contributors.entry(caller.to_text()).or_default();
-        let entry = contribs.entry(token.clone()).or_insert(Nat::from(0u64));
-        *entry = Nat::from(&entry.0 + &amount.0);
+/// How often price trends are refreshed from the AI proxy
+const PRICE_TREND_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+/// Number of oracle prices kept per token
+const MAX_PRICE_HISTORY: usize = 48;
+/// Loan-to-value allowed for collateral with no downtrend
+const BASE_LTV_BPS: u64 = 7_500;
+/// Largest LTV reduction applied to a falling asset
+const MAX_LTV_TIGHTENING_BPS: u64 = 2_500;
+/// LTV reduction per basis point of expected (confidence-weighted) decline
+const LTV_TIGHTENING_FACTOR: f64 = 10.0;
+
+/// Record an oracle price observation for a token
+#[update(guard = "caller_is_controller")]
+fn record_price(token: String, price_e8s: u64) -> bool {
+    let mut pool = POOL.lock().unwrap();
+    if !pool.supported_tokens.contains(&token) {
+        return false;
     }
+    let history = pool.price_history.entry(token).or_default();
+    history.push(price_e8s);
+    if history.len() > MAX_PRICE_HISTORY {
+        history.remove(0);
+    }
+    true
+}
 
-    // Step 2: Mint tokens outside mutex
-    let token_principal_opt = {
+/// Ask the AI proxy for a fresh trend forecast on every token with price history
+async fn refresh_price_trends() {
+    let principal = match *AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap() {
+        Some(p) => p,
+        None => return,
+    };
+    let series: Vec<PriceSeries> = {
         let pool = POOL.lock().unwrap();
-        pool.token_canisters.get(&token).cloned()
+        pool.price_history
+            .iter()
+            .map(|(token, prices)| PriceSeries { token: token.clone(), prices_e8s: prices.clone() })
+            .collect()
     };
 
-    if let Some(token_principal) = token_principal_opt {
-        let minted = dip20::mint(token_principal, caller, amount.clone()).await;
-        if minted {
-            let mut pool = POOL.lock().unwrap();
-            log_mint(&mut pool, &caller.to_text(), &token, &amount);
+    for s in series {
+        let result: Result<(Result<PriceTrend, RiskError>,), _> =
+            call(principal, "predict_price_trend", (s,)).await;
+        match result {
+            Ok((Ok(trend),)) => {
+                let mut pool = POOL.lock().unwrap();
+                pool.price_trends.insert(trend.token.clone(), trend);
+            }
+            Ok((Err(err),)) => ic_cdk::print(format!("predict_price_trend rejected: {}", err)),
+            Err(err) => ic_cdk::print(format!("predict_price_trend failed: {:?}", err)),
         }
     }
+}
 
-    true
+#[update(guard = "caller_is_controller")]
+async fn refresh_price_trends_now() {
+    refresh_price_trends().await;
+}
+
+/// Current loan-to-value for a collateral token, tightened while it trends down
+fn token_ltv_bps(pool: &DeFiPool, token: &str) -> u64 {
+    match pool.price_trends.get(token) {
+        Some(trend) if trend.direction == TrendDirection::Down => {
+            let decline = trend.expected_return_bps.unsigned_abs() as f64 * trend.confidence;
+            let tightening = ((decline * LTV_TIGHTENING_FACTOR) as u64).min(MAX_LTV_TIGHTENING_BPS);
+            BASE_LTV_BPS - tightening
+        }
+        _ => BASE_LTV_BPS,
+    }
+}
+
+#[query]
+fn get_token_ltv(token: String) -> u64 {
+    let pool = POOL.lock().unwrap();
+    token_ltv_bps(&pool, &token)
+}
+
+#[query]
+fn get_price_trend(token: String) -> Option<PriceTrend> {
+    let pool = POOL.lock().unwrap();
+    pool.price_trends.get(&token).cloned()
+}
+
+// ---------------- LIQUIDATION FORECAST ----------------
+
+/// Share of collateral value that counts towards the liquidation threshold
+const LIQUIDATION_THRESHOLD: f64 = 0.8;
+
+/// Average a per-token metric weighted by the USD value of each collateral holding
+fn collateral_weighted(coll: &HashMap<String, Nat>, metric: impl Fn(&str) -> f64) -> f64 {
+    let total = aggregate_collateral(coll);
+    if total <= 0.0 {
+        return 0.0;
+    }
+    coll.iter().map(|(token, amt)| usd_value(token, amt) * metric(token)).sum::<f64>() / total
+}
+
+/// Forecast the caller's liquidation probability over `horizon_days`
+#[update]
+async fn forecast_liquidation(horizon_days: u32) -> Option<LiquidationForecast> {
+    let account_id = acting_account(SessionPermission::RiskQueries, 0.0).ok()?;
+
+    let features = {
+        let pool = POOL.lock().unwrap();
+        let coll = pool.collateral.get(&account_id).cloned().unwrap_or_default();
+        let borrowed = pool.stablecoin_balances.get(&account_id).cloned().unwrap_or_default();
+        let coll_usd = aggregate_collateral(&coll);
+        let borrowed_usd = aggregate_borrowed(&borrowed);
+        if borrowed_usd <= 0.0 {
+            return None; // nothing to liquidate
+        }
+
+        // Collateral-value-weighted volatility and price trend
+        let volatility_bps = collateral_weighted(&coll, |token| token_volatility_bps(token) as f64);
+        let price_trend_bps = collateral_weighted(&coll, |token| {
+            pool.price_trends.get(token).map(|t| t.expected_return_bps as f64).unwrap_or(0.0)
+        });
+
+        LiquidationFeatures {
+            health_factor_bps: (coll_usd * LIQUIDATION_THRESHOLD / borrowed_usd * 10_000.0) as u64,
+            volatility_bps: volatility_bps as u64,
+            price_trend_bps: price_trend_bps as i64,
+            horizon_days,
+        }
+    };
+
+    let principal = (*AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap())?;
+    let result: Result<(Result<LiquidationForecast, RiskError>,), _> =
+        call(principal, "predict_liquidation", (features,)).await;
+    let forecast = match result {
+        Ok((Ok(forecast),)) => forecast,
+        _ => return None,
+    };
+
+    // Graduated warnings surface through the account's risk advice
+    let warning = match forecast.warning_level {
+        WarningLevel::None | WarningLevel::Watch => None,
+        WarningLevel::Warning => Some("Warning"),
+        WarningLevel::Critical => Some("Critical"),
+    };
+    if let Some(level) = warning {
+        let mut pool = POOL.lock().unwrap();
+        if let Some(account) = pool.users.get_mut(&account_id) {
+            account.risk_advice = Some(format!(
+                "{}: {:.0}% chance of liquidation within {} days, consider adding collateral",
+                level,
+                forecast.probability * 100.0,
+                horizon_days
+            ));
+        }
+    }
+
+    Some(forecast)
+}
+
+// ---------------- DEPOSIT COLLATERAL (caller-centric) ----------------
+#[update]
+async fn deposit_collateral(token: String, amount: Nat) -> bool {
+    let account_id = match acting_account(SessionPermission::DepositCollateral, usd_value(&token, &amount)) {
+        Ok(account) => account,
+        Err(err) => {
+            ic_cdk::print(format!("Collateral deposit rejected: {}", err));
+            return false;
+        }
+    };
+
+    // Step 1: Update user collateral inside mutex
+    {
+        let mut pool = POOL.lock().unwrap();
+        let user_coll = pool.collateral.entry(account_id.clone()).or_default();
+        let coll = user_coll.entry(token.clone()).or_insert(Nat::from(0u64));
+        *coll = Nat::from(&coll.0 + &amount.0);
+        let credit = ledger::credit(&account_id, BalanceKind::Collateral, &token, &amount);
+        let now = ic_cdk::api::time();
+        ledger::record(&mut pool, TransactionKind::CollateralDeposit, vec![credit], now);
+    }
+
+    // Step 2: Risk check, without holding the pool across the call
+    let request = position_risk_request(&POOL.lock().unwrap(), &account_id);
+    if let Some(request) = request {
+        let checked = risk_check(request).await;
+        record_risk_advice(&mut POOL.lock().unwrap(), &account_id, &checked);
+    }
+
+    true
+}
+
+// ---------------- CROWDFUND (caller-centric) ----------------
+
+/// Open a campaign raising `goal` of `token` until `deadline` (ns since epoch)
+#[update(guard = "caller_is_not_session")]
+fn create_campaign(
+    title: String,
+    description: String,
+    token: String,
+    goal: Nat,
+    deadline: u64,
+    metadata: Option<CampaignMetadata>,
+) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("anonymous callers cannot create campaigns".to_string());
+    }
+    let metadata = metadata::validate(metadata.unwrap_or_default())?;
+    {
+        let pool = POOL.lock().unwrap();
+        if is_frozen(&pool, &caller.to_text()) {
+            return Err("account is frozen".to_string());
+        }
+        if !pool.supported_tokens.contains(&token) {
+            return Err(format!("token {} not supported", token));
+        }
+    }
+    let mut cf = CF_POOL.lock().unwrap();
+    let id = crowdfund::create(&mut cf, caller, title, description, token, goal, deadline)?;
+    metadata::attach(&mut cf, id, metadata);
+    Ok(id)
+}
+
+#[query]
+fn get_campaign(campaign_id: u64) -> Option<Campaign> {
+    let cf = CF_POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    cf.campaigns.get(&campaign_id).map(|c| crowdfund::view(c, now))
+}
+
+#[query]
+fn list_campaigns() -> Vec<Campaign> {
+    let cf = CF_POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    cf.campaigns.values().map(|c| crowdfund::view(c, now)).collect()
+}
+
+/// Browse campaigns with filters, sorting and paging (at most 100 per page)
+#[query]
+fn search_campaigns(
+    filter: CampaignFilter,
+    sort: CampaignSort,
+    offset: u64,
+    limit: u64,
+) -> CampaignPage {
+    let cf = CF_POOL.lock().unwrap();
+    crowdfund::search(&cf, &filter, sort, offset, limit, ic_cdk::api::time())
+}
+
+#[query]
+fn get_campaign_contributions(campaign_id: u64) -> Vec<CrowdfundEntry> {
+    crowdfund::contributions(&CF_POOL.lock().unwrap(), campaign_id)
+}
+
+#[query]
+fn get_campaign_events(campaign_id: u64) -> Vec<CampaignEvent> {
+    crowdfund::events(&CF_POOL.lock().unwrap(), campaign_id)
+}
+
+/// Post a progress update to a campaign the caller created
+#[update(guard = "caller_is_not_session")]
+fn post_campaign_update(campaign_id: u64, body: String) -> Result<u64, String> {
+    let mut cf = CF_POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    feed::post(&mut cf, campaign_id, ic_cdk::caller(), PostKind::Update, body, now)
+}
+
+/// Comment on a campaign the caller has backed
+#[update(guard = "caller_is_not_session")]
+fn post_campaign_comment(campaign_id: u64, body: String) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if is_frozen(&POOL.lock().unwrap(), &caller.to_text()) {
+        return Err("account is frozen".to_string());
+    }
+    let mut cf = CF_POOL.lock().unwrap();
+    feed::post(&mut cf, campaign_id, caller, PostKind::Comment, body, ic_cdk::api::time())
+}
+
+/// Hide or restore a post (campaign creator or controllers)
+#[update(guard = "caller_is_not_session")]
+fn moderate_campaign_post(post_id: u64, hidden: bool) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let admin = is_admin(&caller);
+    feed::moderate(&mut CF_POOL.lock().unwrap(), post_id, caller, admin, hidden)
+}
+
+/// Visible posts of a campaign, newest first (at most 100 per page)
+#[query]
+fn get_campaign_posts(
+    campaign_id: u64,
+    kind: Option<PostKind>,
+    offset: u64,
+    limit: u64,
+) -> PostPage {
+    feed::page(&CF_POOL.lock().unwrap(), campaign_id, kind, offset, limit)
+}
+
+/// Contribute `amount` from the caller's pool balance every `interval_secs`
+#[update(guard = "caller_is_not_session")]
+fn subscribe_to_campaign(
+    campaign_id: u64,
+    token: String,
+    amount: Nat,
+    interval_secs: u64,
+) -> Result<u64, String> {
+    let user = ic_cdk::caller().to_text();
+    let mut cf = CF_POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    subscriptions::subscribe(&mut cf, &user, campaign_id, token, amount, interval_secs, now)
+}
+
+#[update(guard = "caller_is_not_session")]
+fn cancel_subscription(subscription_id: u64) -> Result<(), String> {
+    let user = ic_cdk::caller().to_text();
+    subscriptions::cancel(&mut CF_POOL.lock().unwrap(), &user, subscription_id)
+}
+
+#[query]
+fn get_my_subscriptions() -> Vec<Subscription> {
+    subscriptions::of_user(&CF_POOL.lock().unwrap(), &ic_cdk::caller().to_text())
+}
+
+/// Skipped and ended debits of the caller's subscriptions, oldest first
+#[query]
+fn get_subscription_notices() -> Vec<SubscriptionNotice> {
+    subscriptions::notices(&CF_POOL.lock().unwrap(), &ic_cdk::caller().to_text())
+}
+
+/// Cap how much any one principal may contribute to the caller's campaign
+#[update(guard = "caller_is_not_session")]
+fn set_contribution_cap(campaign_id: u64, cap: Option<Nat>) -> Result<(), String> {
+    let mut cf = CF_POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    crowdfund::set_contribution_cap(&mut cf, campaign_id, ic_cdk::caller(), cap, now)
+}
+
+/// Ask the proof-of-unique-human canister to attest the caller
+#[update(guard = "caller_is_not_session")]
+async fn verify_uniqueness() -> Result<Verification, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("anonymous callers cannot be verified".to_string());
+    }
+    let canister = CF_POOL
+        .lock()
+        .unwrap()
+        .sybil_config
+        .uniqueness_canister
+        .ok_or("no uniqueness canister configured")?;
+    let res: Result<(bool,), _> = call(canister, "is_unique_human", (caller,)).await;
+    match res {
+        Ok((true,)) => {
+            let mut cf = CF_POOL.lock().unwrap();
+            let now = ic_cdk::api::time();
+            Ok(sybil::record_attestation(&mut cf, &caller.to_text(), canister, now))
+        }
+        Ok((false,)) => Err("uniqueness canister did not attest this principal".to_string()),
+        Err(err) => Err(format!("uniqueness canister call failed: {:?}", err)),
+    }
+}
+
+#[query]
+fn get_verification(user: String) -> Option<Verification> {
+    CF_POOL.lock().unwrap().verifications.get(&user).cloned()
+}
+
+/// Add or remove principals from the verified-backer allowlist (controllers only)
+#[update(guard = "caller_is_controller")]
+fn set_allowlisted_backers(users: Vec<String>, allowed: bool) -> Result<u64, String> {
+    let mut cf = CF_POOL.lock().unwrap();
+    sybil::set_allowlisted(&mut cf, users, allowed, ic_cdk::api::time())
+}
+
+#[update(guard = "caller_is_controller")]
+fn set_sybil_config(config: SybilConfig) -> Result<(), String> {
+    sybil::set_config(&mut CF_POOL.lock().unwrap(), config)
+}
+
+#[query]
+fn get_sybil_config() -> SybilConfig {
+    CF_POOL.lock().unwrap().sybil_config.clone()
+}
+
+/// Contribution time series, backer statistics and progress of one campaign
+#[query]
+fn get_crowdfund_analytics(campaign_id: u64) -> Option<CrowdfundAnalytics> {
+    analytics::analytics(&CF_POOL.lock().unwrap(), campaign_id, ic_cdk::api::time())
+}
+
+/// Receipt badges held by the caller
+#[query]
+fn get_my_receipts() -> Vec<ContributionReceipt> {
+    receipts::of_user(&CF_POOL.lock().unwrap(), &ic_cdk::caller().to_text())
+}
+
+#[query]
+fn get_user_receipts(user: String) -> Vec<ContributionReceipt> {
+    receipts::of_user(&CF_POOL.lock().unwrap(), &user)
+}
+
+#[query]
+fn get_campaign_receipts(campaign_id: u64) -> Vec<ContributionReceipt> {
+    receipts::of_campaign(&CF_POOL.lock().unwrap(), campaign_id)
+}
+
+/// Tier a backer holds in a campaign, for gating perks; none once refunded
+#[query]
+fn get_backer_tier(campaign_id: u64, user: String) -> Option<BackerTier> {
+    receipts::tier_of(&CF_POOL.lock().unwrap(), campaign_id, &user)
+}
+
+/// Return the caller's contribution to a failed campaign from escrow
+#[update(guard = "caller_is_not_session")]
+async fn claim_refund(campaign_id: u64) -> Result<Nat, String> {
+    pay_refund(campaign_id, ic_cdk::caller()).await
+}
+
+/// Send a backer their refund out of escrow
+async fn pay_refund(campaign_id: u64, backer: Principal) -> Result<Nat, String> {
+    let user = backer.to_text();
+    let (token, amount) = {
+        let mut cf = CF_POOL.lock().unwrap();
+        crowdfund::begin_refund(&mut cf, campaign_id, &user, ic_cdk::api::time())?
+    };
+
+    let token_principal = POOL.lock().unwrap().token_canisters.get(&token).cloned();
+    let Some(token_principal) = token_principal else {
+        crowdfund::abort_refund(&mut CF_POOL.lock().unwrap(), campaign_id, &user);
+        return Err("token not supported".to_string());
+    };
+    if !dip20::send(token_principal, backer, amount.clone()).await {
+        crowdfund::abort_refund(&mut CF_POOL.lock().unwrap(), campaign_id, &user);
+        return Err("refund transfer failed, try again".to_string());
+    }
+
+    let mut cf = CF_POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    crowdfund::complete_refund(&mut cf, campaign_id, &user, amount.clone(), now);
+    receipts::mark_refunded(&mut cf, campaign_id, &user, now);
+    Ok(amount)
+}
+
+/// Refunds paid per run of the refund queue
+const REFUND_BATCH: usize = 50;
+
+/// Push out queued refunds of cancelled and disputed campaigns; a refund
+/// that fails stays claimable through `claim_refund`
+async fn process_refund_queue() {
+    for _ in 0..REFUND_BATCH {
+        let Some((campaign_id, user)) = CF_POOL.lock().unwrap().refund_queue.pop_front() else {
+            break;
+        };
+        let Ok(backer) = Principal::from_text(&user) else { continue };
+        if let Err(err) = pay_refund(campaign_id, backer).await {
+            ic_cdk::print(format!("Refund of {} on campaign {} left: {}", user, campaign_id, err));
+        }
+    }
+}
+
+/// Call off the caller's active campaign and refund every backer
+#[update(guard = "caller_is_not_session")]
+fn cancel_campaign(campaign_id: u64, reason: String) -> Result<(), String> {
+    {
+        let mut cf = CF_POOL.lock().unwrap();
+        crowdfund::cancel(&mut cf, campaign_id, ic_cdk::caller(), reason, ic_cdk::api::time())?;
+    }
+    ic_cdk::futures::spawn(process_refund_queue());
+    Ok(())
+}
+
+/// Freeze a campaign's contributions and payouts pending review (arbiters only)
+#[update(guard = "caller_is_arbiter")]
+fn open_campaign_dispute(campaign_id: u64, reason: String) -> Result<(), String> {
+    let mut cf = CF_POOL.lock().unwrap();
+    crowdfund::open_dispute(&mut cf, campaign_id, ic_cdk::caller(), reason, ic_cdk::api::time())
+}
+
+/// Lift the freeze, or fail the campaign and refund its backers (arbiters only)
+#[update(guard = "caller_is_arbiter")]
+fn resolve_campaign_dispute(campaign_id: u64, refund: bool, note: String) -> Result<(), String> {
+    {
+        let mut cf = CF_POOL.lock().unwrap();
+        let (arbiter, now) = (ic_cdk::caller(), ic_cdk::api::time());
+        crowdfund::resolve_dispute(&mut cf, campaign_id, arbiter, refund, note, now)?;
+    }
+    ic_cdk::futures::spawn(process_refund_queue());
+    Ok(())
+}
+
+/// Pay a successful campaign's funds, minus the protocol fee, to its creator
+#[update(guard = "caller_is_not_session")]
+async fn withdraw_campaign_funds(campaign_id: u64) -> Result<Nat, String> {
+    let caller = ic_cdk::caller();
+    let payout = {
+        let mut cf = CF_POOL.lock().unwrap();
+        crowdfund::begin_payout(&mut cf, campaign_id, caller, ic_cdk::api::time())?
+    };
+
+    let token_principal = POOL.lock().unwrap().token_canisters.get(&payout.token).cloned();
+    let Some(token_principal) = token_principal else {
+        crowdfund::abort_payout(&mut CF_POOL.lock().unwrap(), campaign_id, &payout);
+        return Err(format!("token {} not supported", payout.token));
+    };
+    if !dip20::send(token_principal, payout.recipient, payout.amount.clone()).await {
+        crowdfund::abort_payout(&mut CF_POOL.lock().unwrap(), campaign_id, &payout);
+        return Err("payout transfer failed, try again".to_string());
+    }
+    let fee_paid = match payout.treasury {
+        Some(treasury) if payout.fee != 0u64 => {
+            dip20::send(token_principal, treasury, payout.fee.clone()).await
+        }
+        _ => payout.fee == 0u64,
+    };
+
+    let amount = payout.amount.clone();
+    let mut cf = CF_POOL.lock().unwrap();
+    if !fee_paid {
+        crowdfund::owe_fee(&mut cf, &payout.token, &payout.fee);
+    }
+    crowdfund::complete_payout(&mut cf, campaign_id, caller, payout, ic_cdk::api::time());
+    Ok(amount)
+}
+
+/// Protocol fees held in the pool because the treasury was unset or the
+/// transfer failed
+#[query]
+fn get_unpaid_fees() -> Vec<StableBalanceEntry> {
+    let cf = CF_POOL.lock().unwrap();
+    let mut fees: Vec<StableBalanceEntry> = cf
+        .unpaid_fees
+        .iter()
+        .map(|(token, value)| StableBalanceEntry { token: token.clone(), value: value.clone() })
+        .collect();
+    fees.sort_by(|a, b| a.token.cmp(&b.token));
+    fees
+}
+
+/// Send unpaid protocol fees to the treasury; returns what was sent (admin)
+#[update(guard = "caller_is_controller")]
+async fn pay_unpaid_fees() -> Result<Vec<StableBalanceEntry>, String> {
+    let (treasury, owed) = {
+        let mut cf = CF_POOL.lock().unwrap();
+        let treasury = cf.config.treasury.ok_or("no treasury configured")?;
+        (treasury, std::mem::take(&mut cf.unpaid_fees))
+    };
+    let mut paid = vec![];
+    for (token, fee) in owed {
+        let token_principal = POOL.lock().unwrap().token_canisters.get(&token).cloned();
+        let sent = match token_principal {
+            Some(token_principal) => dip20::send(token_principal, treasury, fee.clone()).await,
+            None => false,
+        };
+        if sent {
+            paid.push(StableBalanceEntry { token, value: fee });
+        } else {
+            crowdfund::owe_fee(&mut CF_POOL.lock().unwrap(), &token, &fee);
+        }
+    }
+    Ok(paid)
+}
+
+/// Split the goal into milestones released by backer vote; only before the first contribution
+#[update(guard = "caller_is_not_session")]
+fn set_campaign_milestones(campaign_id: u64, milestones: Vec<MilestoneSpec>) -> Result<(), String> {
+    let mut cf = CF_POOL.lock().unwrap();
+    crowdfund::set_milestones(&mut cf, campaign_id, ic_cdk::caller(), milestones, ic_cdk::api::time())
+}
+
+/// Open the backer vote on the next milestone; returns its index
+#[update(guard = "caller_is_not_session")]
+fn request_milestone_release(campaign_id: u64) -> Result<u32, String> {
+    let mut cf = CF_POOL.lock().unwrap();
+    crowdfund::request_release(&mut cf, campaign_id, ic_cdk::caller(), ic_cdk::api::time())
+}
+
+#[update(guard = "caller_is_not_session")]
+fn vote_milestone(campaign_id: u64, approve: bool) -> Result<(), String> {
+    let mut cf = CF_POOL.lock().unwrap();
+    let user = ic_cdk::caller().to_text();
+    crowdfund::vote(&mut cf, campaign_id, &user, approve, ic_cdk::api::time())
+}
+
+// ---------------- QUADRATIC FUNDING ----------------
+
+/// Escrow a matching pool from the caller and open a round over the given campaigns
+#[update(guard = "caller_is_not_session")]
+async fn create_matching_round(args: MatchingRoundArgs) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("anonymous callers cannot sponsor rounds".to_string());
+    }
+    if !POOL.lock().unwrap().supported_tokens.contains(&args.token) {
+        return Err(format!("token {} not supported", args.token));
+    }
+    matching::validate(&CF_POOL.lock().unwrap(), &args, ic_cdk::api::time())?;
+
+    let token_principal = POOL
+        .lock()
+        .unwrap()
+        .token_canisters
+        .get(&args.token)
+        .cloned()
+        .ok_or("token canister not configured")?;
+    let (token, amount) = (args.token.clone(), args.matching_pool.clone());
+    if !dip20::transfer(token_principal, caller, canister_self(), amount.clone()).await {
+        return Err("matching pool transfer failed".to_string());
+    }
+
+    // Campaigns may have ended while the transfer was in flight
+    let opened = {
+        let mut cf = CF_POOL.lock().unwrap();
+        let now = ic_cdk::api::time();
+        matching::validate(&cf, &args, now).map(|()| matching::open(&mut cf, caller, args, now))
+    };
+    if opened.is_err() && !dip20::send(token_principal, caller, amount.clone()).await {
+        ic_cdk::print(format!("Returning {} {} to {} failed", amount, token, caller));
+    }
+    opened
+}
+
+#[query]
+fn get_matching_round(round_id: u64) -> Option<MatchingRound> {
+    CF_POOL.lock().unwrap().rounds.get(&round_id).cloned()
+}
+
+#[query]
+fn list_matching_rounds() -> Vec<MatchingRound> {
+    CF_POOL.lock().unwrap().rounds.values().cloned().collect()
+}
+
+/// Per-campaign matches: final once the round closed, projected while it is open
+#[query]
+fn get_round_matches(round_id: u64) -> Vec<CampaignMatch> {
+    matching::breakdown(&CF_POOL.lock().unwrap(), round_id, ic_cdk::api::time())
+}
+
+/// Add the caller's funds to an open round's matching pool; returns the new pool size
+#[update(guard = "caller_is_not_session")]
+async fn fund_matching_round(round_id: u64, amount: Nat) -> Result<Nat, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("anonymous callers cannot sponsor rounds".to_string());
+    }
+    let token = {
+        let cf = CF_POOL.lock().unwrap();
+        matching::check_fundable(&cf, round_id, &amount, ic_cdk::api::time())?
+    };
+    let token_principal = POOL
+        .lock()
+        .unwrap()
+        .token_canisters
+        .get(&token)
+        .cloned()
+        .ok_or(format!("no canister for token {}", token))?;
+    if !dip20::transfer(token_principal, caller, canister_self(), amount.clone()).await {
+        return Err("matching funds transfer failed".to_string());
+    }
+
+    // The round may have closed while the transfer was in flight
+    let added = {
+        let mut cf = CF_POOL.lock().unwrap();
+        matching::add_stake(&mut cf, round_id, caller, &amount, ic_cdk::api::time())
+    };
+    if let Err(err) = &added {
+        if !dip20::send(token_principal, caller, amount.clone()).await {
+            ic_cdk::print(format!("Returning {} {} to {} failed", amount, token, caller));
+        }
+        return Err(err.clone());
+    }
+    added
+}
+
+// ---------------- SAVINGS ----------------
+
+/// Move part of the caller's pool balance into savings
+#[update(guard = "caller_is_not_session")]
+fn deposit_savings(token: String, amount: Nat) -> Result<Nat, String> {
+    let mut pool = POOL.lock().unwrap();
+    let account = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    if is_frozen(&pool, &account) {
+        return Err("account is frozen".to_string());
+    }
+    savings::deposit(&mut pool, &account, &token, &amount, ic_cdk::api::time())
+}
+
+/// Move savings and paid interest back to the caller's pool balance
+#[update(guard = "caller_is_not_session")]
+fn withdraw_savings(token: String, amount: Nat) -> Result<Nat, String> {
+    let mut pool = POOL.lock().unwrap();
+    let account = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    if is_frozen(&pool, &account) {
+        return Err("account is frozen".to_string());
+    }
+    savings::withdraw(&mut pool, &account, &token, &amount, ic_cdk::api::time())
+}
+
+#[query]
+fn get_my_savings() -> Vec<SavingsBalance> {
+    let pool = POOL.lock().unwrap();
+    let account = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    savings::balances(&pool, &account, ic_cdk::api::time())
+}
+
+/// Annual savings rate in basis points
+#[query]
+fn get_savings_rate() -> u64 {
+    POOL.lock().unwrap().savings_rate_bps
+}
+
+#[update(guard = "caller_is_controller")]
+fn set_savings_rate(rate_bps: u64) -> Result<(), String> {
+    savings::set_rate(&mut POOL.lock().unwrap(), rate_bps, ic_cdk::api::time())
+}
+
+/// Stability fees collected and not yet paid out as savings interest
+#[query]
+fn get_savings_reserve(token: String) -> Nat {
+    POOL.lock().unwrap().savings_reserve.get(&token).cloned().unwrap_or_default()
+}
+
+// ---------------- STABLECOIN VAULTS ----------------
+
+/// DIP-20 canister of the stablecoin; the pool must be its minter. The
+/// stablecoin also becomes a pool token, so it can be deposited into savings.
+#[update(guard = "caller_is_controller")]
+fn set_stablecoin_canister(principal: Principal) {
+    let mut pool = POOL.lock().unwrap();
+    pool.stablecoin_canister = Some(principal);
+    let symbol = vaults::STABLE_SYMBOL.to_string();
+    if !pool.supported_tokens.contains(&symbol) {
+        pool.supported_tokens.push(symbol.clone());
+    }
+    pool.token_canisters.insert(symbol, principal);
+}
+
+#[update(guard = "caller_is_controller")]
+fn set_vault_config(config: VaultConfig) -> Result<(), String> {
+    vaults::set_config(&mut POOL.lock().unwrap(), config, ic_cdk::api::time())
+}
+
+#[query]
+fn get_vault_config() -> VaultConfig {
+    POOL.lock().unwrap().vault_config.clone()
+}
+
+/// Collateral factor in force for each supported token
+#[query]
+fn get_collateral_factors() -> Vec<CollateralFactor> {
+    vaults::factors(&POOL.lock().unwrap())
+}
+
+/// Move the caller's tokens into their vault
+#[update(guard = "caller_is_not_session")]
+async fn deposit_vault_collateral(token: String, amount: Nat) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let (account, token_principal) = {
+        let pool = POOL.lock().unwrap();
+        let account = accounts::account_of(&pool, &caller.to_text());
+        if is_frozen(&pool, &account) {
+            return Err("account is frozen".to_string());
+        }
+        let token_principal = pool.token_canisters.get(&token).cloned();
+        (account, token_principal.ok_or(format!("token {} not supported", token))?)
+    };
+    if !dip20::transfer(token_principal, caller, canister_self(), amount.clone()).await {
+        return Err("collateral transfer failed".to_string());
+    }
+    let mut pool = POOL.lock().unwrap();
+    let now = ic_cdk::api::time();
+    vaults::add_collateral(&mut pool, &account, &token, &amount, now);
+    let credit = ledger::credit(&account, BalanceKind::VaultCollateral, &token, &amount);
+    ledger::record(&mut pool, TransactionKind::VaultDeposit, vec![credit], now);
+    Ok(())
+}
+
+/// Send vault collateral back to the caller if the vault stays healthy
+#[update(guard = "caller_is_not_session")]
+async fn withdraw_vault_collateral(token: String, amount: Nat) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let (account, token_principal) = {
+        let mut pool = POOL.lock().unwrap();
+        let account = accounts::account_of(&pool, &caller.to_text());
+        if is_frozen(&pool, &account) {
+            return Err("account is frozen".to_string());
+        }
+        let token_principal = pool.token_canisters.get(&token).cloned();
+        let token_principal = token_principal.ok_or(format!("token {} not supported", token))?;
+        vaults::remove_collateral(&mut pool, &account, &token, &amount, ic_cdk::api::time())?;
+        (account, token_principal)
+    };
+    if !dip20::send(token_principal, caller, amount.clone()).await {
+        let mut pool = POOL.lock().unwrap();
+        vaults::add_collateral(&mut pool, &account, &token, &amount, ic_cdk::api::time());
+        return Err("collateral transfer failed".to_string());
+    }
+    let debit = ledger::debit(&account, BalanceKind::VaultCollateral, &token, &amount);
+    let now = ic_cdk::api::time();
+    ledger::record(&mut POOL.lock().unwrap(), TransactionKind::VaultWithdrawal, vec![debit], now);
+    Ok(())
+}
+
+/// Mint stablecoin to the caller against their vault
+#[update(guard = "caller_is_not_session")]
+async fn mint_stable(amount: Nat) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let (account, stable) = {
+        let mut pool = POOL.lock().unwrap();
+        let stable = pool.stablecoin_canister.ok_or("stablecoin canister not configured")?;
+        let account = accounts::account_of(&pool, &caller.to_text());
+        if is_frozen(&pool, &account) {
+            return Err("account is frozen".to_string());
+        }
+        vaults::draw(&mut pool, &account, &amount, ic_cdk::api::time())?;
+        (account, stable)
+    };
+    if !dip20::mint(stable, caller, amount.clone()).await {
+        vaults::undraw(&mut POOL.lock().unwrap(), &account, &amount);
+        return Err("stablecoin mint failed".to_string());
+    }
+    let mut pool = POOL.lock().unwrap();
+    log_mint(&mut pool, &account, vaults::STABLE_SYMBOL, &amount);
+    let credit = ledger::credit(&account, BalanceKind::VaultDebt, vaults::STABLE_SYMBOL, &amount);
+    ledger::record(&mut pool, TransactionKind::StableMint, vec![credit], ic_cdk::api::time());
+    Ok(())
+}
+
+/// Pay back stablecoin, fees first; repaid debt is burned and anything above
+/// what is owed is returned
+#[update(guard = "caller_is_not_session")]
+async fn repay_stable(amount: Nat) -> Result<Nat, String> {
+    let caller = ic_cdk::caller();
+    let (account, stable) = {
+        let pool = POOL.lock().unwrap();
+        let stable = pool.stablecoin_canister.ok_or("stablecoin canister not configured")?;
+        let account = accounts::account_of(&pool, &caller.to_text());
+        if !pool.vaults.contains_key(&account) {
+            return Err("no vault".to_string());
+        }
+        (account, stable)
+    };
+    if !dip20::transfer(stable, caller, canister_self(), amount.clone()).await {
+        return Err("stablecoin transfer failed".to_string());
+    }
+    let now = ic_cdk::api::time();
+    let (repaid, excess) = vaults::repay(&mut POOL.lock().unwrap(), &account, &amount, now);
+    if repaid != 0u64 && !dip20::burn(stable, repaid.clone()).await {
+        ic_cdk::print(format!("Burning {} repaid {} failed", repaid, vaults::STABLE_SYMBOL));
+    }
+    if excess != 0u64 && !dip20::send(stable, caller, excess.clone()).await {
+        let symbol = vaults::STABLE_SYMBOL;
+        ic_cdk::print(format!("Returning {} {} to {} failed", excess, symbol, caller));
+    }
+    Ok(Nat::from(&amount.0 - &excess.0))
+}
+
+#[query]
+fn get_vault(user: String) -> Option<VaultView> {
+    let pool = POOL.lock().unwrap();
+    let user = accounts::account_of(&pool, &user);
+    vaults::view(&pool, &user, ic_cdk::api::time())
+}
+
+/// Swap stablecoin for collateral of the riskiest vaults at face value minus
+/// the redemption fee. The redeemed stablecoin is burned with the debt it
+/// cleared; what no vault could absorb is sent back
+#[update(guard = "caller_is_not_session")]
+async fn redeem_stable(amount: Nat) -> Result<Redemption, String> {
+    let caller = ic_cdk::caller();
+    let (account, stable) = {
+        let pool = POOL.lock().unwrap();
+        let stable = pool.stablecoin_canister.ok_or("stablecoin canister not configured")?;
+        let account = accounts::account_of(&pool, &caller.to_text());
+        if is_frozen(&pool, &account) {
+            return Err("account is frozen".to_string());
+        }
+        (account, stable)
+    };
+    if !dip20::transfer(stable, caller, canister_self(), amount.clone()).await {
+        return Err("stablecoin transfer failed".to_string());
+    }
+    let now = ic_cdk::api::time();
+    let redeemed = vaults::redeem(&mut POOL.lock().unwrap(), &account, &amount, now);
+    let redemption = match redeemed {
+        Ok(redemption) => redemption,
+        Err(e) => {
+            if !dip20::send(stable, caller, amount.clone()).await {
+                let symbol = vaults::STABLE_SYMBOL;
+                ic_cdk::print(format!("Returning {} {} to {} failed", amount, symbol, caller));
+            }
+            return Err(e);
+        }
+    };
+    let unredeemed = Nat::from(&amount.0 - &redemption.amount.0);
+    if unredeemed != 0u64 && !dip20::send(stable, caller, unredeemed.clone()).await {
+        let symbol = vaults::STABLE_SYMBOL;
+        ic_cdk::print(format!("Returning {} {} to {} failed", unredeemed, symbol, caller));
+    }
+    if !dip20::burn(stable, redemption.amount.clone()).await {
+        let symbol = vaults::STABLE_SYMBOL;
+        ic_cdk::print(format!("Burning {} redeemed {} failed", redemption.amount, symbol));
+    }
+    let mut payout: BTreeMap<String, Nat> = BTreeMap::new();
+    for entry in redemption.vaults.iter().flat_map(|v| &v.collateral) {
+        let total = payout.entry(entry.token.clone()).or_default();
+        *total = Nat::from(&total.0 + &entry.value.0);
+    }
+    for (token, value) in payout {
+        let canister = POOL.lock().unwrap().token_canisters.get(&token).cloned();
+        let sent = match canister {
+            Some(canister) => dip20::send(canister, caller, value.clone()).await,
+            None => false,
+        };
+        if !sent {
+            // Park it in the redeemer's vault, withdrawable like any collateral
+            let mut pool = POOL.lock().unwrap();
+            let now = ic_cdk::api::time();
+            vaults::add_collateral(&mut pool, &account, &token, &value, now);
+            let credit = ledger::credit(&account, BalanceKind::VaultCollateral, &token, &value);
+            ledger::record(&mut pool, TransactionKind::VaultDeposit, vec![credit], now);
+        }
+    }
+    Ok(redemption)
+}
+
+/// Vault accounts in the order redemptions reach them, riskiest first
+#[query]
+fn get_redemption_order(limit: u64) -> Vec<String> {
+    let mut order = vaults::redemption_order(&POOL.lock().unwrap(), ic_cdk::api::time());
+    order.truncate(limit as usize);
+    order
+}
+
+/// Redemptions the caller made or that reached the caller's vault
+#[query]
+fn get_my_redemptions() -> Vec<Redemption> {
+    let pool = POOL.lock().unwrap();
+    let account = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    vaults::redemptions_of(&pool, &account)
+}
+
+// ---------------- RATE HISTORY ----------------
+
+/// Daily supply and borrow rates of `token` between `from` and `to` (ns
+/// since epoch), oldest first
+#[query]
+fn get_rate_history(token: String, from: u64, to: u64) -> Result<Vec<RatePoint>, String> {
+    rates::history(&POOL.lock().unwrap(), &token, from, to)
+}
+
+// ---------------- INVOICES ----------------
+
+/// Bill `payer` for `amount` of `token`, payable to the caller's pool balance
+#[update(guard = "caller_is_not_session")]
+fn create_invoice(
+    payer: Principal,
+    token: String,
+    amount: Nat,
+    memo: String,
+    due_date: u64,
+) -> Result<u64, String> {
+    let mut pool = POOL.lock().unwrap();
+    let issuer = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    if is_frozen(&pool, &issuer) {
+        return Err("account is frozen".to_string());
+    }
+    let payer = accounts::account_of(&pool, &payer.to_text());
+    let draft = Invoice {
+        id: 0,
+        issuer,
+        payer,
+        token,
+        amount,
+        memo,
+        due_date,
+        created_at: 0,
+        status: InvoiceStatus::Open,
+        paid_at: None,
+        overdue: false,
+    };
+    invoices::create(&mut pool, draft, ic_cdk::api::time())
+}
+
+/// Pay an invoice addressed to the caller, from their pool balance when it
+/// covers the amount and by token transfer otherwise
+#[update(guard = "caller_is_not_session")]
+async fn pay_invoice(id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let transfer = {
+        let mut pool = POOL.lock().unwrap();
+        let account = accounts::account_of(&pool, &caller.to_text());
+        if is_frozen(&pool, &account) {
+            return Err("account is frozen".to_string());
+        }
+        invoices::begin_payment(&mut pool, &account, id, ic_cdk::api::time())?
+    };
+    let Some((token_principal, amount)) = transfer else { return Ok(()) };
+    let transferred = dip20::transfer(token_principal, caller, canister_self(), amount).await;
+    invoices::finish_payment(&mut POOL.lock().unwrap(), id, transferred, ic_cdk::api::time());
+    if !transferred {
+        return Err("token transfer failed".to_string());
+    }
+    Ok(())
+}
+
+#[update(guard = "caller_is_not_session")]
+fn cancel_invoice(id: u64) -> Result<(), String> {
+    let mut pool = POOL.lock().unwrap();
+    let account = accounts::account_of(&pool, &ic_cdk::caller().to_text());
+    invoices::cancel(&mut pool, &account, id, ic_cdk::api::time())
+}
+
+/// Invoices the caller's account issued or owes, newest first
+#[query]
+fn get_my_invoices() -> Vec<Invoice> {
+    let pool = POOL.lock().unwrap();
+    invoices::of_account(&pool, &accounts::account_of(&pool, &ic_cdk::caller().to_text()))
+}
+
+/// Received, paid, cancelled and overdue notices of the caller's invoices,
+/// oldest first
+#[query]
+fn get_invoice_notices() -> Vec<InvoiceNotice> {
+    let pool = POOL.lock().unwrap();
+    invoices::notices(&pool, &accounts::account_of(&pool, &ic_cdk::caller().to_text()))
+}
+
+// ---------------- TEST FAUCET ----------------
+
+/// Mint the faucet amount of `token` to the caller, once per cooldown
+#[update(guard = "caller_is_not_session")]
+async fn claim_faucet(token: String) -> Result<Nat, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("sign in to use the faucet".to_string());
+    }
+    let (account, token_principal, amount, previous) = {
+        let mut pool = POOL.lock().unwrap();
+        let account = accounts::account_of(&pool, &caller.to_text());
+        let token_principal = pool.token_canisters.get(&token).cloned();
+        let token_principal = token_principal.ok_or(format!("token {} not supported", token))?;
+        let now = ic_cdk::api::time();
+        let (amount, previous) = faucet::begin_claim(&mut pool, &account, &token, now)?;
+        (account, token_principal, amount, previous)
+    };
+    if !dip20::mint(token_principal, caller, amount.clone()).await {
+        faucet::undo_claim(&mut POOL.lock().unwrap(), &account, &token, previous);
+        return Err("faucet mint failed".to_string());
+    }
+    log_mint(&mut POOL.lock().unwrap(), &account, &token, &amount);
+    Ok(amount)
+}
+
+/// Amounts and cooldown of the faucet; `None` when it is disabled
+#[query]
+fn get_faucet_config() -> Option<FaucetConfig> {
+    let pool = POOL.lock().unwrap();
+    pool.faucet_enabled.then(|| pool.faucet_config.clone())
+}
+
+#[update(guard = "caller_is_controller")]
+fn set_faucet_config(config: FaucetConfig) -> Result<(), String> {
+    faucet::set_config(&mut POOL.lock().unwrap(), config)
+}
+
+// ---------------- OPERATION LEDGER ----------------
+
+/// Tip of the operation ledger
+#[query]
+fn get_network_status() -> NetworkStatus {
+    ledger::network_status(&POOL.lock().unwrap(), canister_self())
+}
+
+#[query]
+fn get_block(index: u64) -> Option<LedgerBlock> {
+    ledger::block(&POOL.lock().unwrap(), index)
+}
+
+/// Blocks from `from` on, at most 100 per call
+#[query]
+fn get_blocks(from: u64, limit: u64) -> Vec<LedgerBlock> {
+    ledger::blocks(&POOL.lock().unwrap(), from, limit)
+}
+
+// ---------------- HTTP GATEWAY ----------------
+
+/// `/metrics` (Prometheus) and `/health` for scraping through the HTTP gateway
+#[query]
+fn http_request(request: HttpRequest) -> HttpResponse {
+    metrics::handle(&POOL.lock().unwrap(), &request)
+}
+
+// ---------------- BACKUP & RESTORE ----------------
+
+/// Chunk `chunk_index` of a full state backup; chunk 0 starts a new backup
+#[update(guard = "caller_is_controller")]
+fn backup_state(chunk_index: u64) -> Result<StateChunk, String> {
+    backup::backup_chunk(chunk_index, ic_cdk::api::time())
+}
+
+/// Feed backup chunks back in order; the last one replaces all state and
+/// returns the hash of the restored state
+#[update(guard = "caller_is_controller")]
+fn restore_state(chunk: StateChunk) -> Result<Option<String>, String> {
+    backup::restore_chunk(chunk)
+}
+
+/// Hex SHA-256 of the current state, comparable with a backup's `state_hash`
+#[query(guard = "caller_is_controller")]
+fn get_state_hash() -> String {
+    backup::state_hash()
+}
+
+/// Where the caller's stake in a round went; projected while the round is open
+#[query]
+fn get_sponsor_report(round_id: u64) -> Option<SponsorReport> {
+    let cf = CF_POOL.lock().unwrap();
+    matching::report(&cf, round_id, ic_cdk::caller(), ic_cdk::api::time())
+}
+
+/// Reports for every round the caller has funded
+#[query]
+fn get_my_sponsor_reports() -> Vec<SponsorReport> {
+    matching::reports_of(&CF_POOL.lock().unwrap(), ic_cdk::caller(), ic_cdk::api::time())
+}
+
+/// Return the caller's share of unallocated funds and failed campaigns' matches
+#[update(guard = "caller_is_not_session")]
+async fn reclaim_matching_funds(round_id: u64) -> Result<Nat, String> {
+    let caller = ic_cdk::caller();
+    let reclaim = {
+        let mut cf = CF_POOL.lock().unwrap();
+        matching::begin_reclaim(&mut cf, round_id, caller, ic_cdk::api::time())?
+    };
+
+    // Nothing is reclaimed unless the tokens actually leave the pool
+    let token_principal = POOL
+        .lock()
+        .unwrap()
+        .token_canisters
+        .get(&reclaim.token)
+        .cloned()
+        .ok_or(format!("no canister for token {}", reclaim.token));
+    let token_principal = match token_principal {
+        Ok(token_principal) => token_principal,
+        Err(err) => {
+            matching::abort_reclaim(&mut CF_POOL.lock().unwrap(), round_id, caller, reclaim);
+            return Err(err);
+        }
+    };
+    if !dip20::send(token_principal, caller, reclaim.amount.clone()).await {
+        let amount = reclaim.amount.clone();
+        matching::abort_reclaim(&mut CF_POOL.lock().unwrap(), round_id, caller, reclaim);
+        return Err(format!("transfer of {} failed, try again", amount));
+    }
+    Ok(reclaim.amount)
+}
+
+#[update(guard = "caller_is_controller")]
+fn set_crowdfund_config(config: CrowdfundConfig) -> Result<(), String> {
+    crowdfund::set_config(&mut CF_POOL.lock().unwrap(), config)
+}
+
+#[query]
+fn get_crowdfund_config() -> CrowdfundConfig {
+    CF_POOL.lock().unwrap().config.clone()
+}
+
+/// Move the caller's tokens into the pool's escrow for a campaign
+#[update(guard = "caller_is_not_session")]
+async fn contribute_crowdfund(campaign_id: u64, amount: Nat) -> bool {
+    let caller = ic_cdk::caller();
+
+    if is_frozen(&POOL.lock().unwrap(), &caller.to_text()) {
+        return false;
+    }
+
+    // Step 1: Check the campaign still takes contributions
+    let checked = {
+        let mut cf = CF_POOL.lock().unwrap();
+        let now = ic_cdk::api::time();
+        crowdfund::check_open(&mut cf, campaign_id, &caller.to_text(), &amount, now)
+    };
+    let token = match checked {
+        Ok(token) => token,
+        Err(err) => {
+            ic_cdk::print(format!("Contribution to campaign {} failed: {}", campaign_id, err));
+            return false;
+        }
+    };
+    let Some(token_principal) = POOL.lock().unwrap().token_canisters.get(&token).cloned() else {
+        ic_cdk::print(format!("Contribution failed: no canister for token {}", token));
+        return false;
+    };
+
+    // Step 2: Escrow the contributor's tokens in the pool canister
+    if !dip20::transfer(token_principal, caller, canister_self(), amount.clone()).await {
+        ic_cdk::print("Contribution failed: transferFrom returned false");
+        return false;
+    }
+
+    // Step 3: Record the contribution; the campaign may have closed while the
+    // transfer was in flight, in which case the tokens go back
+    let now = ic_cdk::api::time();
+    let recorded = {
+        let mut cf = CF_POOL.lock().unwrap();
+        let recorded = crowdfund::contribute(&mut cf, campaign_id, &caller.to_text(), &amount, now);
+        if recorded.is_ok() {
+            matching::record(&mut cf, campaign_id, &caller.to_text(), &amount, now);
+            receipts::issue(&mut cf, campaign_id, &caller.to_text(), now);
+        }
+        recorded
+    };
+    if let Err(err) = recorded {
+        ic_cdk::print(format!("Contribution to campaign {} returned: {}", campaign_id, err));
+        if !dip20::send(token_principal, caller, amount.clone()).await {
+            ic_cdk::print(format!("Returning {} {} to {} failed", amount, token, caller));
+        }
+        return false;
+    }
+
+    let mut pool = POOL.lock().unwrap();
+    let usd = usd_value(&token, &amount);
+    record_activity(&mut pool, &caller.to_text(), ActivityKind::Crowdfund, usd);
+    true
+}
+
+// ---------------- ACTIVITY MONITORING (admin) ----------------
+
+/// Run the anomaly monitor immediately instead of waiting for the timer
+#[update(guard = "caller_is_controller")]
+async fn run_activity_monitor_now() {
+    monitor::run_activity_monitor().await;
+}
+
+#[update(guard = "caller_is_controller")]
+fn unfreeze_account(user: String) -> bool {
+    let mut pool = POOL.lock().unwrap();
+    match pool.users.get_mut(&user) {
+        Some(account) => {
+            account.frozen = false;
+            account.flagged = false;
+            true
+        }
+        None => false,
+    }
+}
+
+#[query(guard = "caller_is_controller")]
+fn get_account_flags() -> Vec<AccountFlag> {
+    let pool = POOL.lock().unwrap();
+    pool.account_flags.clone()
+}
+
+// ---------------- EVENT DELIVERY ----------------
+
+/// Push a topic's events to `canister` (controllers only); it must expose
+/// `on_pool_events : (EventBatch) -> (nat64)`
+#[update(guard = "caller_is_controller")]
+fn register_event_subscriber(
+    canister: Principal,
+    topic: EventTopic,
+    from_seq: Option<u64>,
+) -> Result<u64, String> {
+    let mut pool = POOL.lock().unwrap();
+    let head = pubsub::head(&pool, &CF_POOL.lock().unwrap(), topic);
+    pubsub::register(&mut pool, canister, topic, from_seq, head, ic_cdk::api::time())
+}
+
+#[update(guard = "caller_is_controller")]
+fn remove_event_subscriber(subscription_id: u64) -> bool {
+    POOL.lock().unwrap().event_subscribers.remove(&subscription_id).is_some()
+}
+
+/// Retry a subscriber paused after repeated failed deliveries
+#[update(guard = "caller_is_controller")]
+fn resume_event_subscriber(subscription_id: u64) -> bool {
+    pubsub::resume(&mut POOL.lock().unwrap(), subscription_id, ic_cdk::api::time())
+}
+
+#[query(guard = "caller_is_controller")]
+fn get_event_subscribers() -> Vec<EventSubscriber> {
+    POOL.lock().unwrap().event_subscribers.values().cloned().collect()
+}
+
+/// Called by a subscriber to mark events before `cursor` as processed
+#[update]
+fn ack_events(subscription_id: u64, cursor: u64) -> Result<(), String> {
+    let mut pool = POOL.lock().unwrap();
+    let topic = pool.event_subscribers.get(&subscription_id).ok_or("subscription not found")?.topic;
+    let head = pubsub::head(&pool, &CF_POOL.lock().unwrap(), topic);
+    pubsub::ack(&mut pool, subscription_id, ic_cdk::caller(), cursor, head)
+}
+
+/// Read a topic's log directly, for controllers and subscribers of the topic
+/// catching up
+#[query]
+fn get_events(topic: EventTopic, from_seq: u64, limit: u64) -> Result<Vec<PoolEvent>, String> {
+    let caller = ic_cdk::caller();
+    let pool = POOL.lock().unwrap();
+    let subscribed =
+        pool.event_subscribers.values().any(|s| s.canister == caller && s.topic == topic);
+    if !subscribed && !is_admin(&caller) {
+        return Err("caller is not subscribed to this topic".to_string());
+    }
+    let cf = CF_POOL.lock().unwrap();
+    Ok(pubsub::read(&pool, &cf, topic, from_seq, limit as usize))
 }
 
 // ---------------- QUERIES ----------------
+/// Token-wide totals per contributor; prefer `search_campaigns` for browsing
 #[query]
 fn get_crowdfund_status() -> Vec<CrowdfundEntry> {
     let cf = CF_POOL.lock().unwrap();
@@ -478,12 +2463,14 @@ fn get_stable_token() -> StableToken {
 #[query]
 fn get_user_account(user: String) -> Option<UserAccount> {
     let pool = POOL.lock().unwrap();
+    let user = accounts::account_of(&pool, &user);
     pool.users.get(&user).cloned()
 }
 
 #[query]
 fn get_user_balances(user: String) -> Vec<StableBalanceEntry> {
     let pool = POOL.lock().unwrap();
+    let user = accounts::account_of(&pool, &user);
     let mut result = vec![];
     if let Some(balances) = pool.stablecoin_balances.get(&user) {
         for (token, amt) in balances.iter() {
@@ -499,12 +2486,14 @@ fn get_user_balances(user: String) -> Vec<StableBalanceEntry> {
 #[query]
 fn get_user_collateral(user: String) -> Option<HashMap<String, Nat>> {
     let pool = POOL.lock().unwrap();
+    let user = accounts::account_of(&pool, &user);
     pool.collateral.get(&user).cloned()
 }
 
 #[query]
 fn get_balance(user: String, token: String) -> Nat {
     let pool = POOL.lock().unwrap();
+    let user = accounts::account_of(&pool, &user);
     pool.stablecoin_balances
         .get(&user)
         .and_then(|m| m.get(&token))
@@ -0,0 +1,597 @@
+// src/defi_pool_backend/matching.rs
+//! Quadratic funding rounds. A sponsor escrows a matching pool for a set of
+//! campaigns, or for every campaign in a category; contributions made to
+//! those campaigns while the round is open are tallied per round. At close
+//! the pool is split in proportion to
+//! `(sum of sqrt(contribution))^2 - sum of contribution` per campaign, so many
+//! small backers attract more matching than one large one.
+//!
+//! Anti-collusion caps: each backer's counted contribution can be capped, and
+//! no campaign may take more than `max_match_bps` of the pool; the excess is
+//! redistributed to the other campaigns.
+//!
+//! Other sponsors can top up an open round. Each sponsor's stake is booked
+//! apart from campaign contributions; matches, leftovers and refunds from
+//! failed campaigns are attributed to sponsors pro rata to their stakes.
+use crate::crowdfund::{self, saturating_sub};
+use crate::types::{
+    CampaignEvent, CampaignEventKind, CampaignMatch, CampaignStatus, MatchingRound,
+    MatchingRoundArgs, RoundStatus, SponsorCampaignMatch, SponsorReport,
+};
+use crate::{sybil, CrowdfundingPool};
+use candid::{CandidType, Nat, Principal};
+use num_bigint::BigUint;
+use num_traits::cast::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Longest a round may stay open
+const MAX_ROUND_NANOS: u64 = 365 * 86_400 * 1_000_000_000;
+/// Campaigns a single round can match
+pub const MAX_ROUND_CAMPAIGNS: usize = 100;
+/// Resolution of pool shares when converting back to token units
+const SHARE_SCALE: u64 = 1_000_000_000_000;
+
+/// Check a round can be opened before the sponsor's funds are taken
+pub fn validate(cf: &CrowdfundingPool, args: &MatchingRoundArgs, now: u64) -> Result<(), String> {
+    if args.matching_pool == 0u64 {
+        return Err("matching_pool must be positive".to_string());
+    }
+    if args.ends_at <= now || args.ends_at - now > MAX_ROUND_NANOS {
+        return Err("ends_at must be in the future and within a year".to_string());
+    }
+    if args.max_match_bps == 0 || args.max_match_bps > 10_000 {
+        return Err("max_match_bps must be in 1-10000".to_string());
+    }
+    if args.unverified_weight_bps.is_some_and(|bps| bps > 10_000) {
+        return Err("unverified_weight_bps must be at most 10000".to_string());
+    }
+    if args.campaign_ids.len() > MAX_ROUND_CAMPAIGNS {
+        return Err(format!("a round lists at most {} campaigns", MAX_ROUND_CAMPAIGNS));
+    }
+    if args.campaign_ids.is_empty() && args.category.is_none() {
+        return Err("a round needs campaign_ids or a category".to_string());
+    }
+    for id in &args.campaign_ids {
+        let campaign = cf.campaigns.get(id).ok_or(format!("campaign {} not found", id))?;
+        if campaign.token != args.token {
+            return Err(format!("campaign {} raises {}, not {}", id, campaign.token, args.token));
+        }
+        if crowdfund::view(campaign, now).status != CampaignStatus::Active {
+            return Err(format!("campaign {} is not active", id));
+        }
+    }
+    Ok(())
+}
+
+/// Register a funded round; returns its ID
+pub fn open(
+    cf: &mut CrowdfundingPool,
+    sponsor: Principal,
+    mut args: MatchingRoundArgs,
+    now: u64,
+) -> u64 {
+    args.campaign_ids.sort_unstable();
+    args.campaign_ids.dedup();
+    cf.next_round_id += 1;
+    let id = cf.next_round_id;
+    cf.rounds.insert(
+        id,
+        MatchingRound {
+            id,
+            sponsor,
+            token: args.token,
+            matching_pool: Nat::from(0u64),
+            campaign_ids: args.campaign_ids,
+            category: args.category,
+            created_at: now,
+            ends_at: args.ends_at,
+            max_match_bps: args.max_match_bps,
+            max_counted_contribution: args.max_counted_contribution,
+            unverified_weight_bps: args.unverified_weight_bps,
+            status: RoundStatus::Open,
+            unallocated: Nat::from(0u64),
+            reclaimed: Nat::from(0u64),
+        },
+    );
+    // The opening sponsor holds the first stake
+    let _ = add_stake(cf, id, sponsor, &args.matching_pool, now);
+    id
+}
+
+/// Campaigns a round matches: those listed plus, for category rounds, every
+/// campaign in the category raising the round's token
+fn round_campaigns(cf: &CrowdfundingPool, round: &MatchingRound) -> Vec<u64> {
+    let mut ids = round.campaign_ids.clone();
+    if let Some(category) = round.category {
+        let in_category = cf.category_index.get(&category).into_iter().flatten();
+        ids.extend(in_category.filter(|id| {
+            cf.campaigns.get(id).is_some_and(|c| c.token == round.token)
+        }));
+        ids.sort_unstable();
+        ids.dedup();
+    }
+    ids
+}
+
+fn covers(cf: &CrowdfundingPool, round: &MatchingRound, campaign_id: u64) -> bool {
+    round.campaign_ids.contains(&campaign_id)
+        || round.category.is_some_and(|category| {
+            cf.campaigns
+                .get(&campaign_id)
+                .is_some_and(|c| c.metadata.category == category && c.token == round.token)
+        })
+}
+
+/// Tally a contribution against every open round matching the campaign
+pub fn record(cf: &mut CrowdfundingPool, campaign_id: u64, user: &str, amount: &Nat, now: u64) {
+    let open: Vec<u64> = cf
+        .rounds
+        .values()
+        .filter(|r| r.status == RoundStatus::Open && now < r.ends_at)
+        .filter(|r| covers(cf, r, campaign_id))
+        .map(|r| r.id)
+        .collect();
+    for round_id in open {
+        let backers = cf.round_contributions.entry((round_id, campaign_id)).or_default();
+        let entry = backers.entry(user.to_string()).or_insert(Nat::from(0u64));
+        *entry = Nat::from(&entry.0 + &amount.0);
+    }
+}
+
+fn to_f64(n: &Nat) -> f64 {
+    n.0.to_f64().unwrap_or(0.0)
+}
+
+/// Per-campaign matches under the round's current tallies
+pub fn compute(cf: &CrowdfundingPool, round: &MatchingRound, now: u64) -> Vec<CampaignMatch> {
+    let cap = round.max_counted_contribution.as_ref();
+    let mut matches: Vec<CampaignMatch> = round_campaigns(cf, round)
+        .iter()
+        .map(|id| {
+            let backers = cf.round_contributions.get(&(round.id, *id));
+            let mut contributed = Nat::from(0u64);
+            let (mut sqrt_sum, mut linear_sum) = (0.0, 0.0);
+            let mut verified = 0;
+            for (user, amount) in backers.into_iter().flatten() {
+                contributed = Nat::from(&contributed.0 + &amount.0);
+                let counted = match cap {
+                    Some(cap) if amount > cap => to_f64(cap),
+                    _ => to_f64(amount),
+                };
+                if sybil::is_verified(cf, user, now) {
+                    verified += 1;
+                }
+                // Unverified backers count as w * sqrt(contribution)
+                let w = sybil::weight(cf, round, user, now);
+                sqrt_sum += w * counted.sqrt();
+                linear_sum += w * w * counted;
+            }
+            // Failed campaigns drop out; their share goes to the others
+            let eligible = cf
+                .campaigns
+                .get(id)
+                .is_some_and(|c| crowdfund::view(c, now).status != CampaignStatus::Failed);
+            let weight = (sqrt_sum * sqrt_sum - linear_sum).max(0.0);
+            CampaignMatch {
+                campaign_id: *id,
+                unique_contributors: backers.map_or(0, |b| b.len() as u64),
+                verified_contributors: verified,
+                contributed,
+                quadratic_weight: if eligible { weight } else { 0.0 },
+                matched: Nat::from(0u64),
+                capped: false,
+            }
+        })
+        .collect();
+
+    // Split the pool by weight, pinning campaigns that exceed the cap at the
+    // cap and re-splitting the rest until no share exceeds it
+    let cap_share = round.max_match_bps as f64 / 10_000.0;
+    let mut shares = vec![0.0; matches.len()];
+    loop {
+        let pinned: f64 = matches
+            .iter()
+            .zip(&shares)
+            .filter(|(m, _)| m.capped)
+            .map(|(_, s)| s)
+            .sum();
+        let free_weight: f64 =
+            matches.iter().filter(|m| !m.capped).map(|m| m.quadratic_weight).sum();
+        if free_weight <= 0.0 {
+            break;
+        }
+        let mut newly_capped = false;
+        for (m, share) in matches.iter_mut().zip(shares.iter_mut()) {
+            if m.capped {
+                continue;
+            }
+            *share = (1.0 - pinned) * m.quadratic_weight / free_weight;
+            if *share > cap_share {
+                *share = cap_share;
+                m.capped = true;
+                newly_capped = true;
+            }
+        }
+        if !newly_capped {
+            break;
+        }
+    }
+
+    for (m, share) in matches.iter_mut().zip(shares) {
+        let scaled = BigUint::from((share * SHARE_SCALE as f64) as u64);
+        m.matched = Nat::from(&round.matching_pool.0 * scaled / SHARE_SCALE);
+    }
+    matches
+}
+
+/// Close ended rounds, crediting each campaign's match; run with settlement
+pub fn close_ended(cf: &mut CrowdfundingPool, now: u64) {
+    let ended: Vec<u64> = cf
+        .rounds
+        .values()
+        .filter(|r| r.status == RoundStatus::Open && now >= r.ends_at)
+        .map(|r| r.id)
+        .collect();
+    for id in ended {
+        let round = cf.rounds[&id].clone();
+        let matches = compute(cf, &round, now);
+        let mut allocated = Nat::from(0u64);
+        for m in &matches {
+            if m.matched == 0u64 {
+                continue;
+            }
+            allocated = Nat::from(&allocated.0 + &m.matched.0);
+            if let Some(campaign) = cf.campaigns.get_mut(&m.campaign_id) {
+                campaign.matched = Nat::from(&campaign.matched.0 + &m.matched.0);
+                campaign.escrowed = Nat::from(&campaign.escrowed.0 + &m.matched.0);
+            }
+            cf.events.push(CampaignEvent {
+                campaign_id: m.campaign_id,
+                timestamp: now,
+                kind: CampaignEventKind::Matched { round_id: id, amount: m.matched.clone() },
+            });
+        }
+        let round = cf.rounds.get_mut(&id).unwrap();
+        round.status = RoundStatus::Closed;
+        round.unallocated = saturating_sub(&round.matching_pool, &allocated);
+        cf.round_matches.insert(id, matches);
+        ic_cdk::print(format!("Matching round {} closed, {} allocated", id, allocated));
+    }
+}
+
+/// Final matches of a closed round, or a projection for an open one
+pub fn breakdown(cf: &CrowdfundingPool, round_id: u64, now: u64) -> Vec<CampaignMatch> {
+    match (cf.round_matches.get(&round_id), cf.rounds.get(&round_id)) {
+        (Some(matches), _) => matches.clone(),
+        (None, Some(round)) => compute(cf, round, now),
+        (None, None) => vec![],
+    }
+}
+
+/// One sponsor's part of a round's matching pool
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Stake {
+    pub amount: Nat,
+    pub funded_at: u64,
+    /// Total taken back so far
+    pub reclaimed: Nat,
+    reclaimed_unallocated: Nat,
+    /// Campaign -> amount taken back out of its escrow
+    reclaimed_from: BTreeMap<u64, Nat>,
+}
+
+fn pro_rata(total: &Nat, stake: &Nat, pool: &Nat) -> Nat {
+    if *pool == 0u64 {
+        return Nat::from(0u64);
+    }
+    Nat::from(&total.0 * &stake.0 / &pool.0)
+}
+
+/// Check a round still accepts sponsor funds; returns its token
+pub fn check_fundable(
+    cf: &CrowdfundingPool,
+    round_id: u64,
+    amount: &Nat,
+    now: u64,
+) -> Result<String, String> {
+    if *amount == 0u64 {
+        return Err("amount must be positive".to_string());
+    }
+    let round = cf.rounds.get(&round_id).ok_or("round not found")?;
+    if round.status != RoundStatus::Open || now >= round.ends_at {
+        return Err("round is closed".to_string());
+    }
+    Ok(round.token.clone())
+}
+
+/// Add escrowed sponsor funds to an open round; returns the new pool size
+pub fn add_stake(
+    cf: &mut CrowdfundingPool,
+    round_id: u64,
+    sponsor: Principal,
+    amount: &Nat,
+    now: u64,
+) -> Result<Nat, String> {
+    check_fundable(cf, round_id, amount, now)?;
+    let round = cf.rounds.get_mut(&round_id).ok_or("round not found")?;
+    round.matching_pool = Nat::from(&round.matching_pool.0 + &amount.0);
+    let stake = cf.round_stakes.entry(round_id).or_default().entry(sponsor).or_insert(Stake {
+        amount: Nat::from(0u64),
+        funded_at: now,
+        reclaimed: Nat::from(0u64),
+        reclaimed_unallocated: Nat::from(0u64),
+        reclaimed_from: BTreeMap::new(),
+    });
+    stake.amount = Nat::from(&stake.amount.0 + &amount.0);
+    Ok(round.matching_pool.clone())
+}
+
+/// The round's claim on each failed campaign's escrow: its match minus any
+/// part the campaign already released
+fn failed_shares(cf: &CrowdfundingPool, round_id: u64, now: u64) -> Vec<(u64, Nat)> {
+    let mut shares = vec![];
+    for m in cf.round_matches.get(&round_id).into_iter().flatten() {
+        if m.matched == 0u64 {
+            continue;
+        }
+        let Some(campaign) = cf.campaigns.get(&m.campaign_id) else { continue };
+        if crowdfund::view(campaign, now).status != CampaignStatus::Failed
+            || campaign.matched == 0u64
+        {
+            continue;
+        }
+        let spent = saturating_sub(&campaign.released, &campaign.raised);
+        let unspent = saturating_sub(&campaign.matched, &spent);
+        shares.push((m.campaign_id, Nat::from(&unspent.0 * &m.matched.0 / &campaign.matched.0)));
+    }
+    shares
+}
+
+/// What a sponsor can still take back: (unallocated part, per-campaign parts)
+fn owed(
+    cf: &CrowdfundingPool,
+    round: &MatchingRound,
+    stake: &Stake,
+    now: u64,
+) -> (Nat, Vec<(u64, Nat)>) {
+    if round.status != RoundStatus::Closed {
+        return (Nat::from(0u64), vec![]);
+    }
+    let unallocated = pro_rata(&round.unallocated, &stake.amount, &round.matching_pool);
+    let unallocated = saturating_sub(&unallocated, &stake.reclaimed_unallocated);
+    let campaigns = failed_shares(cf, round.id, now)
+        .into_iter()
+        .map(|(id, share)| {
+            let due = pro_rata(&share, &stake.amount, &round.matching_pool);
+            let taken = stake.reclaimed_from.get(&id).cloned().unwrap_or(Nat::from(0u64));
+            (id, saturating_sub(&due, &taken))
+        })
+        .filter(|(_, amount)| *amount != 0u64)
+        .collect();
+    (unallocated, campaigns)
+}
+
+/// Funds a sponsor takes back from a closed round
+pub struct Reclaim {
+    pub token: String,
+    pub amount: Nat,
+    unallocated: Nat,
+    /// Failed campaigns and the part taken out of each one's escrow
+    campaigns: Vec<(u64, Nat)>,
+}
+
+/// Take back the sponsor's share of the unallocated pool and of the matches
+/// of campaigns that failed
+pub fn begin_reclaim(
+    cf: &mut CrowdfundingPool,
+    round_id: u64,
+    caller: Principal,
+    now: u64,
+) -> Result<Reclaim, String> {
+    let round = cf.rounds.get(&round_id).ok_or("round not found")?;
+    if round.status != RoundStatus::Closed {
+        return Err("round is still open".to_string());
+    }
+    let stake = cf
+        .round_stakes
+        .get(&round_id)
+        .and_then(|stakes| stakes.get(&caller))
+        .ok_or("not a sponsor of this round")?;
+    let (unallocated, campaigns) = owed(cf, round, stake, now);
+    let amount = campaigns
+        .iter()
+        .fold(unallocated.clone(), |sum, (_, part)| Nat::from(&sum.0 + &part.0));
+    if amount == 0u64 {
+        return Err("nothing to reclaim".to_string());
+    }
+    let reclaim = Reclaim { token: round.token.clone(), amount, unallocated, campaigns };
+    apply_reclaim(cf, round_id, caller, &reclaim, true);
+    Ok(reclaim)
+}
+
+/// Allow the reclaim again after the transfer failed
+pub fn abort_reclaim(
+    cf: &mut CrowdfundingPool,
+    round_id: u64,
+    caller: Principal,
+    reclaim: Reclaim,
+) {
+    apply_reclaim(cf, round_id, caller, &reclaim, false);
+}
+
+/// Book a reclaim against the stake, round and campaign escrows, or undo it
+fn apply_reclaim(
+    cf: &mut CrowdfundingPool,
+    round_id: u64,
+    sponsor: Principal,
+    reclaim: &Reclaim,
+    take: bool,
+) {
+    let add = |a: &Nat, b: &Nat| if take { Nat::from(&a.0 + &b.0) } else { saturating_sub(a, b) };
+    let sub = |a: &Nat, b: &Nat| if take { saturating_sub(a, b) } else { Nat::from(&a.0 + &b.0) };
+    if let Some(round) = cf.rounds.get_mut(&round_id) {
+        round.reclaimed = add(&round.reclaimed, &reclaim.amount);
+    }
+    let Some(stake) = cf.round_stakes.get_mut(&round_id).and_then(|s| s.get_mut(&sponsor)) else {
+        return;
+    };
+    stake.reclaimed = add(&stake.reclaimed, &reclaim.amount);
+    stake.reclaimed_unallocated = add(&stake.reclaimed_unallocated, &reclaim.unallocated);
+    for (id, part) in &reclaim.campaigns {
+        let taken = stake.reclaimed_from.entry(*id).or_insert(Nat::from(0u64));
+        *taken = add(taken, part);
+        if let Some(campaign) = cf.campaigns.get_mut(id) {
+            campaign.escrowed = sub(&campaign.escrowed, part);
+        }
+    }
+}
+
+/// Where a sponsor's funds in a round went: final once the round closed,
+/// projected while it is open
+pub fn report(
+    cf: &CrowdfundingPool,
+    round_id: u64,
+    sponsor: Principal,
+    now: u64,
+) -> Option<SponsorReport> {
+    let round = cf.rounds.get(&round_id)?;
+    let stake = cf.round_stakes.get(&round_id)?.get(&sponsor)?;
+    let failed: BTreeMap<u64, Nat> = failed_shares(cf, round_id, now).into_iter().collect();
+    let matches = breakdown(cf, round_id, now)
+        .into_iter()
+        .map(|m| SponsorCampaignMatch {
+            campaign_id: m.campaign_id,
+            campaign_status: cf
+                .campaigns
+                .get(&m.campaign_id)
+                .map_or(CampaignStatus::Failed, |c| crowdfund::view(c, now).status),
+            matched: pro_rata(&m.matched, &stake.amount, &round.matching_pool),
+            returnable: failed
+                .get(&m.campaign_id)
+                .map_or(Nat::from(0u64), |s| pro_rata(s, &stake.amount, &round.matching_pool)),
+        })
+        .collect();
+    let (unallocated_owed, campaigns_owed) = owed(cf, round, stake, now);
+    let reclaimable = campaigns_owed
+        .iter()
+        .fold(unallocated_owed, |sum, (_, part)| Nat::from(&sum.0 + &part.0));
+    Some(SponsorReport {
+        round_id,
+        sponsor,
+        token: round.token.clone(),
+        status: round.status,
+        stake: stake.amount.clone(),
+        share_bps: pro_rata(&Nat::from(10_000u64), &stake.amount, &round.matching_pool)
+            .0
+            .to_u64()
+            .unwrap_or(0),
+        funded_at: stake.funded_at,
+        matches,
+        unallocated: pro_rata(&round.unallocated, &stake.amount, &round.matching_pool),
+        reclaimed: stake.reclaimed.clone(),
+        reclaimable,
+    })
+}
+
+/// Reports for every round the sponsor has funded
+pub fn reports_of(cf: &CrowdfundingPool, sponsor: Principal, now: u64) -> Vec<SponsorReport> {
+    cf.rounds.keys().filter_map(|id| report(cf, *id, sponsor, now)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crowdfund::tests::{campaign, DEADLINE};
+
+    /// A 1000 ICP round over two new campaigns; returns the round and campaign IDs
+    fn round(cf: &mut CrowdfundingPool, max_match_bps: u64) -> (u64, u64, u64) {
+        let (a, b) = (campaign(cf, 10_000), campaign(cf, 10_000));
+        let args = MatchingRoundArgs {
+            token: "ICP".to_string(),
+            matching_pool: Nat::from(1_000u64),
+            campaign_ids: vec![a, b],
+            category: None,
+            ends_at: DEADLINE,
+            max_match_bps,
+            max_counted_contribution: None,
+            unverified_weight_bps: None,
+        };
+        validate(cf, &args, 0).unwrap();
+        (open(cf, Principal::anonymous(), args, 0), a, b)
+    }
+
+    fn back(cf: &mut CrowdfundingPool, campaign_id: u64, backers: usize, amount: u64) {
+        for i in 0..backers {
+            let user = format!("{}-{}", campaign_id, i);
+            record(cf, campaign_id, &user, &Nat::from(amount), 1);
+        }
+    }
+
+    fn matched(cf: &CrowdfundingPool, round_id: u64) -> Vec<Nat> {
+        compute(cf, &cf.rounds[&round_id], 2).into_iter().map(|m| m.matched).collect()
+    }
+
+    #[test]
+    fn many_small_backers_outweigh_one_large() {
+        let mut cf = CrowdfundingPool::default();
+        let (id, a, b) = round(&mut cf, 10_000);
+        back(&mut cf, a, 4, 25);
+        back(&mut cf, b, 1, 100);
+        // (4 * sqrt 25)^2 - 100 = 300 against (sqrt 100)^2 - 100 = 0
+        assert_eq!(matched(&cf, id), vec![Nat::from(1_000u64), Nat::from(0u64)]);
+    }
+
+    #[test]
+    fn capped_share_goes_to_the_other_campaigns() {
+        let mut cf = CrowdfundingPool::default();
+        let (id, a, b) = round(&mut cf, 6_000);
+        back(&mut cf, a, 4, 25);
+        back(&mut cf, b, 2, 50);
+        // Uncapped 75% / 25%; the first is pinned at 60%
+        assert_eq!(matched(&cf, id), vec![Nat::from(600u64), Nat::from(400u64)]);
+        assert!(compute(&cf, &cf.rounds[&id], 2)[0].capped);
+    }
+
+    #[test]
+    fn counted_contribution_cap_limits_whales() {
+        let mut cf = CrowdfundingPool::default();
+        let (id, a, b) = round(&mut cf, 10_000);
+        cf.rounds.get_mut(&id).unwrap().max_counted_contribution = Some(Nat::from(25u64));
+        back(&mut cf, a, 2, 25);
+        back(&mut cf, b, 2, 10_000);
+        // Both campaigns count as two backers of 25
+        assert_eq!(matched(&cf, id), vec![Nat::from(500u64), Nat::from(500u64)]);
+    }
+
+    #[test]
+    fn contributions_outside_the_round_are_not_tallied() {
+        let mut cf = CrowdfundingPool::default();
+        let (id, a, _) = round(&mut cf, 10_000);
+        let other = campaign(&mut cf, 10_000);
+        back(&mut cf, other, 4, 25);
+        record(&mut cf, a, "late", &Nat::from(25u64), DEADLINE);
+        assert!(compute(&cf, &cf.rounds[&id], 2).iter().all(|m| m.contributed == 0u64));
+    }
+
+    #[test]
+    fn validate_rejects_bad_rounds() {
+        let mut cf = CrowdfundingPool::default();
+        let a = campaign(&mut cf, 10_000);
+        let args = MatchingRoundArgs {
+            token: "ICP".to_string(),
+            matching_pool: Nat::from(1_000u64),
+            campaign_ids: vec![a],
+            category: None,
+            ends_at: DEADLINE,
+            max_match_bps: 10_000,
+            max_counted_contribution: None,
+            unverified_weight_bps: None,
+        };
+        assert!(validate(&cf, &args, 0).is_ok());
+        assert!(validate(&cf, &MatchingRoundArgs { max_match_bps: 0, ..args.clone() }, 0).is_err());
+        let wrong_token = MatchingRoundArgs { token: "FAKEBTC".to_string(), ..args.clone() };
+        assert!(validate(&cf, &wrong_token, 0).is_err());
+        assert!(validate(&cf, &args, DEADLINE).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+// src/defi_pool_backend/metadata.rs
+//! Campaign metadata: category, media, links, beneficiary and tags. Metadata
+//! is validated before a campaign is created, and categories and tags are
+//! indexed so `search_campaigns` can narrow by them without a full scan.
+use crate::types::{CampaignCategory, CampaignMetadata};
+use crate::CrowdfundingPool;
+use candid::Principal;
+use std::collections::BTreeSet;
+
+const MAX_URL_LEN: usize = 512;
+const MAX_ASSETS: usize = 10;
+const MAX_LINKS: usize = 5;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LEN: usize = 32;
+
+fn check_url(url: &str) -> Result<(), String> {
+    let scheme_ok = url.starts_with("https://") || url.starts_with("ipfs://");
+    if !scheme_ok || url.len() > MAX_URL_LEN || url.chars().any(char::is_whitespace) {
+        return Err(format!(
+            "invalid URL {:?}: need https:// or ipfs://, up to {} bytes",
+            url, MAX_URL_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// Check metadata and normalize tags to lowercase, deduplicated
+pub fn validate(mut metadata: CampaignMetadata) -> Result<CampaignMetadata, String> {
+    if let Some(url) = &metadata.image_url {
+        check_url(url)?;
+    }
+    if metadata.assets.len() > MAX_ASSETS {
+        return Err(format!("at most {} assets", MAX_ASSETS));
+    }
+    if metadata.links.len() > MAX_LINKS {
+        return Err(format!("at most {} links", MAX_LINKS));
+    }
+    for url in metadata.assets.iter().chain(&metadata.links) {
+        check_url(url)?;
+    }
+    if metadata.beneficiary == Some(Principal::anonymous()) {
+        return Err("beneficiary cannot be anonymous".to_string());
+    }
+
+    let mut tags = vec![];
+    for tag in &metadata.tags {
+        let tag = tag.trim().to_lowercase();
+        let valid = !tag.is_empty()
+            && tag.len() <= MAX_TAG_LEN
+            && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !valid {
+            return Err(format!("tags must be 1-{} letters, digits or '-'", MAX_TAG_LEN));
+        }
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    if tags.len() > MAX_TAGS {
+        return Err(format!("at most {} tags", MAX_TAGS));
+    }
+    metadata.tags = tags;
+    Ok(metadata)
+}
+
+/// Attach validated metadata to a new campaign and index it
+pub fn attach(cf: &mut CrowdfundingPool, campaign_id: u64, metadata: CampaignMetadata) {
+    cf.category_index.entry(metadata.category).or_default().insert(campaign_id);
+    for tag in &metadata.tags {
+        cf.tag_index.entry(tag.clone()).or_default().insert(campaign_id);
+    }
+    if let Some(campaign) = cf.campaigns.get_mut(&campaign_id) {
+        campaign.metadata = metadata;
+    }
+}
+
+/// Campaign IDs matching both filters; `None` when neither is set
+pub fn candidates(
+    cf: &CrowdfundingPool,
+    category: Option<CampaignCategory>,
+    tag: Option<&str>,
+) -> Option<BTreeSet<u64>> {
+    let by_category = category.map(|c| cf.category_index.get(&c).cloned().unwrap_or_default());
+    let by_tag = tag.map(|t| cf.tag_index.get(&t.to_lowercase()).cloned().unwrap_or_default());
+    match (by_category, by_tag) {
+        (Some(a), Some(b)) => Some(a.intersection(&b).copied().collect()),
+        (a, b) => a.or(b),
+    }
+}
@@ -0,0 +1,81 @@
+// src/defi_pool_backend/metrics.rs
+//! Monitoring over the IC HTTP gateway. `/metrics` serves the Prometheus
+//! text format (cycles, memory, users, TVL and ledger transactions by kind)
+//! and `/health` answers 200 while the canister has cycles to spare, so a
+//! standard scraper can poll the canister directly.
+use crate::types::{HttpRequest, HttpResponse};
+use crate::{tvl_usd, DeFiPool};
+use ic_cdk::stable::{stable_size, WASM_PAGE_SIZE_IN_BYTES};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+
+/// Below this cycle balance `/health` reports the canister unhealthy
+const MIN_HEALTHY_CYCLES: u128 = 100_000_000_000;
+
+pub fn handle(pool: &DeFiPool, request: &HttpRequest) -> HttpResponse {
+    let path = request.url.split('?').next().unwrap_or_default();
+    match (request.method.as_str(), path) {
+        ("GET", "/metrics") => response(200, "text/plain; version=0.0.4", render(pool)),
+        ("GET", "/health") => health(),
+        _ => response(404, "text/plain", "not found\n".to_string()),
+    }
+}
+
+fn response(status_code: u16, content_type: &str, body: String) -> HttpResponse {
+    HttpResponse {
+        status_code,
+        headers: vec![("Content-Type".to_string(), content_type.to_string())],
+        body: body.into_bytes(),
+    }
+}
+
+fn health() -> HttpResponse {
+    if ic_cdk::api::canister_cycle_balance() < MIN_HEALTHY_CYCLES {
+        return response(503, "text/plain", "low cycles\n".to_string());
+    }
+    response(200, "text/plain", "ok\n".to_string())
+}
+
+fn heap_bytes() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        core::arch::wasm32::memory_size(0) as u64 * WASM_PAGE_SIZE_IN_BYTES
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        0
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, value: impl Display) {
+    header(out, name, kind, help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn render(pool: &DeFiPool) -> String {
+    let mut out = String::new();
+    let cycles = ic_cdk::api::canister_cycle_balance();
+    metric(&mut out, "defi_pool_cycles_balance", "gauge", "Cycle balance", cycles);
+    metric(&mut out, "defi_pool_heap_memory_bytes", "gauge", "Wasm heap size", heap_bytes());
+    let stable = stable_size() * WASM_PAGE_SIZE_IN_BYTES;
+    metric(&mut out, "defi_pool_stable_memory_bytes", "gauge", "Stable memory size", stable);
+    metric(&mut out, "defi_pool_users", "gauge", "Signed-up accounts", pool.users.len());
+    let frozen = pool.users.values().filter(|a| a.frozen).count();
+    metric(&mut out, "defi_pool_frozen_accounts", "gauge", "Accounts frozen", frozen);
+    metric(&mut out, "defi_pool_tvl_usd", "gauge", "USD value of balances and collateral", tvl_usd(pool));
+
+    let mut by_kind: BTreeMap<String, u64> = BTreeMap::new();
+    for block in &pool.ledger {
+        *by_kind.entry(format!("{:?}", block.kind)).or_default() += 1;
+    }
+    let name = "defi_pool_transactions_total";
+    header(&mut out, name, "counter", "Balance-changing calls by kind");
+    for (kind, count) in by_kind {
+        let _ = writeln!(out, "{}{{kind=\"{}\"}} {}", name, kind, count);
+    }
+    out
+}
@@ -0,0 +1,91 @@
+// src/defi_pool_backend/monitor.rs
+use crate::types::{AccountFlag, AnomalyAction, AnomalyResponse, UserEventSummary};
+use crate::{DeFiPool, AI_SERVICE_PROXY_PRINCIPAL, POOL};
+use ic_cdk::call;
+use std::time::Duration;
+
+/// How often accumulated activity is sent to the anomaly detector
+pub const MONITOR_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Kinds of user activity tracked for anomaly detection
+pub enum ActivityKind {
+    Deposit,
+    Borrow,
+    Repay,
+    Withdraw,
+    Crowdfund,
+}
+
+/// Count an action (and its USD volume) in the user's current window
+pub fn record_activity(pool: &mut DeFiPool, user: &str, kind: ActivityKind, usd: f64) {
+    let summary = pool.activity.entry(user.to_string()).or_default();
+    let usd = usd as u64;
+    match kind {
+        ActivityKind::Deposit => {
+            summary.deposit_count += 1;
+            summary.deposit_volume_usd += usd;
+        }
+        ActivityKind::Borrow => {
+            summary.borrow_count += 1;
+            summary.borrow_volume_usd += usd;
+        }
+        ActivityKind::Repay => summary.repay_count += 1,
+        ActivityKind::Withdraw => summary.withdraw_count += 1,
+        ActivityKind::Crowdfund => {
+            summary.crowdfund_count += 1;
+            summary.crowdfund_volume_usd += usd;
+        }
+    }
+}
+
+/// Send each active user's window to the AI proxy and freeze / escalate as advised
+pub async fn run_activity_monitor() {
+    let principal = match *AI_SERVICE_PROXY_PRINCIPAL.lock().unwrap() {
+        Some(p) => p,
+        None => return,
+    };
+
+    // Close the current window before any await
+    let (summaries, window_secs) = {
+        let mut pool = POOL.lock().unwrap();
+        let now = ic_cdk::api::time();
+        let window_secs = now.saturating_sub(pool.activity_window_start) / 1_000_000_000;
+        pool.activity_window_start = now;
+        (std::mem::take(&mut pool.activity), window_secs)
+    };
+
+    for (user, summary) in summaries {
+        let summary = UserEventSummary { window_secs, ..summary };
+        let result: Result<(AnomalyResponse,), _> =
+            call(principal, "analyze_activity", (summary,)).await;
+        let resp = match result {
+            Ok((resp,)) => resp,
+            Err(err) => {
+                ic_cdk::print(format!("analyze_activity failed for {}: {:?}", user, err));
+                continue;
+            }
+        };
+        if resp.action == AnomalyAction::None {
+            continue;
+        }
+
+        let mut pool = POOL.lock().unwrap();
+        if let Some(account) = pool.users.get_mut(&user) {
+            account.flagged = true;
+            if resp.action == AnomalyAction::Freeze {
+                account.frozen = true;
+            }
+        }
+        ic_cdk::print(format!(
+            "Activity monitor: {:?} {} (score {:.2})",
+            resp.action, user, resp.anomaly_score
+        ));
+        pool.account_flags.push(AccountFlag {
+            user,
+            action: resp.action,
+            anomaly_score: resp.anomaly_score,
+            reasons: resp.reasons,
+            timestamp: ic_cdk::api::time(),
+        });
+    }
+}
@@ -0,0 +1,207 @@
+// src/defi_pool_backend/pubsub.rs
+//! Push delivery of pool events to other canisters. A controller registers a
+//! canister against a topic; a timer sends it batches starting at its cursor
+//! through `on_pool_events` and moves the cursor to the position the
+//! subscriber acknowledges. A failed delivery is retried with exponential
+//! backoff and a subscriber that keeps failing is paused. Subscribers can also
+//! acknowledge with `ack_events`, e.g. after processing a batch later.
+use crate::types::{EventBatch, EventSubscriber, EventTopic, PoolEvent, PoolEventPayload};
+use crate::{CrowdfundingPool, DeFiPool, CF_POOL, POOL};
+use candid::Principal;
+use ic_cdk::call;
+use std::time::Duration;
+
+/// How often due subscribers are sent their next batch
+pub const RUN_INTERVAL: Duration = Duration::from_secs(30);
+/// Events per batch and per `get_events` page
+pub const MAX_BATCH: usize = 100;
+const MAX_SUBSCRIBERS: usize = 20;
+/// Failed deliveries in a row before a subscriber is paused
+const MAX_FAILURES: u32 = 10;
+const BASE_BACKOFF_NS: u64 = 30 * 1_000_000_000;
+const MAX_BACKOFF_NS: u64 = 6 * 60 * 60 * 1_000_000_000;
+
+/// Number of events in a topic's log
+pub fn head(pool: &DeFiPool, cf: &CrowdfundingPool, topic: EventTopic) -> u64 {
+    match topic {
+        EventTopic::Campaigns => cf.events.len() as u64,
+        EventTopic::AccountFlags => pool.account_flags.len() as u64,
+    }
+}
+
+/// Up to `limit` events of a topic starting at `from`
+pub fn read(
+    pool: &DeFiPool,
+    cf: &CrowdfundingPool,
+    topic: EventTopic,
+    from: u64,
+    limit: usize,
+) -> Vec<PoolEvent> {
+    let from = from as usize;
+    let limit = limit.min(MAX_BATCH);
+    let page = |payloads: Vec<PoolEventPayload>| {
+        payloads
+            .into_iter()
+            .enumerate()
+            .map(|(i, payload)| PoolEvent { seq: (from + i) as u64, payload })
+            .collect()
+    };
+    match topic {
+        EventTopic::Campaigns => page(
+            cf.events
+                .iter()
+                .skip(from)
+                .take(limit)
+                .cloned()
+                .map(PoolEventPayload::Campaign)
+                .collect(),
+        ),
+        EventTopic::AccountFlags => page(
+            pool.account_flags
+                .iter()
+                .skip(from)
+                .take(limit)
+                .cloned()
+                .map(PoolEventPayload::AccountFlag)
+                .collect(),
+        ),
+    }
+}
+
+/// Register `canister` for a topic; delivery starts at `from` or, if not
+/// given, with the next new event
+pub fn register(
+    pool: &mut DeFiPool,
+    canister: Principal,
+    topic: EventTopic,
+    from: Option<u64>,
+    head: u64,
+    now: u64,
+) -> Result<u64, String> {
+    if pool.event_subscribers.values().any(|s| s.canister == canister && s.topic == topic) {
+        return Err("canister already subscribed to this topic".to_string());
+    }
+    if pool.event_subscribers.len() >= MAX_SUBSCRIBERS {
+        return Err(format!("at most {} event subscribers", MAX_SUBSCRIBERS));
+    }
+    let cursor = from.unwrap_or(head);
+    if cursor > head {
+        return Err(format!("topic has only {} events", head));
+    }
+    let id = pool.next_event_subscriber_id;
+    pool.next_event_subscriber_id += 1;
+    pool.event_subscribers.insert(
+        id,
+        EventSubscriber {
+            id,
+            canister,
+            topic,
+            cursor,
+            active: true,
+            consecutive_failures: 0,
+            next_attempt_at: now,
+            last_delivery_at: None,
+            last_error: None,
+        },
+    );
+    Ok(id)
+}
+
+/// Re-enable a paused subscriber; it is retried on the next run
+pub fn resume(pool: &mut DeFiPool, id: u64, now: u64) -> bool {
+    let Some(sub) = pool.event_subscribers.get_mut(&id) else { return false };
+    sub.active = true;
+    sub.consecutive_failures = 0;
+    sub.next_attempt_at = now;
+    true
+}
+
+/// Move a subscriber's cursor forward on its own behalf
+pub fn ack(
+    pool: &mut DeFiPool,
+    id: u64,
+    caller: Principal,
+    cursor: u64,
+    head: u64,
+) -> Result<(), String> {
+    let sub = pool.event_subscribers.get_mut(&id).ok_or("subscription not found")?;
+    if sub.canister != caller {
+        return Err("only the subscribed canister can acknowledge".to_string());
+    }
+    if cursor < sub.cursor {
+        return Err("cursor cannot move backwards".to_string());
+    }
+    if cursor > head {
+        return Err(format!("topic has only {} events", head));
+    }
+    sub.cursor = cursor;
+    Ok(())
+}
+
+/// Apply the outcome of a delivery that sent events up to `sent_until`
+fn record_result(pool: &mut DeFiPool, id: u64, sent_until: u64, result: Result<u64, String>) {
+    pool.events_in_flight.remove(&id);
+    let now = ic_cdk::api::time();
+    // Removed while the batch was in flight
+    let Some(sub) = pool.event_subscribers.get_mut(&id) else { return };
+    match result {
+        Ok(acked) => {
+            sub.cursor = sub.cursor.max(acked.min(sent_until));
+            sub.consecutive_failures = 0;
+            sub.next_attempt_at = now;
+            sub.last_delivery_at = Some(now);
+            sub.last_error = None;
+        }
+        Err(err) => {
+            sub.consecutive_failures += 1;
+            let backoff = BASE_BACKOFF_NS
+                .saturating_mul(1 << (sub.consecutive_failures - 1).min(20))
+                .min(MAX_BACKOFF_NS);
+            sub.next_attempt_at = now + backoff;
+            sub.last_error = Some(err);
+            if sub.consecutive_failures >= MAX_FAILURES {
+                sub.active = false;
+            }
+        }
+    }
+}
+
+/// Send each due subscriber the next batch past its cursor
+pub async fn deliver_due() {
+    let now = ic_cdk::api::time();
+    let batches: Vec<(Principal, EventBatch)> = {
+        let mut pool = POOL.lock().unwrap();
+        let cf = CF_POOL.lock().unwrap();
+        let batches: Vec<(Principal, EventBatch)> = pool
+            .event_subscribers
+            .values()
+            .filter(|s| s.active && s.next_attempt_at <= now)
+            .filter(|s| !pool.events_in_flight.contains(&s.id))
+            .filter_map(|s| {
+                let head = head(&pool, &cf, s.topic);
+                if s.cursor >= head {
+                    return None;
+                }
+                let batch = EventBatch {
+                    subscription_id: s.id,
+                    topic: s.topic,
+                    events: read(&pool, &cf, s.topic, s.cursor, MAX_BATCH),
+                    head,
+                };
+                Some((s.canister, batch))
+            })
+            .collect();
+        for (_, batch) in &batches {
+            pool.events_in_flight.insert(batch.subscription_id);
+        }
+        batches
+    };
+
+    for (canister, batch) in batches {
+        let id = batch.subscription_id;
+        let sent_until = batch.events.last().map(|e| e.seq + 1).unwrap_or(0);
+        let res: Result<(u64,), _> = call(canister, "on_pool_events", (batch,)).await;
+        let result = res.map(|(acked,)| acked).map_err(|err| format!("{:?}", err));
+        record_result(&mut POOL.lock().unwrap(), id, sent_until, result);
+    }
+}
@@ -0,0 +1,119 @@
+// src/defi_pool_backend/rates.rs
+//! Daily history of the rates each market pays and charges, so a frontend
+//! can chart them without scraping snapshots. Lending charges no interest,
+//! so the pool's only rates are set by governance and both apply to the
+//! stablecoin: the savings rate, paid on savings, and the stability fee,
+//! charged on minted stablecoin; every other token reports zero.
+//! `savings::set_rate` and `vaults::set_config` record the new rates into
+//! the day's bucket; a day without a change carries the rates before it.
+//! Lending keeps no per-token borrowed total, so utilization is not tracked.
+use crate::types::RatePoint;
+use crate::vaults::STABLE_SYMBOL;
+use crate::DeFiPool;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+pub const BUCKET_SECS: u64 = 86_400;
+const BUCKET_NANOS: u64 = BUCKET_SECS * 1_000_000_000;
+/// Most days one history query returns
+pub const MAX_HISTORY_DAYS: u64 = 366;
+
+/// Rates in force at the end of a day
+#[derive(Clone, Copy, Default, CandidType, Serialize, Deserialize)]
+pub struct DayRates {
+    pub savings_rate_bps: u64,
+    pub stability_fee_bps: u64,
+}
+
+/// Record the pool's current rates as in force for the rest of today
+pub fn record(pool: &mut DeFiPool, now: u64) {
+    let rates = DayRates {
+        savings_rate_bps: pool.savings_rate_bps,
+        stability_fee_bps: pool.vault_config.stability_fee_bps,
+    };
+    pool.rate_history.insert(now - now % BUCKET_NANOS, rates);
+}
+
+/// One point per day of `token`'s rates from `from` to `to` (ns since
+/// epoch), oldest first. Days before the first recorded rates are left out.
+pub fn history(
+    pool: &DeFiPool,
+    token: &str,
+    from: u64,
+    to: u64,
+) -> Result<Vec<RatePoint>, String> {
+    if token != STABLE_SYMBOL && !pool.supported_tokens.iter().any(|t| t == token) {
+        return Err(format!("token {} not supported", token));
+    }
+    if from > to {
+        return Err("from must not be after to".to_string());
+    }
+    let first = from - from % BUCKET_NANOS;
+    let days = (to - first) / BUCKET_NANOS + 1;
+    if days > MAX_HISTORY_DAYS {
+        return Err(format!("at most {} days per query", MAX_HISTORY_DAYS));
+    }
+    let points = (0..days)
+        .map(|day| first + day * BUCKET_NANOS)
+        .filter_map(|start| {
+            let (_, rates) = pool.rate_history.range(..=start).next_back()?;
+            // Both rates apply to the stablecoin only: savings are held in it
+            // and vaults mint it
+            let stable = token == STABLE_SYMBOL;
+            Some(RatePoint {
+                start,
+                supply_apy_bps: if stable { rates.savings_rate_bps } else { 0 },
+                borrow_apr_bps: if stable { rates.stability_fee_bps } else { 0 },
+            })
+        })
+        .collect();
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VaultConfig;
+    use crate::{savings, vaults};
+
+    const DAY: u64 = BUCKET_NANOS;
+
+    fn pool() -> DeFiPool {
+        DeFiPool { supported_tokens: vec!["ICP".to_string()], ..Default::default() }
+    }
+
+    fn fee(stability_fee_bps: u64) -> VaultConfig {
+        VaultConfig { stability_fee_bps, collateral_factors: vec![] }
+    }
+
+    fn rates(points: &[RatePoint]) -> Vec<(u64, u64)> {
+        points.iter().map(|p| (p.supply_apy_bps, p.borrow_apr_bps)).collect()
+    }
+
+    #[test]
+    fn days_carry_the_last_change_before_them() {
+        let mut pool = pool();
+        savings::set_rate(&mut pool, 500, DAY + 10).unwrap();
+        vaults::set_config(&mut pool, fee(200), 3 * DAY).unwrap();
+        // The last change of a day wins
+        vaults::set_config(&mut pool, fee(300), 3 * DAY + 10).unwrap();
+
+        let stable = history(&pool, STABLE_SYMBOL, 0, 4 * DAY).unwrap();
+        assert_eq!(stable[0].start, DAY);
+        assert_eq!(rates(&stable), [(500, 0), (500, 0), (500, 300), (500, 300)]);
+        // Other tokens neither earn savings interest nor pay a stability fee
+        let icp = history(&pool, "ICP", 3 * DAY, 3 * DAY).unwrap();
+        assert_eq!(rates(&icp), [(0, 0)]);
+    }
+
+    #[test]
+    fn history_rejects_bad_ranges() {
+        let mut pool = pool();
+        record(&mut pool, 0);
+        assert!(history(&pool, "FAKEDOGE", 0, DAY).is_err());
+        assert!(history(&pool, "ICP", DAY, 0).is_err());
+        assert!(history(&pool, "ICP", 0, MAX_HISTORY_DAYS * DAY).is_err());
+        let year = history(&pool, "ICP", 0, (MAX_HISTORY_DAYS - 1) * DAY).unwrap();
+        assert_eq!(year.len() as u64, MAX_HISTORY_DAYS);
+    }
+}
@@ -0,0 +1,83 @@
+// src/defi_pool_backend/receipts.rs
+//! Contribution receipts: every backer of a campaign holds one receipt badge
+//! recording the campaign, the amount contributed and the backer tier it
+//! earns. Receipts are bound to the backer; there is no transfer, so
+//! campaigns can gate perks on them. Further contributions top up the same
+//! receipt and may raise its tier.
+use crate::types::{BackerTier, ContributionReceipt};
+use crate::CrowdfundingPool;
+use candid::Nat;
+
+/// Tier thresholds in basis points of the campaign goal, highest first
+const TIER_THRESHOLDS_BPS: [(BackerTier, u64); 3] =
+    [(BackerTier::Gold, 1_000), (BackerTier::Silver, 500), (BackerTier::Bronze, 100)];
+
+/// Tier earned by contributing `amount` towards `goal`
+pub fn tier_for(amount: &Nat, goal: &Nat) -> BackerTier {
+    TIER_THRESHOLDS_BPS
+        .iter()
+        .find(|(_, bps)| &amount.0 * 10_000u64 >= &goal.0 * *bps)
+        .map_or(BackerTier::Supporter, |(tier, _)| *tier)
+}
+
+/// Mint or top up the backer's receipt after a recorded contribution
+pub fn issue(cf: &mut CrowdfundingPool, campaign_id: u64, user: &str, now: u64) {
+    let Some(campaign) = cf.campaigns.get(&campaign_id) else {
+        return;
+    };
+    let total = cf
+        .campaign_contributions
+        .get(&campaign_id)
+        .and_then(|backers| backers.get(user))
+        .cloned()
+        .unwrap_or(Nat::from(0u64));
+    let tier = tier_for(&total, &campaign.goal);
+
+    let key = (campaign_id, user.to_string());
+    if let Some(receipt) = cf.receipt_index.get(&key).and_then(|id| cf.receipts.get_mut(id)) {
+        receipt.amount = total;
+        receipt.tier = tier;
+        receipt.updated_at = now;
+        return;
+    }
+    cf.next_receipt_id += 1;
+    let id = cf.next_receipt_id;
+    cf.receipts.insert(
+        id,
+        ContributionReceipt {
+            id,
+            campaign_id,
+            owner: user.to_string(),
+            token: campaign.token.clone(),
+            amount: total,
+            tier,
+            issued_at: now,
+            updated_at: now,
+            refunded: false,
+        },
+    );
+    cf.receipt_index.insert(key, id);
+}
+
+/// Flag the receipt once its backer has been refunded
+pub fn mark_refunded(cf: &mut CrowdfundingPool, campaign_id: u64, user: &str, now: u64) {
+    let key = (campaign_id, user.to_string());
+    if let Some(receipt) = cf.receipt_index.get(&key).and_then(|id| cf.receipts.get_mut(id)) {
+        receipt.refunded = true;
+        receipt.updated_at = now;
+    }
+}
+
+pub fn of_user(cf: &CrowdfundingPool, user: &str) -> Vec<ContributionReceipt> {
+    cf.receipts.values().filter(|r| r.owner == user).cloned().collect()
+}
+
+pub fn of_campaign(cf: &CrowdfundingPool, campaign_id: u64) -> Vec<ContributionReceipt> {
+    cf.receipts.values().filter(|r| r.campaign_id == campaign_id).cloned().collect()
+}
+
+/// Tier a backer holds in a campaign; refunded receipts no longer count
+pub fn tier_of(cf: &CrowdfundingPool, campaign_id: u64, user: &str) -> Option<BackerTier> {
+    let id = cf.receipt_index.get(&(campaign_id, user.to_string()))?;
+    cf.receipts.get(id).filter(|r| !r.refunded).map(|r| r.tier)
+}
@@ -0,0 +1,171 @@
+// src/defi_pool_backend/recovery.rs
+//! Guardian recovery. An account names guardian principals and how many of
+//! them must agree. If its holder loses their Internet Identity or seed, a
+//! guardian proposes a new principal, the others approve, and once the
+//! timelock has passed the account's balances, savings, vaults, collateral,
+//! invoices, loan and credit history are moved to that principal. Until then
+//! any principal of the account can cancel, which stops a hostile guardian set. Linked principals
+//! and session keys are dropped on recovery since they may be what was lost;
+//! crowdfunding contributions stay with the principal that made them.
+use crate::types::{GuardianSet, RecoveryRequest, TransactionKind};
+use crate::{accounts, ledger, DeFiPool};
+use candid::Principal;
+use std::collections::HashMap;
+
+/// Wait between a proposal and its execution, for the holder to object
+const RECOVERY_TIMELOCK_NANOS: u64 = 3 * 24 * 60 * 60 * 1_000_000_000;
+const MAX_GUARDIANS: usize = 10;
+
+/// Replace the guardians of `account`; an empty list removes them. Any
+/// pending recovery is cancelled.
+pub fn set_guardians(
+    pool: &mut DeFiPool,
+    account: &str,
+    guardians: Vec<Principal>,
+    threshold: u32,
+    now: u64,
+) -> Result<(), String> {
+    if !pool.users.contains_key(account) {
+        return Err("account not found".to_string());
+    }
+    pool.recoveries.remove(account);
+    if guardians.is_empty() {
+        pool.guardians.remove(account);
+        return Ok(());
+    }
+    let mut unique: Vec<Principal> = Vec::new();
+    for guardian in guardians {
+        if guardian == Principal::anonymous() {
+            return Err("anonymous principal cannot be a guardian".to_string());
+        }
+        if accounts::account_of(pool, &guardian.to_text()) == account {
+            return Err("a principal of the account cannot guard it".to_string());
+        }
+        if !unique.contains(&guardian) {
+            unique.push(guardian);
+        }
+    }
+    if unique.len() > MAX_GUARDIANS {
+        return Err(format!("at most {} guardians", MAX_GUARDIANS));
+    }
+    if threshold == 0 || threshold as usize > unique.len() {
+        return Err(format!("threshold must be between 1 and {}", unique.len()));
+    }
+    pool.guardians.insert(
+        account.to_string(),
+        GuardianSet { guardians: unique, threshold, updated_at: now },
+    );
+    Ok(())
+}
+
+fn move_key<V>(map: &mut HashMap<String, V>, from: &str, to: &str) {
+    if let Some(value) = map.remove(from) {
+        map.insert(to.to_string(), value);
+    }
+}
+
+/// Holds an account, is linked to one or is a session key
+fn acts_on_account(pool: &DeFiPool, principal: &str) -> bool {
+    accounts::has_account(pool, principal)
+        || pool.account_links.contains_key(principal)
+        || pool.sessions.contains_key(principal)
+}
+
+fn require_guardian(pool: &DeFiPool, account: &str, guardian: Principal) -> Result<(), String> {
+    let set = pool.guardians.get(account).ok_or("account has no guardians")?;
+    if !set.guardians.contains(&guardian) {
+        return Err("caller is not a guardian of this account".to_string());
+    }
+    Ok(())
+}
+
+/// Start recovering `account` to `new_principal`; counts as the proposer's
+/// approval
+pub fn propose(
+    pool: &mut DeFiPool,
+    account: &str,
+    guardian: Principal,
+    new_principal: Principal,
+    now: u64,
+) -> Result<(), String> {
+    require_guardian(pool, account, guardian)?;
+    let new_text = new_principal.to_text();
+    if new_principal == Principal::anonymous() || acts_on_account(pool, &new_text) {
+        return Err("new principal must not hold or act on an account".to_string());
+    }
+    if pool.recoveries.contains_key(account) {
+        return Err("a recovery is already pending".to_string());
+    }
+    pool.recoveries.insert(
+        account.to_string(),
+        RecoveryRequest {
+            account: account.to_string(),
+            new_principal,
+            approvals: vec![guardian],
+            proposed_at: now,
+            executable_at: now + RECOVERY_TIMELOCK_NANOS,
+        },
+    );
+    Ok(())
+}
+
+pub fn approve(pool: &mut DeFiPool, account: &str, guardian: Principal) -> Result<u32, String> {
+    require_guardian(pool, account, guardian)?;
+    let request = pool.recoveries.get_mut(account).ok_or("no recovery pending")?;
+    if !request.approvals.contains(&guardian) {
+        request.approvals.push(guardian);
+    }
+    Ok(request.approvals.len() as u32)
+}
+
+/// Move every pool record of `account` to the proposed principal once the
+/// threshold and timelock are met; returns the new account key
+pub fn execute(pool: &mut DeFiPool, account: &str, now: u64) -> Result<String, String> {
+    let request = pool.recoveries.get(account).ok_or("no recovery pending")?;
+    let set = pool.guardians.get(account).ok_or("account has no guardians")?;
+    let approvals = request.approvals.iter().filter(|g| set.guardians.contains(g)).count();
+    if approvals < set.threshold as usize {
+        return Err(format!("{} of {} approvals", approvals, set.threshold));
+    }
+    if now < request.executable_at {
+        return Err(format!("timelocked until {}", request.executable_at));
+    }
+    let new_account = request.new_principal.to_text();
+    if acts_on_account(pool, &new_account) {
+        return Err("new principal has acquired an account".to_string());
+    }
+    pool.recoveries.remove(account);
+
+    let operations = ledger::transfer_all(pool, account, &new_account);
+    ledger::record(pool, TransactionKind::AccountRecovery, operations, now);
+    move_key(&mut pool.users, account, &new_account);
+    move_key(&mut pool.stablecoin_balances, account, &new_account);
+    move_key(&mut pool.collateral, account, &new_account);
+    move_key(&mut pool.savings, account, &new_account);
+    move_key(&mut pool.vaults, account, &new_account);
+    move_key(&mut pool.usernames, account, &new_account);
+    move_key(&mut pool.per_user_mint_logs, account, &new_account);
+    move_key(&mut pool.loan_features, account, &new_account);
+    move_key(&mut pool.activity, account, &new_account);
+    move_key(&mut pool.guardians, account, &new_account);
+    move_key(&mut pool.invoice_notices, account, &new_account);
+    for invoice in pool.invoices.values_mut() {
+        if invoice.issuer == account {
+            invoice.issuer = new_account.clone();
+        }
+        if invoice.payer == account {
+            invoice.payer = new_account.clone();
+        }
+    }
+    if pool.admitted_users.remove(account) {
+        pool.admitted_users.insert(new_account.clone());
+    }
+    pool.account_links.retain(|_, linked_to| linked_to != account);
+    pool.sessions.retain(|_, session| session.account != account);
+    Ok(new_account)
+}
+
+/// Called by a principal of the account to stop a pending recovery
+pub fn cancel(pool: &mut DeFiPool, account: &str) -> Result<(), String> {
+    pool.recoveries.remove(account).map(|_| ()).ok_or("no recovery pending".to_string())
+}
@@ -0,0 +1,233 @@
+// src/defi_pool_backend/savings.rs
+//! Savings bucket. Users move part of their pool balance into savings, where
+//! it earns the governance-set savings rate and can be moved back at any
+//! time. Savings are kept apart from pool balances and collateral, so they
+//! never count toward borrowing or risk.
+//!
+//! Interest is funded from borrow interest. Lending in the pool charges none,
+//! so the only borrow interest is the stability fee vaults pay on minted
+//! stablecoin, which `vaults::repay` moves into the savings reserve. Savings
+//! are therefore held in the stablecoin. Interest the reserve cannot cover
+//! stays owed and is paid on a later withdrawal.
+use crate::types::{BalanceKind, SavingsBalance, TransactionKind};
+use crate::vaults::STABLE_SYMBOL;
+use crate::{ledger, rates, DeFiPool};
+use candid::{CandidType, Nat};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+/// Highest savings rate governance can set (20% a year)
+pub const MAX_SAVINGS_RATE_BPS: u64 = 2_000;
+const SECS_PER_YEAR: u64 = 365 * 86_400;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Savings of one token for one account
+#[derive(CandidType, Serialize, Deserialize, Clone, Default)]
+pub struct Position {
+    pub principal: Nat,
+    /// Interest earned and not yet paid out
+    pub accrued: Nat,
+    pub last_accrual: u64,
+}
+
+/// Interest on `position` since its last accrual at `rate_bps`
+fn pending_interest(position: &Position, rate_bps: u64, now: u64) -> BigUint {
+    let elapsed = now.saturating_sub(position.last_accrual) / NANOS_PER_SEC;
+    &position.principal.0 * rate_bps * elapsed / (10_000u64 * SECS_PER_YEAR)
+}
+
+fn accrue(position: &mut Position, rate_bps: u64, now: u64) {
+    let interest = pending_interest(position, rate_bps, now);
+    position.accrued = Nat::from(&position.accrued.0 + interest);
+    position.last_accrual = now;
+}
+
+/// Change the savings rate, settling interest earned at the old rate first
+pub fn set_rate(pool: &mut DeFiPool, rate_bps: u64, now: u64) -> Result<(), String> {
+    if rate_bps > MAX_SAVINGS_RATE_BPS {
+        return Err(format!("savings rate cannot exceed {} bps", MAX_SAVINGS_RATE_BPS));
+    }
+    let old_rate = pool.savings_rate_bps;
+    for positions in pool.savings.values_mut() {
+        for position in positions.values_mut() {
+            accrue(position, old_rate, now);
+        }
+    }
+    pool.savings_rate_bps = rate_bps;
+    rates::record(pool, now);
+    Ok(())
+}
+
+/// Move `amount` of `token` from the account's pool balance into savings
+pub fn deposit(
+    pool: &mut DeFiPool,
+    account: &str,
+    token: &str,
+    amount: &Nat,
+    now: u64,
+) -> Result<Nat, String> {
+    if *amount == 0u64 {
+        return Err("amount must be positive".to_string());
+    }
+    if token != STABLE_SYMBOL {
+        return Err(format!("savings are held in {}, which stability fees pay", STABLE_SYMBOL));
+    }
+    let balance = pool
+        .stablecoin_balances
+        .get_mut(account)
+        .and_then(|balances| balances.get_mut(token))
+        .filter(|balance| **balance >= *amount)
+        .ok_or(format!("insufficient {} balance", token))?;
+    *balance = Nat::from(&balance.0 - &amount.0);
+
+    let rate = pool.savings_rate_bps;
+    let position = pool
+        .savings
+        .entry(account.to_string())
+        .or_default()
+        .entry(token.to_string())
+        .or_insert_with(|| Position { last_accrual: now, ..Default::default() });
+    accrue(position, rate, now);
+    position.principal = Nat::from(&position.principal.0 + &amount.0);
+    let total = position.principal.clone();
+    let operations = vec![
+        ledger::debit(account, BalanceKind::Pool, token, amount),
+        ledger::credit(account, BalanceKind::Savings, token, amount),
+    ];
+    ledger::record(pool, TransactionKind::SavingsDeposit, operations, now);
+    Ok(total)
+}
+
+/// Move `amount` of savings plus as much owed interest as the reserve covers
+/// back to the pool balance; returns the total credited
+pub fn withdraw(
+    pool: &mut DeFiPool,
+    account: &str,
+    token: &str,
+    amount: &Nat,
+    now: u64,
+) -> Result<Nat, String> {
+    let rate = pool.savings_rate_bps;
+    let position = pool
+        .savings
+        .get_mut(account)
+        .and_then(|positions| positions.get_mut(token))
+        .ok_or(format!("no {} savings", token))?;
+    if position.principal < *amount {
+        return Err(format!("only {} {} in savings", position.principal, token));
+    }
+    accrue(position, rate, now);
+
+    let reserve = pool.savings_reserve.entry(token.to_string()).or_default();
+    let interest = position.accrued.0.clone().min(reserve.0.clone());
+    *reserve = Nat::from(&reserve.0 - &interest);
+    position.accrued = Nat::from(&position.accrued.0 - &interest);
+    position.principal = Nat::from(&position.principal.0 - &amount.0);
+    if position.principal == 0u64 && position.accrued == 0u64 {
+        if let Some(positions) = pool.savings.get_mut(account) {
+            positions.remove(token);
+            if positions.is_empty() {
+                pool.savings.remove(account);
+            }
+        }
+    }
+
+    let credited = &amount.0 + &interest;
+    let balance = pool
+        .stablecoin_balances
+        .entry(account.to_string())
+        .or_default()
+        .entry(token.to_string())
+        .or_insert(Nat::from(0u64));
+    *balance = Nat::from(&balance.0 + &credited);
+    let (interest, credited) = (Nat::from(interest), Nat::from(credited));
+    let operations = vec![
+        ledger::debit(account, BalanceKind::Savings, token, amount),
+        ledger::debit(ledger::PROTOCOL_ACCOUNT, BalanceKind::SavingsReserve, token, &interest),
+        ledger::credit(account, BalanceKind::Pool, token, &credited),
+    ];
+    ledger::record(pool, TransactionKind::SavingsWithdrawal, operations, now);
+    Ok(credited)
+}
+
+/// The account's savings with interest accrued up to `now`
+pub fn balances(pool: &DeFiPool, account: &str, now: u64) -> Vec<SavingsBalance> {
+    let Some(positions) = pool.savings.get(account) else { return Vec::new() };
+    let mut balances: Vec<SavingsBalance> = positions
+        .iter()
+        .map(|(token, position)| {
+            let pending = pending_interest(position, pool.savings_rate_bps, now);
+            SavingsBalance {
+                token: token.clone(),
+                principal: position.principal.clone(),
+                accrued_interest: Nat::from(&position.accrued.0 + pending),
+                rate_bps: pool.savings_rate_bps,
+            }
+        })
+        .collect();
+    balances.sort_by(|a, b| a.token.cmp(&b.token));
+    balances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YEAR: u64 = SECS_PER_YEAR * NANOS_PER_SEC;
+    const TOKEN: &str = STABLE_SYMBOL;
+
+    /// alice with 10_000 pUSD saved at 10% from time 0
+    fn saver() -> DeFiPool {
+        let mut pool = DeFiPool::default();
+        let balances = pool.stablecoin_balances.entry("alice".to_string()).or_default();
+        balances.insert(TOKEN.to_string(), Nat::from(10_000u64));
+        set_rate(&mut pool, 1_000, 0).unwrap();
+        deposit(&mut pool, "alice", TOKEN, &Nat::from(10_000u64), 0).unwrap();
+        pool
+    }
+
+    fn reserve(pool: &mut DeFiPool, amount: u64) {
+        pool.savings_reserve.insert(TOKEN.to_string(), Nat::from(amount));
+    }
+
+    #[test]
+    fn interest_is_paid_as_far_as_the_reserve_covers() {
+        let mut pool = saver();
+        assert_eq!(balances(&pool, "alice", YEAR)[0].accrued_interest, Nat::from(1_000u64));
+        reserve(&mut pool, 400);
+        let credited = withdraw(&mut pool, "alice", TOKEN, &Nat::from(10_000u64), YEAR);
+        assert_eq!(credited, Ok(Nat::from(10_400u64)));
+        // The rest stays owed until the reserve refills
+        assert_eq!(balances(&pool, "alice", YEAR)[0].accrued_interest, Nat::from(600u64));
+        reserve(&mut pool, 1_000);
+        let credited = withdraw(&mut pool, "alice", TOKEN, &Nat::from(0u64), YEAR);
+        assert_eq!(credited, Ok(Nat::from(600u64)));
+        assert!(pool.savings.is_empty());
+        assert_eq!(pool.stablecoin_balances["alice"][TOKEN], Nat::from(11_000u64));
+    }
+
+    #[test]
+    fn rate_change_settles_interest_at_the_old_rate() {
+        let mut pool = saver();
+        set_rate(&mut pool, 0, YEAR / 2).unwrap();
+        assert_eq!(balances(&pool, "alice", YEAR)[0].accrued_interest, Nat::from(500u64));
+        assert!(set_rate(&mut pool, MAX_SAVINGS_RATE_BPS + 1, YEAR).is_err());
+    }
+
+    #[test]
+    fn moves_are_limited_to_the_available_balance() {
+        let mut pool = saver();
+        assert!(deposit(&mut pool, "alice", TOKEN, &Nat::from(1u64), 0).is_err());
+        let too_much = Nat::from(10_001u64);
+        assert!(withdraw(&mut pool, "alice", TOKEN, &too_much, 0).is_err());
+    }
+
+    #[test]
+    fn savings_are_held_in_the_stablecoin_only() {
+        let mut pool = saver();
+        let balances = pool.stablecoin_balances.entry("alice".to_string()).or_default();
+        balances.insert("ICP".to_string(), Nat::from(100u64));
+        assert!(deposit(&mut pool, "alice", "ICP", &Nat::from(100u64), 0).is_err());
+        assert_eq!(pool.stablecoin_balances["alice"]["ICP"], Nat::from(100u64));
+    }
+}
@@ -0,0 +1,204 @@
+// src/defi_pool_backend/sessions.rs
+//! Session keys. An account holder authorizes another principal (a bot, a
+//! mobile session) to perform a chosen set of operations on their account,
+//! optionally capped at a USD value per day, until an expiry. A session
+//! principal can do nothing else in the pool, and revoking it takes effect
+//! on its next call. Allowance is counted when an operation is authorized.
+//! Tokens a session borrows are minted to the account, not to the session.
+use crate::types::{SessionKey, SessionPermission};
+use crate::{accounts, DeFiPool};
+
+const MAX_SESSION_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+const MAX_SESSIONS_PER_ACCOUNT: usize = 20;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+const NANOS_PER_DAY: u64 = 86_400 * NANOS_PER_SEC;
+
+/// Whether `principal` is registered as a session key, expired or not
+pub fn is_session(pool: &DeFiPool, principal: &str) -> bool {
+    pool.sessions.contains_key(principal)
+}
+
+/// Authorize `session` on `owner`'s account, replacing an earlier grant to
+/// the same session
+pub fn authorize(
+    pool: &mut DeFiPool,
+    owner: &str,
+    session: &str,
+    permissions: Vec<SessionPermission>,
+    daily_limit_usd: Option<u64>,
+    ttl_secs: u64,
+    now: u64,
+) -> Result<SessionKey, String> {
+    if permissions.is_empty() {
+        return Err("a session needs at least one permission".to_string());
+    }
+    if ttl_secs == 0 || ttl_secs > MAX_SESSION_TTL_SECS {
+        return Err(format!("ttl must be between 1 and {} seconds", MAX_SESSION_TTL_SECS));
+    }
+    let account = accounts::account_of(pool, owner);
+    if !pool.users.contains_key(&account) {
+        return Err("account not found".to_string());
+    }
+    // A session principal must not hold or share an account of its own
+    if accounts::account_of(pool, session) != session || pool.users.contains_key(session) {
+        return Err("session principal already acts on an account".to_string());
+    }
+    match pool.sessions.get(session) {
+        Some(existing) if existing.account != account => {
+            return Err("session principal belongs to another account".to_string())
+        }
+        _ => {}
+    }
+
+    pool.sessions.retain(|_, s| s.expires_at > now);
+    let others =
+        pool.sessions.values().filter(|s| s.account == account && s.session != session).count();
+    if others >= MAX_SESSIONS_PER_ACCOUNT {
+        return Err(format!("at most {} sessions per account", MAX_SESSIONS_PER_ACCOUNT));
+    }
+    let mut unique = Vec::new();
+    for permission in permissions {
+        if !unique.contains(&permission) {
+            unique.push(permission);
+        }
+    }
+    let key = SessionKey {
+        session: session.to_string(),
+        account,
+        permissions: unique,
+        daily_limit_usd,
+        spent_today_usd: 0,
+        day: now / NANOS_PER_DAY,
+        created_at: now,
+        expires_at: now + ttl_secs * NANOS_PER_SEC,
+    };
+    pool.sessions.insert(session.to_string(), key.clone());
+    Ok(key)
+}
+
+/// Remove a session; allowed for the account's principals and the session itself
+pub fn revoke(pool: &mut DeFiPool, caller: &str, session: &str) -> Result<(), String> {
+    let key = pool.sessions.get(session).ok_or("session not found")?;
+    if caller != session && key.account != accounts::account_of(pool, caller) {
+        return Err("session belongs to another account".to_string());
+    }
+    pool.sessions.remove(session);
+    Ok(())
+}
+
+/// Sessions authorized on an account
+pub fn of_account(pool: &DeFiPool, account: &str) -> Vec<SessionKey> {
+    let mut sessions: Vec<SessionKey> =
+        pool.sessions.values().filter(|s| s.account == account).cloned().collect();
+    sessions.sort_by_key(|s| s.created_at);
+    sessions
+}
+
+/// Check that `session` may perform `permission` moving `usd`, charge its
+/// daily allowance and return the account it acts on
+pub fn check(
+    pool: &mut DeFiPool,
+    session: &str,
+    permission: SessionPermission,
+    usd: f64,
+    now: u64,
+) -> Result<String, String> {
+    let key = pool.sessions.get_mut(session).ok_or("session not found")?;
+    if key.expires_at <= now {
+        return Err("session expired".to_string());
+    }
+    if !key.permissions.contains(&permission) {
+        return Err(format!("session may not {:?}", permission));
+    }
+    let today = now / NANOS_PER_DAY;
+    if key.day != today {
+        key.day = today;
+        key.spent_today_usd = 0;
+    }
+    let usd = usd.max(0.0).ceil() as u64;
+    if let Some(limit) = key.daily_limit_usd {
+        if key.spent_today_usd + usd > limit {
+            return Err(format!(
+                "daily session limit of {} USD reached ({} used)",
+                limit, key.spent_today_usd
+            ));
+        }
+    }
+    key.spent_today_usd += usd;
+    Ok(key.account.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::UserAccount;
+
+    const DAY: u64 = NANOS_PER_DAY;
+
+    fn pool_with_session(daily_limit_usd: Option<u64>) -> DeFiPool {
+        let mut pool = DeFiPool::default();
+        pool.users.insert("alice".to_string(), UserAccount::default());
+        let permissions = vec![SessionPermission::Borrow, SessionPermission::Borrow];
+        let ttl_secs = 7 * 86_400;
+        authorize(&mut pool, "alice", "bot", permissions, daily_limit_usd, ttl_secs, DAY).unwrap();
+        pool
+    }
+
+    #[test]
+    fn daily_cap_limits_spending_until_the_next_day() {
+        let mut pool = pool_with_session(Some(100));
+        let borrow = SessionPermission::Borrow;
+        assert_eq!(check(&mut pool, "bot", borrow, 60.0, DAY), Ok("alice".to_string()));
+        assert!(check(&mut pool, "bot", borrow, 41.0, DAY + 1).is_err());
+        assert!(check(&mut pool, "bot", borrow, 40.0, DAY + 2).is_ok());
+        assert!(check(&mut pool, "bot", borrow, 0.5, DAY + 3).is_err());
+        assert!(check(&mut pool, "bot", borrow, 100.0, 2 * DAY).is_ok());
+    }
+
+    #[test]
+    fn uncapped_session_spends_freely() {
+        let mut pool = pool_with_session(None);
+        assert!(check(&mut pool, "bot", SessionPermission::Borrow, 1e12, DAY).is_ok());
+    }
+
+    #[test]
+    fn session_expires_after_its_ttl() {
+        let mut pool = pool_with_session(None);
+        let expires_at = pool.sessions["bot"].expires_at;
+        assert_eq!(expires_at, DAY + 7 * DAY);
+        let borrow = SessionPermission::Borrow;
+        assert!(check(&mut pool, "bot", borrow, 1.0, expires_at - 1).is_ok());
+        let expired = check(&mut pool, "bot", borrow, 1.0, expires_at);
+        assert_eq!(expired, Err("session expired".to_string()));
+    }
+
+    #[test]
+    fn session_is_limited_to_its_permissions() {
+        let mut pool = pool_with_session(None);
+        assert_eq!(pool.sessions["bot"].permissions, vec![SessionPermission::Borrow]);
+        assert!(check(&mut pool, "bot", SessionPermission::WithdrawCollateral, 0.0, DAY).is_err());
+    }
+
+    #[test]
+    fn authorize_rejects_bad_grants() {
+        let mut pool = pool_with_session(None);
+        let grant = |pool: &mut DeFiPool, session: &str, ttl_secs: u64| {
+            let permissions = vec![SessionPermission::Repay];
+            authorize(pool, "alice", session, permissions, None, ttl_secs, DAY)
+        };
+        assert!(grant(&mut pool, "bot2", 0).is_err());
+        assert!(grant(&mut pool, "bot2", MAX_SESSION_TTL_SECS + 1).is_err());
+        // An account holder cannot become someone's session
+        pool.users.insert("bob".to_string(), UserAccount::default());
+        assert!(grant(&mut pool, "bob", 60).is_err());
+        assert!(authorize(&mut pool, "carol", "bot3", vec![], None, 60, DAY).is_err());
+    }
+
+    #[test]
+    fn only_the_account_or_the_session_can_revoke() {
+        let mut pool = pool_with_session(None);
+        assert!(revoke(&mut pool, "mallory", "bot").is_err());
+        assert!(revoke(&mut pool, "alice", "bot").is_ok());
+        assert!(!is_session(&pool, "bot"));
+    }
+}
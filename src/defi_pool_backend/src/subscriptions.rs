@@ -0,0 +1,177 @@
+// src/defi_pool_backend/subscriptions.rs
+//! Recurring contributions. A backer subscribes to a campaign with an amount
+//! and interval; a timer debits the amount from their pool balance (tokens
+//! already deposited with the pool) and records it as a contribution. A debit
+//! that cannot be made is skipped and the backer gets a notice; the
+//! subscription ends once the campaign stops taking contributions.
+use crate::monitor::{record_activity, ActivityKind};
+use crate::types::{
+    BalanceKind, CampaignStatus, Subscription, SubscriptionNotice, SubscriptionNoticeKind,
+    TransactionKind,
+};
+use crate::{accounts, crowdfund, ledger, matching, receipts, CrowdfundingPool, DeFiPool};
+use candid::Nat;
+use std::time::Duration;
+
+/// How often due subscriptions are charged
+pub const RUN_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const MIN_INTERVAL_SECS: u64 = 60 * 60;
+const MAX_SUBSCRIPTIONS_PER_USER: usize = 50;
+/// Notices kept per user, oldest dropped first
+const MAX_NOTICES_PER_USER: usize = 100;
+
+/// Register a subscription; the first debit happens on the next run
+pub fn subscribe(
+    cf: &mut CrowdfundingPool,
+    user: &str,
+    campaign_id: u64,
+    token: String,
+    amount: Nat,
+    interval_secs: u64,
+    now: u64,
+) -> Result<u64, String> {
+    if interval_secs < MIN_INTERVAL_SECS {
+        return Err(format!("interval must be at least {} seconds", MIN_INTERVAL_SECS));
+    }
+    let campaign_token = crowdfund::check_open(cf, campaign_id, user, &amount, now)?;
+    if campaign_token != token {
+        return Err(format!("campaign raises {}, not {}", campaign_token, token));
+    }
+    let active = cf.subscriptions.values().filter(|s| s.active && s.subscriber == user).count();
+    if active >= MAX_SUBSCRIPTIONS_PER_USER {
+        return Err(format!("at most {} active subscriptions", MAX_SUBSCRIPTIONS_PER_USER));
+    }
+
+    cf.next_subscription_id += 1;
+    let id = cf.next_subscription_id;
+    cf.subscriptions.insert(
+        id,
+        Subscription {
+            id,
+            subscriber: user.to_string(),
+            campaign_id,
+            token,
+            amount,
+            interval_secs,
+            next_due: now,
+            created_at: now,
+            active: true,
+            payments: 0,
+            skipped: 0,
+            total_contributed: Nat::from(0u64),
+        },
+    );
+    Ok(id)
+}
+
+pub fn cancel(cf: &mut CrowdfundingPool, user: &str, subscription_id: u64) -> Result<(), String> {
+    let sub = cf.subscriptions.get_mut(&subscription_id).ok_or("subscription not found")?;
+    if sub.subscriber != user {
+        return Err("not your subscription".to_string());
+    }
+    if !sub.active {
+        return Err("subscription already ended".to_string());
+    }
+    sub.active = false;
+    Ok(())
+}
+
+pub fn of_user(cf: &CrowdfundingPool, user: &str) -> Vec<Subscription> {
+    cf.subscriptions.values().filter(|s| s.subscriber == user).cloned().collect()
+}
+
+pub fn notices(cf: &CrowdfundingPool, user: &str) -> Vec<SubscriptionNotice> {
+    cf.subscription_notices.get(user).cloned().unwrap_or_default()
+}
+
+fn notify(cf: &mut CrowdfundingPool, sub: &Subscription, kind: SubscriptionNoticeKind, now: u64) {
+    let notices = cf.subscription_notices.entry(sub.subscriber.clone()).or_default();
+    if notices.len() >= MAX_NOTICES_PER_USER {
+        notices.remove(0);
+    }
+    notices.push(SubscriptionNotice {
+        subscription_id: sub.id,
+        campaign_id: sub.campaign_id,
+        timestamp: now,
+        kind,
+    });
+}
+
+/// Timer entry point: charge every active subscription that is due
+pub fn run_due(pool: &mut DeFiPool, cf: &mut CrowdfundingPool, now: u64) {
+    let due: Vec<Subscription> = cf
+        .subscriptions
+        .values()
+        .filter(|s| s.active && s.next_due <= now)
+        .cloned()
+        .collect();
+    for sub in due {
+        let outcome = charge(pool, cf, &sub, now);
+        let Some(stored) = cf.subscriptions.get_mut(&sub.id) else { continue };
+        stored.next_due = now + sub.interval_secs * 1_000_000_000;
+        let kind = match outcome {
+            Ok(()) => {
+                stored.payments += 1;
+                stored.total_contributed = Nat::from(&stored.total_contributed.0 + &sub.amount.0);
+                continue;
+            }
+            Err(Charge::Skip(reason)) => {
+                stored.skipped += 1;
+                SubscriptionNoticeKind::Skipped { reason }
+            }
+            Err(Charge::End(reason)) => {
+                stored.active = false;
+                SubscriptionNoticeKind::Ended { reason }
+            }
+        };
+        notify(cf, &sub, kind, now);
+    }
+}
+
+enum Charge {
+    Skip(String),
+    End(String),
+}
+
+/// Move one installment from the pool balance into the campaign's escrow
+fn charge(
+    pool: &mut DeFiPool,
+    cf: &mut CrowdfundingPool,
+    sub: &Subscription,
+    now: u64,
+) -> Result<(), Charge> {
+    // A campaign that stopped taking funds ends the subscription; anything
+    // else, like the backer cap, only skips this installment
+    crowdfund::check_open(cf, sub.campaign_id, &sub.subscriber, &sub.amount, now).map_err(
+        |err| {
+            let active = cf
+                .campaigns
+                .get(&sub.campaign_id)
+                .is_some_and(|c| c.status == CampaignStatus::Active);
+            if active { Charge::Skip(err) } else { Charge::End(err) }
+        },
+    )?;
+    if crate::is_frozen(pool, &sub.subscriber) {
+        return Err(Charge::Skip("account is frozen".to_string()));
+    }
+    // The pool balance belongs to the account the subscriber is linked to
+    let account = accounts::account_of(pool, &sub.subscriber);
+    let balance = pool
+        .stablecoin_balances
+        .get_mut(&account)
+        .and_then(|balances| balances.get_mut(&sub.token))
+        .filter(|balance| **balance >= sub.amount)
+        .ok_or(Charge::Skip(format!("insufficient {} balance", sub.token)))?;
+
+    // The tokens are already held by the pool, so the debit is the escrow
+    crowdfund::contribute(cf, sub.campaign_id, &sub.subscriber, &sub.amount, now)
+        .map_err(Charge::End)?;
+    *balance = Nat::from(&balance.0 - &sub.amount.0);
+    let debit = ledger::debit(&account, BalanceKind::Pool, &sub.token, &sub.amount);
+    ledger::record(pool, TransactionKind::SubscriptionCharge, vec![debit], now);
+    matching::record(cf, sub.campaign_id, &sub.subscriber, &sub.amount, now);
+    receipts::issue(cf, sub.campaign_id, &sub.subscriber, now);
+    let usd = crate::usd_value(&sub.token, &sub.amount);
+    record_activity(pool, &account, ActivityKind::Crowdfund, usd);
+    Ok(())
+}
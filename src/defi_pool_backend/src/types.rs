@@ -1,5 +1,7 @@
 use candid::CandidType;
+use candid::Int;
 use candid::Nat;
+use candid::Principal;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
@@ -12,6 +14,15 @@ pub struct UserAccount {
     pub credit_score: Nat,
     pub risk_advice: Option<String>,
     pub username: Option<String>,
+    /// Signup time (ns since epoch)
+    pub created_at: u64,
+    pub repayment_count: u64,
+    pub total_repaid_usd: Nat,
+    pub liquidation_count: u64,
+    /// Frozen by the activity monitor: no borrowing, withdrawals or contributions
+    pub frozen: bool,
+    /// Escalated for manual review by the activity monitor
+    pub flagged: bool,
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
@@ -22,21 +33,209 @@ pub struct BorrowRequest {
     pub amount: Nat,
 }
 
-/// Request payload for AI Risk Engine
-#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct RiskRequest {
-    pub volatility: Nat,
-    pub collateral: Nat,
-    pub borrowed: Nat,
-    pub deposits: Nat,
+/// Request payload for AI Risk Engine (`risk_v2`), fixed-point: 1e8 = 1 USD
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RiskRequestV2 {
+    pub volatility_e8s: Nat,
+    pub collateral_e8s: Nat,
+    pub borrowed_e8s: Nat,
+    pub deposits_e8s: Nat,
     pub credit_score: Nat,
+    /// Advice language; `None` for English
+    pub lang: Option<String>,
+    /// Account the proxy records the score under
+    pub account: Option<String>,
+}
+
+/// Risk band reported by the AI Risk Engine
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiskTier {
+    Low,
+    Moderate,
+    High,
+    Critical,
 }
 
 /// Response payload from AI Risk Engine
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct RiskResponse {
     pub risk_score: u8, // 0 = safe, 1 = high risk
+    /// Calibrated probability of the high risk class
+    pub probability: f64,
+    pub tier: RiskTier,
     pub advice: String,
+    /// Features pushing hardest towards high risk, strongest first
+    pub top_drivers: Vec<String>,
+    pub recommendations: Recommendations,
+}
+
+/// Risk feedback for a prospective borrow, computed in query mode
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BorrowPreview {
+    /// Whether the amount fits the model's recommended borrowing headroom;
+    /// advisory, `borrow` does not enforce it
+    pub within_headroom: bool,
+    pub amount_usd: f64,
+    pub risk: RiskResponse,
+}
+
+/// Structured position changes suggested by the AI Risk Engine (USD)
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Recommendations {
+    pub target_probability: f64,
+    /// Further borrowing that stays approved
+    pub max_additional_borrow_usd: f64,
+    pub collateral_top_up_usd: Option<f64>,
+    pub repay_usd: Option<f64>,
+}
+
+/// Request payload for the AI credit scoring model
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CreditFeatures {
+    pub repayment_count: u64,
+    pub total_repaid_usd: Nat,
+    pub liquidation_count: u64,
+    pub account_age_days: u64,
+}
+
+/// Response payload from the AI credit scoring model
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CreditScoreResponse {
+    pub credit_score: u32,
+    pub probability_good: f64,
+}
+
+/// Per-user activity counters sent to the AI anomaly detector
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UserEventSummary {
+    pub window_secs: u64,
+    pub deposit_count: u64,
+    pub deposit_volume_usd: u64,
+    pub borrow_count: u64,
+    pub borrow_volume_usd: u64,
+    pub repay_count: u64,
+    pub withdraw_count: u64,
+    pub crowdfund_count: u64,
+    pub crowdfund_volume_usd: u64,
+}
+
+/// Action recommended by the AI anomaly detector
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnomalyAction {
+    None,
+    Escalate,
+    Freeze,
+}
+
+/// Response payload from the AI anomaly detector
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AnomalyResponse {
+    pub anomaly_score: f64,
+    pub action: AnomalyAction,
+    pub reasons: Vec<String>,
+}
+
+/// Record of an account escalated or frozen by the activity monitor
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AccountFlag {
+    pub user: String,
+    pub action: AnomalyAction,
+    pub anomaly_score: f64,
+    pub reasons: Vec<String>,
+    pub timestamp: u64,
+}
+
+/// Request payload for AI liquidation forecasting
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LiquidationFeatures {
+    pub health_factor_bps: u64,
+    pub volatility_bps: u64,
+    pub price_trend_bps: i64,
+    pub horizon_days: u32,
+}
+
+/// Graduated liquidation warning level
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WarningLevel {
+    None,
+    Watch,
+    Warning,
+    Critical,
+}
+
+/// Response payload from AI liquidation forecasting
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LiquidationForecast {
+    pub probability: f64,
+    pub warning_level: WarningLevel,
+    pub days_to_danger: Option<u32>,
+}
+
+/// Recent oracle prices sent to the AI trend model
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PriceSeries {
+    pub token: String,
+    pub prices_e8s: Vec<u64>,
+}
+
+/// Short-term price direction forecast
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrendDirection {
+    Up,
+    Flat,
+    Down,
+}
+
+/// Response payload from the AI trend model
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PriceTrend {
+    pub token: String,
+    pub direction: TrendDirection,
+    pub expected_return_bps: i64,
+    pub volatility_bps: u64,
+    pub confidence: f64,
+}
+
+/// Validation error returned by the AI Risk Engine
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum RiskError {
+    InvalidFeature {
+        feature: String,
+        value: Nat,
+        max: Nat,
+    },
+    InsufficientData {
+        feature: String,
+        required: u64,
+        got: u64,
+    },
+    QuotaExceeded {
+        limit: u64,
+        resets_at: u64,
+    },
+    InsufficientCycles {
+        required: u64,
+        attached: u64,
+    },
+}
+
+impl std::fmt::Display for RiskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskError::InvalidFeature { feature, value, max } => {
+                write!(f, "{} = {} exceeds max {}", feature, value, max)
+            }
+            RiskError::InsufficientData { feature, required, got } => {
+                write!(f, "{} needs {} observations, got {}", feature, required, got)
+            }
+            RiskError::QuotaExceeded { limit, resets_at } => {
+                write!(f, "daily quota of {} calls used, resets at {}", limit, resets_at)
+            }
+            RiskError::InsufficientCycles { required, attached } => {
+                write!(f, "{} cycles required per call, {} attached", required, attached)
+            }
+        }
+    }
 }
 
 /// Represents a balance entry for a specific token
@@ -46,6 +245,18 @@ pub struct StableBalanceEntry {
     pub value: Nat,
 }
 
+/// Shard totals reported to the registry's roll-up
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct ShardStats {
+    pub user_count: u64,
+    pub deposits: Vec<StableBalanceEntry>,   // per-token pool balances
+    pub collateral: Vec<StableBalanceEntry>, // per-token collateral
+    pub total_borrowed: Nat,
+    pub stable_supply: Nat,
+    pub tvl_usd: f64,
+    pub timestamp: u64,
+}
+
 /// Aggregated token balances for all users
 #[derive(CandidType, Serialize, Deserialize, Clone, Default)]
 pub struct StableToken {
@@ -61,6 +272,795 @@ pub struct CrowdfundEntry {
     pub amount: Nat,
 }
 
+/// Lifecycle of a crowdfunding campaign
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CampaignStatus {
+    Active,
+    /// Deadline passed with the goal met
+    Successful,
+    /// Deadline passed below the goal
+    Failed,
+}
+
+/// What a campaign is about
+#[derive(
+    CandidType,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+)]
+pub enum CampaignCategory {
+    Technology,
+    Art,
+    Community,
+    Education,
+    Health,
+    Environment,
+    Charity,
+    Games,
+    #[default]
+    Other,
+}
+
+/// Presentation data for campaign cards, checked on creation
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CampaignMetadata {
+    pub category: CampaignCategory,
+    /// Cover image; https:// or ipfs:// URL
+    pub image_url: Option<String>,
+    /// Further images, videos or documents
+    pub assets: Vec<String>,
+    /// Project website, socials, repositories
+    pub links: Vec<String>,
+    /// Receives payouts instead of the creator when set
+    pub beneficiary: Option<Principal>,
+    /// Lowercase keywords for search
+    pub tags: Vec<String>,
+}
+
+/// A crowdfunding campaign raising one token
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Campaign {
+    pub id: u64,
+    pub creator: Principal,
+    pub title: String,
+    pub description: String,
+    pub token: String,
+    pub goal: Nat,
+    pub raised: Nat,
+    /// Contributions close at this time (ns since epoch)
+    pub deadline: u64,
+    pub created_at: u64,
+    pub status: CampaignStatus,
+    pub contributor_count: u64,
+    /// Whether the creator has withdrawn the funds of a successful campaign
+    pub paid_out: bool,
+    /// Tranches released by backer vote; empty releases everything at once
+    pub milestones: Vec<Milestone>,
+    /// Funds paid to the creator so far, fees included
+    pub released: Nat,
+    /// Quadratic funding matches credited by closed rounds; counts towards the goal
+    pub matched: Nat,
+    /// Tokens the pool canister currently holds for this campaign
+    pub escrowed: Nat,
+    pub metadata: CampaignMetadata,
+    /// Most one principal may contribute in total
+    pub contribution_cap: Option<Nat>,
+    /// An arbiter is reviewing the campaign; contributions and payouts are frozen
+    pub disputed: bool,
+}
+
+/// Where a milestone is in its release vote
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MilestoneStatus {
+    Pending,
+    Voting,
+    /// Vote passed; the creator can withdraw the tranche
+    Approved,
+    Released,
+    /// Vote failed; the rest of the escrow is refunded
+    Rejected,
+}
+
+/// Milestone as proposed by the creator
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MilestoneSpec {
+    pub description: String,
+    pub amount: Nat,
+}
+
+/// A tranche of campaign funds released after a backer vote
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Milestone {
+    pub description: String,
+    pub amount: Nat,
+    pub status: MilestoneStatus,
+    pub vote_ends_at: Option<u64>,
+    /// Contribution-weighted votes
+    pub votes_for: Nat,
+    pub votes_against: Nat,
+}
+
+/// What happened to a campaign
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum CampaignEventKind {
+    /// The deadline passed and the campaign left Active
+    Settled { status: CampaignStatus },
+    /// A backer of a failed campaign got their contribution back
+    Refunded { user: String, amount: Nat },
+    /// The creator withdrew the funds; `fee` went to the treasury or unpaid fees
+    PaidOut { creator: Principal, amount: Nat, fee: Nat },
+    MilestoneVoteOpened { index: u32, ends_at: u64 },
+    MilestoneResolved { index: u32, approved: bool },
+    /// A quadratic funding round closed and credited this campaign
+    Matched { round_id: u64, amount: Nat },
+    /// The creator called the campaign off; it settles as Failed
+    Cancelled { reason: String },
+    DisputeOpened { arbiter: Principal, reason: String },
+    /// `refunded` failed the campaign and refunded its backers
+    DisputeResolved { arbiter: Principal, refunded: bool, note: String },
+}
+
+/// Entry in the crowdfunding event log
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CampaignEvent {
+    pub campaign_id: u64,
+    pub timestamp: u64,
+    pub kind: CampaignEventKind,
+}
+
+/// Admin settings for crowdfunding payouts
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CrowdfundConfig {
+    /// Share of a successful campaign's funds kept as protocol fee
+    pub protocol_fee_bps: u64,
+    /// Receives protocol fees; while `None` they are held as unpaid fees
+    pub treasury: Option<Principal>,
+    /// May open and resolve disputes alongside the controllers
+    pub arbiters: Vec<Principal>,
+}
+
+/// Whether a matching round still tallies contributions
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundStatus {
+    Open,
+    Closed,
+}
+
+/// Arguments for opening a quadratic funding round
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MatchingRoundArgs {
+    pub token: String,
+    /// Escrowed from the sponsor when the round opens
+    pub matching_pool: Nat,
+    pub campaign_ids: Vec<u64>,
+    /// Also match every campaign in this category raising `token`
+    pub category: Option<CampaignCategory>,
+    pub ends_at: u64,
+    /// Largest share of the pool one campaign can receive
+    pub max_match_bps: u64,
+    /// Per-backer contribution counted towards the quadratic weight
+    pub max_counted_contribution: Option<Nat>,
+    /// Weight of backers without a verification; `None` counts everyone fully
+    pub unverified_weight_bps: Option<u64>,
+}
+
+/// Sponsors' matching pool split across campaigns by quadratic funding
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MatchingRound {
+    pub id: u64,
+    /// Opened the round; other sponsors may top it up while it is open
+    pub sponsor: Principal,
+    pub token: String,
+    /// All sponsors' stakes
+    pub matching_pool: Nat,
+    pub campaign_ids: Vec<u64>,
+    pub category: Option<CampaignCategory>,
+    pub created_at: u64,
+    pub ends_at: u64,
+    pub max_match_bps: u64,
+    pub max_counted_contribution: Option<Nat>,
+    pub unverified_weight_bps: Option<u64>,
+    pub status: RoundStatus,
+    /// Pool left over at close, reclaimable by the sponsors
+    pub unallocated: Nat,
+    /// Taken back by sponsors so far
+    pub reclaimed: Nat,
+}
+
+/// One campaign's share of a matching round
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CampaignMatch {
+    pub campaign_id: u64,
+    pub unique_contributors: u64,
+    /// Backers verified as unique when the match was computed
+    pub verified_contributors: u64,
+    /// Contributed during the round
+    pub contributed: Nat,
+    /// (sum of sqrt(contribution))^2 - sum of contribution, after caps
+    pub quadratic_weight: f64,
+    pub matched: Nat,
+    /// Whether `max_match_bps` limited the match
+    pub capped: bool,
+}
+
+/// How a principal proved it is a distinct person
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum VerificationSource {
+    /// Added by a controller
+    Allowlist,
+    /// Attested by the configured proof-of-unique-human canister
+    Attestation { canister: Principal },
+}
+
+/// A principal counted as a unique backer in quadratic matching
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Verification {
+    pub user: String,
+    pub source: VerificationSource,
+    pub verified_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+/// Admin settings for backer verification
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SybilConfig {
+    /// Answers `is_unique_human(principal) -> (bool)`; `None` disables attestations
+    pub uniqueness_canister: Option<Principal>,
+    /// How long an attestation counts before it must be renewed; 0 never expires
+    pub attestation_ttl_secs: u64,
+}
+
+/// Contributions received in one time bucket
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ContributionBucket {
+    /// Bucket start (ns since epoch)
+    pub start: u64,
+    pub amount: Nat,
+    pub count: u64,
+}
+
+/// A backer's total contribution to a campaign
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BackerTotal {
+    pub user: String,
+    pub amount: Nat,
+}
+
+/// Funding statistics of one campaign
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CrowdfundAnalytics {
+    pub campaign_id: u64,
+    pub token: String,
+    pub bucket_secs: u64,
+    /// Oldest first; buckets without contributions are left out
+    pub buckets: Vec<ContributionBucket>,
+    pub unique_contributors: u64,
+    pub contribution_count: u64,
+    pub raised: Nat,
+    pub average_contribution: Nat,
+    /// Largest first
+    pub largest_backers: Vec<BackerTotal>,
+    /// Contributions plus matching funds over the goal; above 10000 when overfunded
+    pub progress_bps: u64,
+}
+
+/// A sponsor's part of one campaign's match
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SponsorCampaignMatch {
+    pub campaign_id: u64,
+    pub campaign_status: CampaignStatus,
+    pub matched: Nat,
+    /// Unspent match of a failed campaign, owed back to the sponsor
+    pub returnable: Nat,
+}
+
+/// Where a sponsor's stake in a round went
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SponsorReport {
+    pub round_id: u64,
+    pub sponsor: Principal,
+    pub token: String,
+    /// Figures are projections until the round is Closed
+    pub status: RoundStatus,
+    pub stake: Nat,
+    /// Stake as a share of the whole matching pool
+    pub share_bps: u64,
+    pub funded_at: u64,
+    pub matches: Vec<SponsorCampaignMatch>,
+    /// Share of the pool left over at close
+    pub unallocated: Nat,
+    pub reclaimed: Nat,
+    /// Available to `reclaim_matching_funds` now
+    pub reclaimable: Nat,
+}
+
+/// Narrows `search_campaigns`; unset fields match everything
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CampaignFilter {
+    pub status: Option<CampaignStatus>,
+    pub token: Option<String>,
+    pub category: Option<CampaignCategory>,
+    pub tag: Option<String>,
+    /// Case-insensitive substring of the title, description or tags
+    pub text: Option<String>,
+}
+
+/// Ordering of `search_campaigns` results
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CampaignSort {
+    Newest,
+    EndingSoon,
+    MostRaised,
+    MostBackers,
+    /// Highest share of the goal funded first
+    MostFunded,
+}
+
+/// One page of search results
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CampaignPage {
+    pub campaigns: Vec<Campaign>,
+    /// Matches across all pages
+    pub total: u64,
+}
+
+/// Who wrote a campaign post
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostKind {
+    /// Progress report from the creator
+    Update,
+    /// Message from a backer
+    Comment,
+}
+
+/// Entry in a campaign's feed
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CampaignPost {
+    pub id: u64,
+    pub campaign_id: u64,
+    pub author: Principal,
+    pub kind: PostKind,
+    pub body: String,
+    pub timestamp: u64,
+    /// Hidden by the creator or an admin; left out of the feed
+    pub hidden: bool,
+}
+
+/// One page of a campaign feed, newest first
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PostPage {
+    pub posts: Vec<CampaignPost>,
+    /// Visible posts across all pages
+    pub total: u64,
+}
+
+/// Recurring contribution debited from the backer's pool balance
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Subscription {
+    pub id: u64,
+    pub subscriber: String,
+    pub campaign_id: u64,
+    pub token: String,
+    pub amount: Nat,
+    pub interval_secs: u64,
+    /// Next debit is attempted at the first timer tick after this time
+    pub next_due: u64,
+    pub created_at: u64,
+    /// Cleared on cancellation or once the campaign stops taking funds
+    pub active: bool,
+    pub payments: u64,
+    pub skipped: u64,
+    pub total_contributed: Nat,
+}
+
+/// Why a subscription did not contribute
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum SubscriptionNoticeKind {
+    /// The debit was skipped; the subscription stays active
+    Skipped { reason: String },
+    /// The campaign closed and the subscription ended
+    Ended { reason: String },
+}
+
+/// Message to a subscriber about a missed debit
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SubscriptionNotice {
+    pub subscription_id: u64,
+    pub campaign_id: u64,
+    pub timestamp: u64,
+    pub kind: SubscriptionNoticeKind,
+}
+
+/// Backer tier by share of the campaign goal contributed
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BackerTier {
+    Supporter,
+    /// At least 1% of the goal
+    Bronze,
+    /// At least 5% of the goal
+    Silver,
+    /// At least 10% of the goal
+    Gold,
+}
+
+/// Non-transferable receipt held by each backer of a campaign
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ContributionReceipt {
+    pub id: u64,
+    pub campaign_id: u64,
+    pub owner: String,
+    pub token: String,
+    /// Total contributed to the campaign; later contributions top it up
+    pub amount: Nat,
+    pub tier: BackerTier,
+    pub issued_at: u64,
+    pub updated_at: u64,
+    /// The backer claimed a refund; the receipt stays as a record
+    pub refunded: bool,
+}
+
+/// Event log a subscriber canister follows
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventTopic {
+    /// Crowdfunding campaign events
+    Campaigns,
+    /// Accounts escalated or frozen by the activity monitor
+    AccountFlags,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum PoolEventPayload {
+    Campaign(CampaignEvent),
+    AccountFlag(AccountFlag),
+}
+
+/// Event with its position in its topic's log
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PoolEvent {
+    pub seq: u64,
+    pub payload: PoolEventPayload,
+}
+
+/// Sent to a subscriber's `on_pool_events`; it answers with the `seq` it has
+/// processed up to (exclusive), which becomes its new cursor
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct EventBatch {
+    pub subscription_id: u64,
+    pub topic: EventTopic,
+    pub events: Vec<PoolEvent>,
+    /// Events in the log at the time of sending
+    pub head: u64,
+}
+
+/// Canister receiving pushed events
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct EventSubscriber {
+    pub id: u64,
+    pub canister: Principal,
+    pub topic: EventTopic,
+    /// Next event to deliver; everything before it was acknowledged
+    pub cursor: u64,
+    /// Cleared after too many failed deliveries; a controller can resume it
+    pub active: bool,
+    pub consecutive_failures: u32,
+    pub next_attempt_at: u64,
+    pub last_delivery_at: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+/// Parameter a governance proposal can change
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GovernableParameter {
+    pub name: String,
+    /// Current value, rendered as text
+    pub value: String,
+    /// Endpoint that changes it
+    pub setter: String,
+}
+
+/// Principals sharing one account
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AccountLinks {
+    /// Key the account's state is stored under
+    pub account: String,
+    /// Other principals acting on it
+    pub linked: Vec<String>,
+}
+
+/// Operation a session key may perform on its account
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionPermission {
+    Deposit,
+    DepositCollateral,
+    WithdrawCollateral,
+    Borrow,
+    Repay,
+    /// Credit score refreshes and liquidation forecasts
+    RiskQueries,
+}
+
+/// Principal authorized to act on an account with limited permissions
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SessionKey {
+    pub session: String,
+    pub account: String,
+    pub permissions: Vec<SessionPermission>,
+    /// USD value the session may move per day; `None` is unlimited
+    pub daily_limit_usd: Option<u64>,
+    pub spent_today_usd: u64,
+    /// Day (since epoch) `spent_today_usd` counts for
+    pub day: u64,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+/// Principals that can jointly recover an account
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GuardianSet {
+    pub guardians: Vec<Principal>,
+    /// Approvals needed to recover
+    pub threshold: u32,
+    pub updated_at: u64,
+}
+
+/// Pending move of an account to a new principal
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RecoveryRequest {
+    pub account: String,
+    pub new_principal: Principal,
+    pub approvals: Vec<Principal>,
+    pub proposed_at: u64,
+    pub executable_at: u64,
+}
+
+/// One token's savings, with interest accrued so far
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SavingsBalance {
+    pub token: String,
+    pub principal: Nat,
+    /// Earned and not yet paid out, including interest since the last update
+    pub accrued_interest: Nat,
+    pub rate_bps: u64,
+}
+
+/// Share of a token's USD value that counts as vault capacity
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CollateralFactor {
+    pub token: String,
+    pub factor_bps: u64,
+}
+
+/// Governance settings for stablecoin vaults
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct VaultConfig {
+    /// Annual fee on outstanding stablecoin
+    pub stability_fee_bps: u64,
+    /// Tokens without an entry use the lending LTV
+    pub collateral_factors: Vec<CollateralFactor>,
+}
+
+/// A user's vault with fees accrued up to now
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct VaultView {
+    pub collateral: Vec<StableBalanceEntry>,
+    pub collateral_usd: f64,
+    /// Stablecoin the collateral supports in total
+    pub capacity_usd: f64,
+    pub debt: Nat,
+    pub fees: Nat,
+    /// Capacity over debt plus fees; `None` when nothing is owed
+    pub health_factor_bps: Option<u64>,
+}
+
+/// Stablecoin redeemed against one vault's debt
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RedeemedVault {
+    pub account: String,
+    pub debt_cleared: Nat,
+    /// Collateral taken from the vault for the redeemer
+    pub collateral: Vec<StableBalanceEntry>,
+}
+
+/// Stablecoin swapped for vault collateral at face value minus a fee
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Redemption {
+    pub id: u64,
+    pub redeemer: String,
+    /// Stablecoin redeemed; what was asked for beyond it is returned
+    pub amount: Nat,
+    pub fee_bps: u64,
+    /// Vaults hit, riskiest first
+    pub vaults: Vec<RedeemedVault>,
+    pub timestamp: u64,
+}
+
+/// Rates of one market on one day
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RatePoint {
+    /// Start of the day (ns since epoch)
+    pub start: u64,
+    /// Simple annual rate paid on savings
+    pub supply_apy_bps: u64,
+    /// Simple annual rate charged on borrowing; only minted stablecoin pays one
+    pub borrow_apr_bps: u64,
+}
+
+/// Lifecycle of an invoice
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvoiceStatus {
+    Open,
+    Paid,
+    /// Withdrawn by the issuer before payment
+    Cancelled,
+}
+
+/// Payment request from one account to another
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Invoice {
+    pub id: u64,
+    /// Account credited on payment
+    pub issuer: String,
+    /// Account expected to pay
+    pub payer: String,
+    pub token: String,
+    pub amount: Nat,
+    pub memo: String,
+    pub due_date: u64,
+    pub created_at: u64,
+    pub status: InvoiceStatus,
+    pub paid_at: Option<u64>,
+    /// Set once the due date passed unpaid and the parties were notified
+    pub overdue: bool,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug)]
+pub enum InvoiceNoticeKind {
+    /// An invoice addressed to the user was created
+    Received,
+    Paid,
+    Cancelled,
+    /// The due date passed without payment
+    Overdue,
+}
+
+/// Message to an issuer or payer about one of their invoices
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct InvoiceNotice {
+    pub invoice_id: u64,
+    pub timestamp: u64,
+    pub kind: InvoiceNoticeKind,
+}
+
+/// Install and upgrade arguments of the pool canister
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct InitArgs {
+    /// Let any principal claim test tokens; for demo and test deployments only
+    pub faucet_enabled: bool,
+}
+
+/// What the test faucet hands out
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct FaucetConfig {
+    /// Amount of each token one claim mints
+    pub amounts: Vec<StableBalanceEntry>,
+    /// Wait between two claims of the same token by one account
+    pub cooldown_secs: u64,
+}
+
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        let amount = |token: &str, value: u64| StableBalanceEntry {
+            token: token.to_string(),
+            value: Nat::from(value),
+        };
+        FaucetConfig {
+            amounts: vec![amount("ICP", 100), amount("FAKEBTC", 1), amount("FAKEETH", 1)],
+            cooldown_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+/// Which of an account's balances an operation changes
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BalanceKind {
+    /// Deposited and borrowed tokens held for the account
+    Pool,
+    Collateral,
+    Savings,
+    VaultCollateral,
+    /// Stablecoin minted against the vault and not yet repaid
+    VaultDebt,
+    /// Funds paying savings interest, held by the protocol account
+    SavingsReserve,
+}
+
+/// What an update did to balances
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionKind {
+    Deposit,
+    Borrow,
+    Repay,
+    CollateralDeposit,
+    CollateralWithdrawal,
+    SavingsDeposit,
+    SavingsWithdrawal,
+    VaultDeposit,
+    VaultWithdrawal,
+    StableMint,
+    StableRepayment,
+    /// Stablecoin exchanged for the collateral of the riskiest vaults
+    StableRedemption,
+    InvoicePayment,
+    /// A recurring contribution moved into campaign escrow
+    SubscriptionCharge,
+    /// Guardian recovery moved the account to a new principal
+    AccountRecovery,
+}
+
+/// Signed change of one balance
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Operation {
+    pub account: String,
+    pub balance: BalanceKind,
+    pub token: String,
+    pub delta: Int,
+}
+
+/// One transaction, in the order it was applied
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LedgerBlock {
+    pub index: u64,
+    pub timestamp: u64,
+    pub kind: TransactionKind,
+    pub operations: Vec<Operation>,
+}
+
+/// Tip of the ledger
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct NetworkStatus {
+    /// The pool canister the ledger belongs to
+    pub network: Principal,
+    pub current_block_index: Option<u64>,
+    pub current_block_timestamp: Option<u64>,
+    pub oldest_block_index: Option<u64>,
+}
+
+/// Request through the IC HTTP gateway
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(CandidType, Serialize, Clone, Debug)]
+pub struct HttpResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// One slice of a state backup; see `backup_state`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct StateChunk {
+    pub index: u64,
+    pub total_chunks: u64,
+    /// Hex SHA-256 of the whole backup, the same in every chunk
+    pub state_hash: String,
+    pub taken_at: u64,
+    pub bytes: Vec<u8>,
+}
+
 /// Crowdfunding pool structure
 #[derive(Default)]
 pub struct CrowdfundingPool {
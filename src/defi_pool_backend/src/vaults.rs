@@ -0,0 +1,402 @@
+// src/defi_pool_backend/vaults.rs
+//! Collateralized stablecoin vaults. Each account has one vault holding any
+//! mix of supported tokens; the stablecoin can be minted against it up to the
+//! vault's capacity, the sum of each token's USD value times its collateral
+//! factor. Outstanding stablecoin accrues a stability fee. Repayments go to
+//! fees first, and collected fees fund the stablecoin's savings reserve;
+//! repaid debt is burned, so the supply tracks outstanding debt plus fees.
+//!
+//! Anyone can redeem stablecoin for collateral at face value minus a fee.
+//! Redemptions clear the debt of the vaults with the lowest collateral
+//! ratio first, so when the coin trades below a dollar, buying and redeeming
+//! it is profitable and pulls the price back up.
+//!
+//! One stablecoin unit (e8s) is worth one USD e8s, so debt compares directly
+//! against `usd_value` of the collateral.
+use crate::types::{
+    BalanceKind, CollateralFactor, RedeemedVault, Redemption, StableBalanceEntry,
+    TransactionKind, VaultConfig, VaultView,
+};
+use crate::{ledger, rates, token_ltv_bps, token_price, usd_value, DeFiPool};
+use candid::{CandidType, Nat};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Key of the stablecoin in pool maps (mint logs, savings reserve)
+pub const STABLE_SYMBOL: &str = "pUSD";
+/// Highest stability fee governance can set (25% a year)
+const MAX_STABILITY_FEE_BPS: u64 = 2_500;
+/// Share of redeemed value left with the redeemed vault (0.5%)
+pub const REDEMPTION_FEE_BPS: u64 = 50;
+const SECS_PER_YEAR: u64 = 365 * 86_400;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Default)]
+pub struct Vault {
+    pub collateral: BTreeMap<String, Nat>,
+    /// Stablecoin minted and not yet repaid
+    pub debt: Nat,
+    /// Stability fees owed on top of `debt`
+    pub fees: Nat,
+    pub last_accrual: u64,
+}
+
+/// Add stability fees since the last accrual
+fn accrue(vault: &mut Vault, fee_bps: u64, now: u64) {
+    let elapsed = now.saturating_sub(vault.last_accrual) / NANOS_PER_SEC;
+    let fee = &vault.debt.0 * fee_bps * elapsed / (10_000u64 * SECS_PER_YEAR);
+    vault.fees = Nat::from(&vault.fees.0 + fee);
+    vault.last_accrual = now;
+}
+
+/// Collateral factor of `token`: the configured one, tightened like the
+/// lending LTV when the token's price trend is falling
+fn factor_bps(pool: &DeFiPool, token: &str) -> u64 {
+    let ltv = token_ltv_bps(pool, token);
+    match pool.vault_config.collateral_factors.iter().find(|f| f.token == token) {
+        Some(f) => f.factor_bps.min(ltv),
+        None => ltv,
+    }
+}
+
+/// USD e8s of stablecoin the vault's collateral supports
+fn capacity(pool: &DeFiPool, vault: &Vault) -> f64 {
+    vault
+        .collateral
+        .iter()
+        .map(|(token, amount)| {
+            usd_value(token, amount) * factor_bps(pool, token) as f64 / 10_000.0
+        })
+        .sum()
+}
+
+fn owed(vault: &Vault) -> BigUint {
+    &vault.debt.0 + &vault.fees.0
+}
+
+fn is_healthy(pool: &DeFiPool, vault: &Vault) -> bool {
+    owed(vault).to_f64().unwrap_or(f64::MAX) <= capacity(pool, vault)
+}
+
+/// Collateral value over debt plus fees
+fn collateral_ratio(vault: &Vault) -> f64 {
+    let collateral: f64 = vault.collateral.iter().map(|(t, a)| usd_value(t, a)).sum();
+    collateral / owed(vault).to_f64().unwrap_or(f64::MAX)
+}
+
+pub fn set_config(pool: &mut DeFiPool, config: VaultConfig, now: u64) -> Result<(), String> {
+    if config.stability_fee_bps > MAX_STABILITY_FEE_BPS {
+        return Err(format!("stability fee cannot exceed {} bps", MAX_STABILITY_FEE_BPS));
+    }
+    if let Some(f) = config.collateral_factors.iter().find(|f| f.factor_bps > 10_000) {
+        return Err(format!("collateral factor of {} exceeds 100%", f.token));
+    }
+    // Fees up to now are owed at the old rate
+    let fee_bps = pool.vault_config.stability_fee_bps;
+    for vault in pool.vaults.values_mut() {
+        accrue(vault, fee_bps, now);
+    }
+    pool.vault_config = config;
+    rates::record(pool, now);
+    Ok(())
+}
+
+pub fn add_collateral(pool: &mut DeFiPool, account: &str, token: &str, amount: &Nat, now: u64) {
+    let vault = pool
+        .vaults
+        .entry(account.to_string())
+        .or_insert_with(|| Vault { last_accrual: now, ..Default::default() });
+    let held = vault.collateral.entry(token.to_string()).or_default();
+    *held = Nat::from(&held.0 + &amount.0);
+}
+
+/// Take collateral out if the vault stays healthy; the caller sends it
+pub fn remove_collateral(
+    pool: &mut DeFiPool,
+    account: &str,
+    token: &str,
+    amount: &Nat,
+    now: u64,
+) -> Result<(), String> {
+    let fee_bps = pool.vault_config.stability_fee_bps;
+    let mut vault = pool.vaults.get(account).cloned().ok_or("no vault")?;
+    accrue(&mut vault, fee_bps, now);
+    let held = vault.collateral.get_mut(token).filter(|held| **held >= *amount);
+    let held = held.ok_or(format!("insufficient {} in vault", token))?;
+    *held = Nat::from(&held.0 - &amount.0);
+    if *held == 0u64 {
+        vault.collateral.remove(token);
+    }
+    if !is_healthy(pool, &vault) {
+        return Err("withdrawal would leave the vault undercollateralized".to_string());
+    }
+    pool.vaults.insert(account.to_string(), vault);
+    Ok(())
+}
+
+/// Record `amount` of new debt if the vault can carry it; the caller mints
+pub fn draw(pool: &mut DeFiPool, account: &str, amount: &Nat, now: u64) -> Result<(), String> {
+    if *amount == 0u64 {
+        return Err("amount must be positive".to_string());
+    }
+    let fee_bps = pool.vault_config.stability_fee_bps;
+    let mut vault = pool.vaults.get(account).cloned().ok_or("no vault")?;
+    accrue(&mut vault, fee_bps, now);
+    vault.debt = Nat::from(&vault.debt.0 + &amount.0);
+    if !is_healthy(pool, &vault) {
+        return Err("vault collateral does not cover this amount".to_string());
+    }
+    pool.vaults.insert(account.to_string(), vault);
+    Ok(())
+}
+
+/// Undo a `draw` whose mint failed
+pub fn undraw(pool: &mut DeFiPool, account: &str, amount: &Nat) {
+    if let Some(vault) = pool.vaults.get_mut(account) {
+        let undone = vault.debt.0.clone().min(amount.0.clone());
+        vault.debt = Nat::from(&vault.debt.0 - undone);
+    }
+}
+
+/// Apply a repayment, fees first; fees go to the stablecoin savings reserve.
+/// Returns the debt repaid, to be burned, and the part of `amount` that
+/// exceeded what was owed.
+pub fn repay(pool: &mut DeFiPool, account: &str, amount: &Nat, now: u64) -> (Nat, Nat) {
+    let fee_bps = pool.vault_config.stability_fee_bps;
+    let Some(vault) = pool.vaults.get_mut(account) else {
+        return (Nat::from(0u64), amount.clone());
+    };
+    accrue(vault, fee_bps, now);
+    let to_fees = vault.fees.0.clone().min(amount.0.clone());
+    let rest = &amount.0 - &to_fees;
+    let to_debt = vault.debt.0.clone().min(rest.clone());
+    vault.fees = Nat::from(&vault.fees.0 - &to_fees);
+    vault.debt = Nat::from(&vault.debt.0 - &to_debt);
+    let reserve = pool.savings_reserve.entry(STABLE_SYMBOL.to_string()).or_default();
+    *reserve = Nat::from(&reserve.0 + &to_fees);
+    let excess = Nat::from(rest - &to_debt);
+    let (to_fees, to_debt) = (Nat::from(to_fees), Nat::from(to_debt));
+    let protocol = ledger::PROTOCOL_ACCOUNT;
+    let operations = vec![
+        ledger::debit(account, BalanceKind::VaultDebt, STABLE_SYMBOL, &to_debt),
+        ledger::credit(protocol, BalanceKind::SavingsReserve, STABLE_SYMBOL, &to_fees),
+    ];
+    ledger::record(pool, TransactionKind::StableRepayment, operations, now);
+    (to_debt, excess)
+}
+
+/// Vault state with fees and health as of `now`
+pub fn view(pool: &DeFiPool, account: &str, now: u64) -> Option<VaultView> {
+    let mut vault = pool.vaults.get(account)?.clone();
+    accrue(&mut vault, pool.vault_config.stability_fee_bps, now);
+    let capacity = capacity(pool, &vault);
+    let owed = owed(&vault).to_f64().unwrap_or(f64::MAX);
+    let collateral: Vec<StableBalanceEntry> = vault
+        .collateral
+        .iter()
+        .map(|(token, value)| StableBalanceEntry { token: token.clone(), value: value.clone() })
+        .collect();
+    Some(VaultView {
+        collateral,
+        collateral_usd: vault.collateral.iter().map(|(t, a)| usd_value(t, a)).sum(),
+        capacity_usd: capacity,
+        debt: vault.debt,
+        fees: vault.fees,
+        health_factor_bps: (owed > 0.0).then(|| (capacity / owed * 10_000.0) as u64),
+    })
+}
+
+/// Factors in force for every supported token
+pub fn factors(pool: &DeFiPool) -> Vec<CollateralFactor> {
+    pool.supported_tokens
+        .iter()
+        .map(|token| CollateralFactor { token: token.clone(), factor_bps: factor_bps(pool, token) })
+        .collect()
+}
+
+/// Vaults with debt in the order redemptions reach them, lowest collateral
+/// ratio first. Vaults whose collateral no longer covers what they owe are
+/// left out: redeeming against them could not pay face value.
+pub fn redemption_order(pool: &DeFiPool, now: u64) -> Vec<String> {
+    let fee_bps = pool.vault_config.stability_fee_bps;
+    let mut ranked: Vec<(f64, &String)> = pool
+        .vaults
+        .iter()
+        .filter(|(_, vault)| vault.debt > 0u64)
+        .filter_map(|(account, vault)| {
+            let mut vault = vault.clone();
+            accrue(&mut vault, fee_bps, now);
+            let ratio = collateral_ratio(&vault);
+            (ratio >= 1.0).then_some((ratio, account))
+        })
+        .collect();
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+    ranked.into_iter().map(|(_, account)| account.clone()).collect()
+}
+
+/// Clear up to `amount` of debt along `redemption_order`, taking collateral
+/// worth the cleared debt minus the fee out of each vault. The caller already
+/// holds the stablecoin; it sends the collateral and returns what the
+/// vaults could not absorb.
+pub fn redeem(
+    pool: &mut DeFiPool,
+    redeemer: &str,
+    amount: &Nat,
+    now: u64,
+) -> Result<Redemption, String> {
+    if *amount == 0u64 {
+        return Err("amount must be positive".to_string());
+    }
+    let fee_bps = pool.vault_config.stability_fee_bps;
+    let mut remaining = amount.0.clone();
+    let mut redeemed = Vec::new();
+    let mut operations = Vec::new();
+    for account in redemption_order(pool, now) {
+        if remaining == BigUint::from(0u32) {
+            break;
+        }
+        let Some(vault) = pool.vaults.get_mut(&account) else { continue };
+        accrue(vault, fee_bps, now);
+        let cleared = vault.debt.0.clone().min(remaining.clone());
+        let share = (10_000 - REDEMPTION_FEE_BPS) as f64 / 10_000.0;
+        let mut payout_usd = cleared.to_f64().unwrap_or(0.0) * share;
+        let mut taken = Vec::new();
+        for (token, held) in vault.collateral.iter_mut() {
+            if payout_usd <= 0.0 {
+                break;
+            }
+            let units = BigUint::from((payout_usd / token_price(token)) as u128);
+            let units = units.min(held.0.clone());
+            if units == BigUint::from(0u32) {
+                continue;
+            }
+            let units = Nat::from(units);
+            payout_usd -= usd_value(token, &units);
+            *held = Nat::from(&held.0 - &units.0);
+            taken.push(StableBalanceEntry { token: token.clone(), value: units });
+        }
+        vault.collateral.retain(|_, held| *held != 0u64);
+        vault.debt = Nat::from(&vault.debt.0 - &cleared);
+        remaining -= &cleared;
+        let cleared = Nat::from(cleared);
+        operations.push(ledger::debit(&account, BalanceKind::VaultDebt, STABLE_SYMBOL, &cleared));
+        for StableBalanceEntry { token, value } in &taken {
+            operations.push(ledger::debit(&account, BalanceKind::VaultCollateral, token, value));
+        }
+        redeemed.push(RedeemedVault { account, debt_cleared: cleared, collateral: taken });
+    }
+    if redeemed.is_empty() {
+        return Err("no vault has debt to redeem against".to_string());
+    }
+    ledger::record(pool, TransactionKind::StableRedemption, operations, now);
+    let redemption = Redemption {
+        id: pool.redemptions.len() as u64,
+        redeemer: redeemer.to_string(),
+        amount: Nat::from(&amount.0 - remaining),
+        fee_bps: REDEMPTION_FEE_BPS,
+        vaults: redeemed,
+        timestamp: now,
+    };
+    pool.redemptions.push(redemption.clone());
+    Ok(redemption)
+}
+
+/// Redemptions `account` made or whose vault they reached, newest first
+pub fn redemptions_of(pool: &DeFiPool, account: &str) -> Vec<Redemption> {
+    pool.redemptions
+        .iter()
+        .rev()
+        .filter(|r| r.redeemer == account || r.vaults.iter().any(|v| v.account == account))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YEAR: u64 = SECS_PER_YEAR * NANOS_PER_SEC;
+
+    /// A vault of `collateral` ICP (one USD each, 75% factor) owing `debt`
+    fn vault(pool: &mut DeFiPool, account: &str, collateral: u64, debt: u64) {
+        add_collateral(pool, account, "ICP", &Nat::from(collateral), 0);
+        if debt > 0 {
+            draw(pool, account, &Nat::from(debt), 0).unwrap();
+        }
+    }
+
+    fn debt(pool: &DeFiPool, account: &str) -> Nat {
+        pool.vaults[account].debt.clone()
+    }
+
+    #[test]
+    fn draws_are_limited_by_capacity() {
+        let mut pool = DeFiPool::default();
+        vault(&mut pool, "alice", 1_000, 750);
+        assert!(draw(&mut pool, "alice", &Nat::from(1u64), 0).is_err());
+        assert!(remove_collateral(&mut pool, "alice", "ICP", &Nat::from(1u64), 0).is_err());
+        undraw(&mut pool, "alice", &Nat::from(750u64));
+        assert!(remove_collateral(&mut pool, "alice", "ICP", &Nat::from(1_000u64), 0).is_ok());
+        assert!(draw(&mut pool, "bob", &Nat::from(1u64), 0).is_err());
+    }
+
+    #[test]
+    fn repayments_cover_fees_first_and_fund_savings() {
+        let mut pool = DeFiPool::default();
+        let config = VaultConfig { stability_fee_bps: 1_000, collateral_factors: vec![] };
+        set_config(&mut pool, config, 0).unwrap();
+        vault(&mut pool, "alice", 1_000, 500);
+        assert_eq!(view(&pool, "alice", YEAR).unwrap().fees, Nat::from(50u64));
+
+        let repaid = repay(&mut pool, "alice", &Nat::from(100u64), YEAR);
+        assert_eq!(repaid, (Nat::from(50u64), Nat::from(0u64)));
+        assert_eq!(debt(&pool, "alice"), Nat::from(450u64));
+        assert_eq!(pool.savings_reserve[STABLE_SYMBOL], Nat::from(50u64));
+        // Paying more than is owed returns the excess
+        let repaid = repay(&mut pool, "alice", &Nat::from(500u64), YEAR);
+        assert_eq!(repaid, (Nat::from(450u64), Nat::from(50u64)));
+        assert_eq!(debt(&pool, "alice"), Nat::from(0u64));
+    }
+
+    #[test]
+    fn redemptions_hit_the_lowest_collateral_ratio_first() {
+        let mut pool = DeFiPool::default();
+        vault(&mut pool, "alice", 1_000, 700);
+        vault(&mut pool, "bob", 2_000, 500);
+        vault(&mut pool, "carol", 1_000, 0);
+        assert_eq!(redemption_order(&pool, 0), vec!["alice".to_string(), "bob".to_string()]);
+
+        let redemption = redeem(&mut pool, "dave", &Nat::from(800u64), 1).unwrap();
+        assert_eq!(redemption.amount, Nat::from(800u64));
+        let [alice, bob] = &redemption.vaults[..] else { panic!("expected two vaults") };
+        assert_eq!((alice.account.as_str(), bob.account.as_str()), ("alice", "bob"));
+        assert_eq!(alice.debt_cleared, Nat::from(700u64));
+        assert_eq!(bob.debt_cleared, Nat::from(100u64));
+        // Face value minus the 0.5% fee, rounded down to whole units
+        assert_eq!(alice.collateral[0].value, Nat::from(696u64));
+        assert_eq!(bob.collateral[0].value, Nat::from(99u64));
+        assert_eq!(pool.vaults["alice"].collateral["ICP"], Nat::from(304u64));
+        assert_eq!(debt(&pool, "bob"), Nat::from(400u64));
+
+        assert_eq!(redemptions_of(&pool, "bob").len(), 1);
+        assert_eq!(redemptions_of(&pool, "dave").len(), 1);
+        assert!(redemptions_of(&pool, "carol").is_empty());
+    }
+
+    #[test]
+    fn redemption_is_capped_by_redeemable_debt() {
+        let mut pool = DeFiPool::default();
+        assert!(redeem(&mut pool, "dave", &Nat::from(10u64), 0).is_err());
+        vault(&mut pool, "alice", 1_000, 100);
+        // Collateral no longer covers the debt: not redeemable at face value
+        vault(&mut pool, "bob", 100, 0);
+        pool.vaults.get_mut("bob").unwrap().debt = Nat::from(200u64);
+        assert_eq!(redemption_order(&pool, 0), vec!["alice".to_string()]);
+
+        let redemption = redeem(&mut pool, "dave", &Nat::from(500u64), 0).unwrap();
+        assert_eq!(redemption.amount, Nat::from(100u64));
+        assert_eq!(debt(&pool, "bob"), Nat::from(200u64));
+    }
+}
@@ -133,4 +133,16 @@ fn mint(to: Principal, amount: Nat) -> bool {
     true
 }
 
-
+/// Destroy `amount` of the caller's own tokens
+#[update]
+fn burn(amount: Nat) -> bool {
+    let caller = ic_cdk::caller();
+    let mut token = TOKEN.lock().unwrap();
+    let balance = token.balances.get(&caller).cloned().unwrap_or(Nat::from(0u64));
+    if balance.0 < amount.0 {
+        return false;
+    }
+    token.balances.insert(caller, Nat::from(&balance.0 - &amount.0));
+    token.total_supply = Nat::from(&token.total_supply.0 - &amount.0);
+    true
+}
@@ -0,0 +1,287 @@
+// src/pool_registry/lib.rs
+//! Shard registry. Users are spread over several pool canisters so no single
+//! canister has to hold every account; this canister maps each principal to
+//! its shard and rolls the shards' totals up into protocol-wide figures.
+//!
+//! A user calls `assign_shard` once; the registry picks the least loaded
+//! shard that is accepting users and admits the principal there. Frontends
+//! then look the shard up with `get_shard`.
+//!
+//! Shards, assignments and the governance canister are candid-encoded into
+//! stable memory across upgrades; shard reports are rebuilt by the next
+//! roll-up.
+
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_cdk::call;
+use ic_cdk::stable::{stable_grow, stable_read, stable_size, stable_write, WASM_PAGE_SIZE_IN_BYTES};
+use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
+use num_bigint::BigUint;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+mod types;
+use types::{GlobalStats, ShardInfo, ShardStats, StableBalanceEntry};
+
+/// How often shard totals are rolled up
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Registry state
+#[derive(Default)]
+pub struct Registry {
+    pub shards: BTreeMap<Principal, ShardInfo>,
+    pub assignments: HashMap<Principal, Principal>, // user -> shard
+    pub shard_stats: HashMap<Principal, ShardStats>, // shard -> last report
+    pub global: GlobalStats,
+}
+
+static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(|| Mutex::new(Registry::default()));
+/// DAO governance canister; once set it is the only admin
+static GOVERNANCE_CANISTER: Lazy<Mutex<Option<Principal>>> = Lazy::new(|| Mutex::new(None));
+
+#[init]
+fn init() {
+    start_timers();
+}
+
+/// State carried across upgrades
+#[derive(CandidType, Serialize, Deserialize)]
+struct UpgradeState {
+    shards: Vec<ShardInfo>,
+    assignments: Vec<(Principal, Principal)>,
+    governance_canister: Option<Principal>,
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    let state = {
+        let registry = REGISTRY.lock().unwrap();
+        UpgradeState {
+            shards: registry.shards.values().cloned().collect(),
+            assignments: registry.assignments.iter().map(|(u, s)| (*u, *s)).collect(),
+            governance_canister: *GOVERNANCE_CANISTER.lock().unwrap(),
+        }
+    };
+    let bytes = Encode!(&state).expect("failed to encode registry state");
+    let pages = (bytes.len() as u64 + 8).div_ceil(WASM_PAGE_SIZE_IN_BYTES);
+    let current = stable_size();
+    if pages > current {
+        stable_grow(pages - current).expect("failed to grow stable memory");
+    }
+    stable_write(0, &(bytes.len() as u64).to_le_bytes());
+    stable_write(8, &bytes);
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    if stable_size() > 0 {
+        let mut len = [0u8; 8];
+        stable_read(0, &mut len);
+        let mut bytes = vec![0u8; u64::from_le_bytes(len) as usize];
+        if !bytes.is_empty() {
+            stable_read(8, &mut bytes);
+            let state = Decode!(&bytes, UpgradeState).expect("failed to decode registry state");
+            let mut registry = REGISTRY.lock().unwrap();
+            registry.shards = state.shards.into_iter().map(|s| (s.canister_id, s)).collect();
+            registry.assignments = state.assignments.into_iter().collect();
+            *GOVERNANCE_CANISTER.lock().unwrap() = state.governance_canister;
+        }
+    }
+    start_timers();
+}
+
+fn start_timers() {
+    ic_cdk_timers::set_timer_interval(ROLLUP_INTERVAL, || ic_cdk::futures::spawn(rollup()));
+}
+
+/// Guard: the governance canister once set, canister controllers until then
+fn caller_is_controller() -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    match *GOVERNANCE_CANISTER.lock().unwrap() {
+        Some(governance) if caller == governance => Ok(()),
+        Some(_) => Err("admin calls must come from the governance canister".to_string()),
+        None if ic_cdk::api::is_controller(&caller) => Ok(()),
+        None => Err("caller is not a controller".to_string()),
+    }
+}
+
+/// Hand admin rights to a DAO governance canister; can only be done once
+#[update(guard = "caller_is_controller")]
+fn set_governance_canister(principal: Principal) -> Result<(), String> {
+    if principal == Principal::anonymous() {
+        return Err("governance canister cannot be anonymous".to_string());
+    }
+    let mut governance = GOVERNANCE_CANISTER.lock().unwrap();
+    if governance.is_some() {
+        return Err("governance canister already set".to_string());
+    }
+    *governance = Some(principal);
+    Ok(())
+}
+
+#[query]
+fn get_governance_canister() -> Option<Principal> {
+    *GOVERNANCE_CANISTER.lock().unwrap()
+}
+
+// ---------------- SHARDS (admin) ----------------
+
+/// Register a pool canister as a shard; it must have this registry set
+#[update(guard = "caller_is_controller")]
+fn add_shard(canister_id: Principal, capacity: u64) -> Result<(), String> {
+    let mut registry = REGISTRY.lock().unwrap();
+    if registry.shards.contains_key(&canister_id) {
+        return Err("shard already registered".to_string());
+    }
+    if capacity == 0 {
+        return Err("capacity must be positive".to_string());
+    }
+    registry.shards.insert(
+        canister_id,
+        ShardInfo {
+            canister_id,
+            user_count: 0,
+            capacity,
+            accepting: true,
+            added_at: ic_cdk::api::time(),
+        },
+    );
+    Ok(())
+}
+
+/// Change a shard's capacity or stop new assignments to it
+#[update(guard = "caller_is_controller")]
+fn update_shard(canister_id: Principal, capacity: u64, accepting: bool) -> Result<(), String> {
+    let mut registry = REGISTRY.lock().unwrap();
+    let shard = registry.shards.get_mut(&canister_id).ok_or("shard not found")?;
+    shard.capacity = capacity;
+    shard.accepting = accepting;
+    Ok(())
+}
+
+#[query]
+fn list_shards() -> Vec<ShardInfo> {
+    REGISTRY.lock().unwrap().shards.values().cloned().collect()
+}
+
+// ---------------- ASSIGNMENT ----------------
+
+/// The caller's shard, assigning the least loaded open shard on first call
+#[update]
+async fn assign_shard() -> Result<Principal, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("anonymous callers cannot be assigned".to_string());
+    }
+    let shard = {
+        let mut registry = REGISTRY.lock().unwrap();
+        if let Some(shard) = registry.assignments.get(&caller) {
+            return Ok(*shard);
+        }
+        let shard = registry
+            .shards
+            .values()
+            .filter(|s| s.accepting && s.user_count < s.capacity)
+            .min_by_key(|s| s.user_count)
+            .map(|s| s.canister_id)
+            .ok_or("no shard is accepting users")?;
+        // Reserve the slot before the await so concurrent calls spread out
+        registry.assignments.insert(caller, shard);
+        registry.shards.get_mut(&shard).unwrap().user_count += 1;
+        shard
+    };
+
+    let admitted: Result<(bool,), _> = call(shard, "admit_user", (caller,)).await;
+    if !matches!(admitted, Ok((true,))) {
+        let mut registry = REGISTRY.lock().unwrap();
+        registry.assignments.remove(&caller);
+        if let Some(info) = registry.shards.get_mut(&shard) {
+            info.user_count -= 1;
+        }
+        return Err("shard did not admit the user, try again".to_string());
+    }
+    Ok(shard)
+}
+
+#[query]
+fn get_shard(user: Principal) -> Option<Principal> {
+    REGISTRY.lock().unwrap().assignments.get(&user).cloned()
+}
+
+// ---------------- ROLL-UPS ----------------
+
+fn add_entries(totals: &mut BTreeMap<String, BigUint>, entries: &[StableBalanceEntry]) {
+    for entry in entries {
+        *totals.entry(entry.token.clone()).or_default() += &entry.value.0;
+    }
+}
+
+fn to_entries(totals: BTreeMap<String, BigUint>) -> Vec<StableBalanceEntry> {
+    totals
+        .into_iter()
+        .map(|(token, value)| StableBalanceEntry { token, value: Nat::from(value) })
+        .collect()
+}
+
+/// Collect every shard's totals and recompute the global aggregates; a shard
+/// that does not answer keeps its previous report
+async fn rollup() {
+    let shards: Vec<Principal> = REGISTRY.lock().unwrap().shards.keys().cloned().collect();
+    let mut reporting = 0;
+    for shard in &shards {
+        let res: Result<(ShardStats,), _> = call(*shard, "get_shard_stats", ()).await;
+        match res {
+            Ok((stats,)) => {
+                reporting += 1;
+                REGISTRY.lock().unwrap().shard_stats.insert(*shard, stats);
+            }
+            Err(err) => ic_cdk::print(format!("Roll-up: shard {} unreachable: {:?}", shard, err)),
+        }
+    }
+
+    let mut registry = REGISTRY.lock().unwrap();
+    let (mut deposits, mut collateral) = (BTreeMap::new(), BTreeMap::new());
+    let mut global = GlobalStats {
+        shard_count: shards.len() as u64,
+        shards_reporting: reporting,
+        updated_at: ic_cdk::api::time(),
+        ..Default::default()
+    };
+    let (mut borrowed, mut supply) = (BigUint::from(0u32), BigUint::from(0u32));
+    for stats in registry.shard_stats.values() {
+        global.user_count += stats.user_count;
+        global.tvl_usd += stats.tvl_usd;
+        borrowed += &stats.total_borrowed.0;
+        supply += &stats.stable_supply.0;
+        add_entries(&mut deposits, &stats.deposits);
+        add_entries(&mut collateral, &stats.collateral);
+    }
+    global.total_borrowed = Nat::from(borrowed);
+    global.stable_supply = Nat::from(supply);
+    global.deposits = to_entries(deposits);
+    global.collateral = to_entries(collateral);
+    registry.global = global;
+}
+
+/// Run the roll-up now instead of waiting for the timer
+#[update(guard = "caller_is_controller")]
+async fn rollup_now() {
+    rollup().await;
+}
+
+#[query]
+fn get_global_stats() -> GlobalStats {
+    REGISTRY.lock().unwrap().global.clone()
+}
+
+#[query]
+fn get_shard_report(shard: Principal) -> Option<ShardStats> {
+    REGISTRY.lock().unwrap().shard_stats.get(&shard).cloned()
+}
+
+#[query]
+fn version() -> String {
+    "Pool Registry v1.0.0".to_string()
+}
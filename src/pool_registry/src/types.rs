@@ -0,0 +1,50 @@
+// src/pool_registry/types.rs
+use candid::{CandidType, Nat, Principal};
+use serde::{Deserialize, Serialize};
+
+/// A pool canister holding a slice of the users
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ShardInfo {
+    pub canister_id: Principal,
+    /// Users assigned by the registry
+    pub user_count: u64,
+    /// Assignments stop once `user_count` reaches this
+    pub capacity: u64,
+    /// Cleared to drain a shard; existing users stay
+    pub accepting: bool,
+    pub added_at: u64,
+}
+
+/// Per-token total reported by a shard
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct StableBalanceEntry {
+    pub token: String,
+    pub value: Nat,
+}
+
+/// Totals a shard reports to the roll-up (mirrors the pool's `get_shard_stats`)
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ShardStats {
+    pub user_count: u64,
+    pub deposits: Vec<StableBalanceEntry>,
+    pub collateral: Vec<StableBalanceEntry>,
+    pub total_borrowed: Nat,
+    pub stable_supply: Nat,
+    pub tvl_usd: f64,
+    pub timestamp: u64,
+}
+
+/// Aggregates across every shard as of the last roll-up
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GlobalStats {
+    pub shard_count: u64,
+    /// Shards that answered the last roll-up
+    pub shards_reporting: u64,
+    pub user_count: u64,
+    pub deposits: Vec<StableBalanceEntry>,
+    pub collateral: Vec<StableBalanceEntry>,
+    pub total_borrowed: Nat,
+    pub stable_supply: Nat,
+    pub tvl_usd: f64,
+    pub updated_at: u64,
+}